@@ -0,0 +1,139 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt, Member};
+
+/// Implements renderer::Vertex for a #[repr(C)] struct, every field is an attribute at the next free location
+/// with the format of its type, #[vertex(location = 3)] sets the location, #[vertex(format = UNormByte4)] sets
+/// the format for types with more than one possible format and #[vertex(skip)] ignores padding
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+// The options that can be set on a field
+#[derive(Default)]
+struct FieldOptions {
+    location: Option<u32>,
+    format: Option<Ident>,
+    skip: bool,
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("vertex"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("location") {
+                let location: LitInt = meta.value()?.parse()?;
+                options.location = Some(location.base10_parse()?);
+            } else if meta.path.is_ident("format") {
+                options.format = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error("expected location, format or skip"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+// Offsets only describe the layout seen by the GPU if the fields are kept in order
+fn is_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                // Skips the value of align(N) and packed(N)
+                let _content;
+                syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "Vertex can only be derived for structs",
+            ))
+        }
+    };
+    if !is_repr_c(&input)? {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "Vertex can only be derived for #[repr(C)] structs",
+        ));
+    }
+    let fields: Vec<&syn::Field> = match fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let mut attributes = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let options = field_options(field)?;
+        if options.skip {
+            continue;
+        }
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(syn::Index {
+                index: index as u32,
+                span: field.span(),
+            }),
+        };
+        let ty = &field.ty;
+        let format = match options.format {
+            Some(format) => quote!(::renderer::AttributeFormat::#format),
+            None => quote!(<#ty as ::renderer::VertexAttributeType>::FORMAT),
+        };
+        let set_location = options
+            .location
+            .map(|location| quote!(location = #location;));
+        attributes.push(quote! {
+            #set_location
+            let format = #format;
+            attributes.push(::renderer::VertexAttribute {
+                location,
+                format,
+                offset: ::core::mem::offset_of!(Self, #member) as u32,
+            });
+            location += format.locations();
+        });
+    }
+    let count = attributes.len();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::renderer::Vertex for #name #ty_generics #where_clause {
+            #[allow(unused_assignments, unused_mut)]
+            fn attributes() -> ::std::vec::Vec<::renderer::VertexAttribute> {
+                let mut attributes = ::std::vec::Vec::with_capacity(#count);
+                let mut location: u32 = 0;
+                #(#attributes)*
+                attributes
+            }
+        }
+    })
+}
@@ -0,0 +1,116 @@
+use crate::error::{Error, ErrorKind};
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+/// A buffer along with the memory backing it
+pub struct Buffer {
+    handle: vk::Buffer,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    memory_properties: vk::MemoryPropertyFlags,
+}
+
+impl Buffer {
+    pub fn new(
+        device: &VulkanDevice,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        memory_properties: vk::MemoryPropertyFlags,
+    ) -> Result<Buffer, Error> {
+        let create_info = vk::BufferCreateInfoBuilder::new()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let handle = unsafe { device.handle().create_buffer(&create_info, None, None) }.result()?;
+        let requirements = unsafe { device.handle().get_buffer_memory_requirements(handle, None) };
+        let memory = match device.allocate_memory(&requirements, memory_properties) {
+            Ok(memory) => memory,
+            Err(error) => {
+                unsafe { device.handle().destroy_buffer(Some(handle), None) };
+                return Err(error);
+            }
+        };
+        if let Err(error) =
+            unsafe { device.handle().bind_buffer_memory(handle, memory, 0) }.result()
+        {
+            unsafe {
+                device.handle().destroy_buffer(Some(handle), None);
+                device.handle().free_memory(Some(memory), None);
+            }
+            return Err(Error::from(error));
+        }
+        Ok(Buffer {
+            handle,
+            memory,
+            size,
+            usage,
+            memory_properties,
+        })
+    }
+
+    pub fn handle(&self) -> vk::Buffer {
+        self.handle
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    pub fn usage(&self) -> vk::BufferUsageFlags {
+        self.usage
+    }
+
+    /// Copies data into the buffer starting at offset, the buffer must be host visible
+    pub fn write(
+        &self,
+        device: &VulkanDevice,
+        offset: vk::DeviceSize,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if !self
+            .memory_properties
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE)
+        {
+            return Err(Error::new(ErrorKind::InvalidBufferAccess, None)
+                .with_context(&"Only buffers in host visible memory can be written to directly"));
+        }
+        if offset + data.len() as vk::DeviceSize > self.size {
+            return Err(Error::new(ErrorKind::InvalidBufferAccess, None)
+                .with_context(&"The data being written does not fit in the buffer"));
+        }
+        let mut mapped = std::ptr::null_mut();
+        unsafe {
+            device
+                .handle()
+                .map_memory(self.memory, 0, vk::WHOLE_SIZE, None, &mut mapped)
+                .result()?;
+            // SAFE: The size of the buffer was checked above and mapped memory can't overlap data
+            let destination = (mapped as *mut u8).add(offset as usize);
+            std::ptr::copy_nonoverlapping(data.as_ptr(), destination, data.len());
+            if !self
+                .memory_properties
+                .contains(vk::MemoryPropertyFlags::HOST_COHERENT)
+            {
+                let range = vk::MappedMemoryRangeBuilder::new()
+                    .memory(self.memory)
+                    .offset(0)
+                    .size(vk::WHOLE_SIZE);
+                device
+                    .handle()
+                    .flush_mapped_memory_ranges(&[range])
+                    .result()?;
+            }
+            device.handle().unmap_memory(self.memory);
+        }
+        Ok(())
+    }
+
+    /// Destroys the buffer and frees its memory, the buffer must no longer be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe {
+            device.handle().destroy_buffer(Some(self.handle), None);
+            device.handle().free_memory(Some(self.memory), None);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+mod pool;
+mod recorder;
+
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+/// A pool that command buffers for a single queue family are allocated from
+pub struct CommandPool {
+    handle: vk::CommandPool,
+    family_index: u32,
+}
+
+/// Records commands into a command buffer that is in the recording state
+pub struct CommandRecorder<'a> {
+    device: &'a VulkanDevice,
+    command_buffer: vk::CommandBuffer,
+    // Dynamic viewports and scissors cover this when a pipeline is bound
+    render_extent: Option<vk::Extent2D>,
+}
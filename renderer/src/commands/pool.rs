@@ -0,0 +1,72 @@
+use super::CommandPool;
+use crate::error::Error;
+use crate::{OperationQueue, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl CommandPool {
+    pub fn new(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        flags: vk::CommandPoolCreateFlags,
+    ) -> Result<CommandPool, Error> {
+        let create_info = vk::CommandPoolCreateInfoBuilder::new()
+            .flags(flags)
+            .queue_family_index(queue.family_index());
+        let handle = unsafe {
+            device
+                .handle()
+                .create_command_pool(&create_info, None, None)
+        }
+        .result()?;
+        Ok(CommandPool {
+            handle,
+            family_index: queue.family_index(),
+        })
+    }
+
+    pub fn handle(&self) -> vk::CommandPool {
+        self.handle
+    }
+
+    /// The queue family that command buffers from this pool can be submitted to
+    pub fn family_index(&self) -> u32 {
+        self.family_index
+    }
+
+    /// Allocates primary command buffers from the pool
+    pub fn allocate(
+        &self,
+        device: &VulkanDevice,
+        count: u32,
+    ) -> Result<Vec<vk::CommandBuffer>, Error> {
+        let allocate_info = vk::CommandBufferAllocateInfoBuilder::new()
+            .command_pool(self.handle)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(count);
+        let command_buffers =
+            unsafe { device.handle().allocate_command_buffers(&allocate_info) }.result()?;
+        Ok(command_buffers)
+    }
+
+    pub fn free(&self, device: &VulkanDevice, command_buffers: &[vk::CommandBuffer]) {
+        unsafe {
+            device
+                .handle()
+                .free_command_buffers(self.handle, command_buffers)
+        };
+    }
+
+    /// Returns every command buffer allocated from this pool to the initial state
+    pub fn reset(&self, device: &VulkanDevice) -> Result<(), Error> {
+        unsafe { device.handle().reset_command_pool(self.handle, None) }.result()?;
+        Ok(())
+    }
+
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe {
+            device
+                .handle()
+                .destroy_command_pool(Some(self.handle), None)
+        };
+    }
+}
@@ -0,0 +1,341 @@
+use super::CommandRecorder;
+use crate::error::{Error, ErrorKind};
+use crate::{
+    Buffer, ComputePipeline, DeviceExtensions, Framebuffer, GraphicsPipeline, Image,
+    PipelineLayout, PushConstants, RenderingAttachment, Renderpass, VulkanDevice,
+};
+use erupt::vk1_0 as vk;
+
+impl<'a> CommandRecorder<'a> {
+    /// Wraps a command buffer that has already begun recording
+    pub fn new(device: &'a VulkanDevice, command_buffer: vk::CommandBuffer) -> CommandRecorder<'a> {
+        CommandRecorder {
+            device,
+            command_buffer,
+            render_extent: None,
+        }
+    }
+
+    pub fn command_buffer(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    pub fn device(&self) -> &VulkanDevice {
+        self.device
+    }
+
+    /// The extent being rendered to, usually the extent of the swapchain this frame. Pipelines bound afterwards
+    /// with a dynamic viewport or scissor have every viewport or scissor set to cover the whole extent
+    pub fn set_render_extent(&mut self, extent: vk::Extent2D) {
+        self.render_extent = Some(extent);
+    }
+
+    /// Begins the renderpass rendering to the whole framebuffer, which becomes the render extent. There must be a
+    /// clear value for each attachment up to the last one that is cleared
+    pub fn begin_renderpass(
+        &mut self,
+        renderpass: &Renderpass,
+        framebuffer: &Framebuffer,
+        clear_values: &[vk::ClearValue],
+    ) {
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: framebuffer.extent(),
+        };
+        let begin_info = vk::RenderPassBeginInfoBuilder::new()
+            .render_pass(renderpass.handle())
+            .framebuffer(framebuffer.handle())
+            .render_area(render_area)
+            .clear_values(clear_values);
+        unsafe {
+            self.device.handle().cmd_begin_render_pass(
+                self.command_buffer,
+                &begin_info,
+                vk::SubpassContents::INLINE,
+            )
+        };
+        self.render_extent = Some(framebuffer.extent());
+    }
+
+    /// Begins rendering to the attachments without a renderpass, the extent becomes the render extent. The
+    /// attachments must already be in their layouts, fails if dynamic rendering was not enabled on the device
+    pub fn begin_rendering(
+        &mut self,
+        extent: vk::Extent2D,
+        color_attachments: &[RenderingAttachment],
+        depth_attachment: Option<&RenderingAttachment>,
+    ) -> Result<(), Error> {
+        let dynamic_rendering = self.device.dynamic_rendering.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::DeviceExtensionsNotFound(vec![DeviceExtensions::DynamicRendering]),
+                None,
+            )
+        })?;
+        dynamic_rendering.begin_rendering(
+            self.command_buffer,
+            extent,
+            color_attachments,
+            depth_attachment,
+        );
+        self.render_extent = Some(extent);
+        Ok(())
+    }
+
+    pub fn end_rendering(&mut self) {
+        if let Some(dynamic_rendering) = &self.device.dynamic_rendering {
+            dynamic_rendering.end_rendering(self.command_buffer);
+        }
+    }
+
+    /// Moves to the next subpass of the renderpass
+    pub fn next_subpass(&mut self) {
+        unsafe {
+            self.device
+                .handle()
+                .cmd_next_subpass(self.command_buffer, vk::SubpassContents::INLINE)
+        };
+    }
+
+    pub fn end_renderpass(&mut self) {
+        unsafe {
+            self.device
+                .handle()
+                .cmd_end_render_pass(self.command_buffer)
+        };
+    }
+
+    /// Binds the pipeline, its dynamic viewports and scissors are set from the render extent if there is one,
+    /// set them afterwards to draw to part of the extent
+    pub fn bind_pipeline(&mut self, pipeline: &GraphicsPipeline) {
+        unsafe {
+            self.device.handle().cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.handle(),
+            )
+        };
+        if let Some(extent) = self.render_extent {
+            let count = pipeline.viewport_count() as usize;
+            if pipeline.is_dynamic(vk::DynamicState::VIEWPORT) {
+                let viewport = vk::ViewportBuilder::new()
+                    .width(extent.width as f32)
+                    .height(extent.height as f32)
+                    .min_depth(0.0)
+                    .max_depth(1.0);
+                self.set_viewports(0, &vec![viewport; count]);
+            }
+            if pipeline.is_dynamic(vk::DynamicState::SCISSOR) {
+                let scissor = vk::Rect2DBuilder::new().extent(extent);
+                self.set_scissors(0, &vec![scissor; count]);
+            }
+        }
+    }
+
+    /// Sets dynamic viewports starting at the first index
+    pub fn set_viewports(&mut self, first_viewport: u32, viewports: &[vk::ViewportBuilder]) {
+        unsafe {
+            self.device
+                .handle()
+                .cmd_set_viewport(self.command_buffer, first_viewport, viewports)
+        };
+    }
+
+    /// Sets dynamic scissors starting at the first index
+    pub fn set_scissors(&mut self, first_scissor: u32, scissors: &[vk::Rect2DBuilder]) {
+        unsafe {
+            self.device
+                .handle()
+                .cmd_set_scissor(self.command_buffer, first_scissor, scissors)
+        };
+    }
+
+    /// Binds a descriptor set to the given set number of a graphics pipeline layout
+    pub fn bind_descriptor_set(
+        &mut self,
+        layout: &PipelineLayout,
+        set_number: u32,
+        set: vk::DescriptorSet,
+    ) {
+        unsafe {
+            self.device.handle().cmd_bind_descriptor_sets(
+                self.command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                layout.handle(),
+                set_number,
+                &[set],
+                &[],
+            )
+        };
+    }
+
+    pub fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        unsafe {
+            self.device.handle().cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.handle(),
+            )
+        };
+    }
+
+    /// Binds a descriptor set to the given set number of a compute pipeline layout
+    pub fn bind_compute_descriptor_set(
+        &mut self,
+        layout: &PipelineLayout,
+        set_number: u32,
+        set: vk::DescriptorSet,
+    ) {
+        unsafe {
+            self.device.handle().cmd_bind_descriptor_sets(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                layout.handle(),
+                set_number,
+                &[set],
+                &[],
+            )
+        };
+    }
+
+    /// Runs the bound compute pipeline for the given number of workgroups in each dimension
+    pub fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
+        unsafe {
+            self.device.handle().cmd_dispatch(
+                self.command_buffer,
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            )
+        };
+    }
+
+    /// Dispatches enough workgroups of the given size to cover every pixel of a width by height image
+    pub fn dispatch_for_extent(&mut self, width: u32, height: u32, local_size: (u32, u32)) {
+        let (local_x, local_y) = local_size;
+        self.dispatch(width.div_ceil(local_x), height.div_ceil(local_y), 1);
+    }
+
+    /// Pushes a value to the stages that read it, the layout must have been created with the push constants of T
+    pub fn push_constants<T: PushConstants>(
+        &mut self,
+        layout: &PipelineLayout,
+        value: &T,
+    ) -> Result<(), Error> {
+        if !layout.has_push_constants::<T>() {
+            return Err(Error::new(ErrorKind::InvalidPipelineConfig, None)
+                .with_context(&"The pipeline layout has no push constant range for the value"));
+        }
+        unsafe {
+            self.device.handle().cmd_push_constants(
+                self.command_buffer,
+                layout.handle(),
+                T::STAGES,
+                T::OFFSET,
+                T::SIZE,
+                value as *const T as *const std::ffi::c_void,
+            )
+        };
+        Ok(())
+    }
+
+    /// Transitions every subresource of the image into the new layout
+    pub fn transition_image(&mut self, image: &mut Image, new_layout: vk::ImageLayout) {
+        image.transition_layout(self.device, self.command_buffer, new_layout);
+    }
+
+    /// Copies tightly packed pixels from a buffer into the first mip level and array layer of the image,
+    /// the image must be in the TRANSFER_DST_OPTIMAL layout
+    pub fn copy_buffer_to_image(&mut self, buffer: &Buffer, buffer_offset: u64, image: &Image) {
+        self.copy_buffer_to_mip_level(buffer, buffer_offset, image, 0);
+    }
+
+    /// Copies tightly packed pixels from a buffer into a mip level of the first array layer,
+    /// the mip level must be in the TRANSFER_DST_OPTIMAL layout
+    pub fn copy_buffer_to_mip_level(
+        &mut self,
+        buffer: &Buffer,
+        buffer_offset: u64,
+        image: &Image,
+        mip_level: u32,
+    ) {
+        let extent = image.extent();
+        let region = vk::BufferImageCopyBuilder::new()
+            .buffer_offset(buffer_offset)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: image.aspect(),
+                mip_level,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vk::Extent3D {
+                width: (extent.width >> mip_level).max(1),
+                height: (extent.height >> mip_level).max(1),
+                depth: 1,
+            });
+        unsafe {
+            self.device.handle().cmd_copy_buffer_to_image(
+                self.command_buffer,
+                buffer.handle(),
+                image.handle(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[region],
+            )
+        };
+    }
+
+    /// Fills every mip level after the first by blitting each level from the one above it with a linear filter,
+    /// the image needs TRANSFER_SRC and TRANSFER_DST usage and its format must support linear filtering.
+    /// Blits decode SRGB formats before filtering so those images are downsampled in linear light
+    pub fn blit_mip_chain(&mut self, image: &mut Image) {
+        let extent = image.extent();
+        let aspect_mask = image.aspect();
+        let layers = 0..image.array_layers();
+        for level in 1..image.mip_levels() {
+            image.transition_subresources(
+                self.device,
+                self.command_buffer,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                level - 1..level,
+                layers.clone(),
+            );
+            image.transition_subresources(
+                self.device,
+                self.command_buffer,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                level..level + 1,
+                layers.clone(),
+            );
+            let level_offset = |level: u32| vk::Offset3D {
+                x: (extent.width >> level).max(1) as i32,
+                y: (extent.height >> level).max(1) as i32,
+                z: 1,
+            };
+            let subresource = |mip_level: u32| vk::ImageSubresourceLayers {
+                aspect_mask,
+                mip_level,
+                base_array_layer: 0,
+                layer_count: image.array_layers(),
+            };
+            let origin = vk::Offset3D { x: 0, y: 0, z: 0 };
+            let blit = vk::ImageBlitBuilder::new()
+                .src_subresource(subresource(level - 1))
+                .src_offsets([origin, level_offset(level - 1)])
+                .dst_subresource(subresource(level))
+                .dst_offsets([origin, level_offset(level)]);
+            unsafe {
+                self.device.handle().cmd_blit_image(
+                    self.command_buffer,
+                    image.handle(),
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image.handle(),
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[blit],
+                    vk::Filter::LINEAR,
+                )
+            };
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use erupt::vk1_0 as vk;
 use std::collections::HashMap;
 use std::ffi::CStr;
+use std::path::PathBuf;
 // use super::Gpu;
 use super::{
     ConfigureDevice, DeviceExtensions, ExtensionManager, Feature, Features, PciVendor, QueueFamily,
     QueueManager,
 };
 use crate::error::{Error, ErrorKind};
-use crate::{SelectedDevice, Version, VulkanDevice};
+use crate::pipeline::{PipelineCache, PipelineCacheKey};
+use crate::rendering::PhysicalDeviceDynamicRenderingFeatures;
+use crate::{SelectedDevice, Version, Vulkan12Features, VulkanDevice};
 
 // Notes from Nvidia: Don’t overlap compute work on the graphics queue with compute work on a
 // dedicated asynchronous compute queue. This may lead to gaps in execution of the
@@ -22,6 +25,7 @@ impl<'a> ConfigureDevice<'a> {
             api_version,
             device_handle,
             device_features,
+            vulkan12_features,
             queue_families,
             driver_version,
             vendor_id,
@@ -29,6 +33,7 @@ impl<'a> ConfigureDevice<'a> {
             device_name,
             device_type,
             available_extensions,
+            limits,
         } = selected_device;
 
         ConfigureDevice {
@@ -47,20 +52,28 @@ impl<'a> ConfigureDevice<'a> {
             extensions_to_load: HashMap::new(),
             device_features,
             enabled_features: vk::PhysicalDeviceFeatures::default(),
+            vulkan12_features,
+            // The limits are kept so the device knows how large a bindless table can be
+            enabled_vulkan12_features: Vulkan12Features {
+                max_update_after_bind_sampled_images: vulkan12_features
+                    .max_update_after_bind_sampled_images,
+                max_update_after_bind_samplers: vulkan12_features.max_update_after_bind_samplers,
+                ..Default::default()
+            },
             queues_to_create: Vec::new(),
+            limits,
+            pipeline_cache_directory: None,
         }
     }
 
     // Retrieve a handle to a feature to either check its availability or enable that feature
     pub fn feature(&mut self, feature: &Features) -> Feature {
-        match feature {
-            Features::GeometryShader => Feature::new(
-                self.device_features.geometry_shader > 0,
-                &mut self.enabled_features.geometry_shader,
-            ),
-            Features::TesselationShader => Feature::new(
-                self.device_features.tessellation_shader > 0,
-                &mut self.enabled_features.tessellation_shader,
+        let available = feature.is_set_in(&self.device_features, &self.vulkan12_features);
+        match feature.device_feature(&mut self.enabled_features) {
+            Some(enabled) => Feature::new(available, enabled),
+            None => Feature::new(
+                self.vulkan12_features.has_descriptor_indexing(),
+                &mut self.enabled_vulkan12_features.descriptor_indexing,
             ),
         }
     }
@@ -86,6 +99,13 @@ impl<'a> ConfigureDevice<'a> {
         self
     }
 
+    /// Persist the pipeline cache in the directory, the cache of each device and driver is kept in its own file,
+    /// if the directory can't be used the device is still created and PipelineCache::fallback_reason says why
+    pub fn pipeline_cache_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.pipeline_cache_directory = Some(directory.into());
+        self
+    }
+
     pub fn extensions_to_load<F>(mut self, select_extensions: F) -> Self
     where
         F: Fn(&mut ExtensionManager<DeviceExtensions>) -> (),
@@ -105,6 +125,13 @@ impl<'a> ConfigureDevice<'a> {
     }
 
     fn is_extension_available(&self, extension: &DeviceExtensions) -> bool {
+        // The extensions dynamic rendering depends on are only core in Vulkan 1.2
+        if *extension == DeviceExtensions::DynamicRendering {
+            let api_version: u32 = self.api_version.clone().into();
+            if !self.instance.enabled().vk1_2 || api_version < vk::make_version(1, 2, 0) {
+                return false;
+            }
+        }
         // self.available_extensions.iter().map(|ext| unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) } ).any(|ext_name| ext_name == extension.get_name())
 
         for available_extension in self.available_device_extensions.iter() {
@@ -165,7 +192,23 @@ impl<'a> ConfigureDevice<'a> {
             .filter(|(_, &present)| present == true)
             .map(|(ext, _)| ext.get_name().as_ptr())
             .collect();
+        // Vulkan 1.2 features can only be chained when the device supports them
+        let vulkan12_features = self.enabled_vulkan12_features.to_vulkan();
+        let mut p_next = if self.enabled_vulkan12_features.descriptor_indexing > 0 {
+            &vulkan12_features as *const _ as *const std::ffi::c_void
+        } else {
+            std::ptr::null()
+        };
+        let dynamic_rendering = PhysicalDeviceDynamicRenderingFeatures::enabled(p_next);
+        if self
+            .extensions_to_load
+            .get(&DeviceExtensions::DynamicRendering)
+            == Some(&true)
+        {
+            p_next = &dynamic_rendering as *const _ as *const std::ffi::c_void;
+        }
         let create_info = vk::DeviceCreateInfo {
+            p_next,
             enabled_extension_count: device_extensions.len() as u32,
             pp_enabled_extension_names: device_extensions.as_ptr(),
             p_enabled_features: &self.enabled_features,
@@ -180,18 +223,57 @@ impl<'a> ConfigureDevice<'a> {
         let device =
             erupt::DeviceLoader::new(&self.instance, self.device_handle, &create_info, None)
                 .expect("Failed to create device");
+        // Format support decides how textures are filtered and which attachment formats can be used
+        let format_properties = QUERIED_FORMATS
+            .iter()
+            .map(|&format| {
+                let properties = unsafe {
+                    self.instance.get_physical_device_format_properties(
+                        self.device_handle,
+                        format,
+                        None,
+                    )
+                };
+                (format, properties)
+            })
+            .collect();
         // We no longer need access to the vector of DeviceQueues as they are only used to create the queues
         // Instead we can get information about the queues using the RenderQueues object
         let render_queues = self
             .render_queues
             .expect("Failed to create the RenderQueues");
+        // Memory types are needed whenever an image or buffer is bound to memory
+        let memory_properties = unsafe {
+            self.instance
+                .get_physical_device_memory_properties(self.device_handle, None)
+        };
+
+        // The cache is only seeded from files written by the same device and driver
+        let properties = unsafe {
+            self.instance
+                .get_physical_device_properties(self.device_handle, None)
+        };
+        let key = PipelineCacheKey::new(&properties);
+        // A cache that can't be created only makes pipeline creation slower, so it never stops the device
+        // from being created, the reason is kept on the cache for the caller to report
+        let pipeline_cache =
+            PipelineCache::new(&device, key, self.pipeline_cache_directory.as_deref())
+                .or_else(|error| {
+                    PipelineCache::new(&device, key, None).map(|cache| cache.fallback_from(error))
+                })
+                .unwrap_or_else(|error| PipelineCache::disabled(key).fallback_from(error));
 
         VulkanDevice::new(
             self.device_handle,
             render_queues,
             self.enabled_features,
+            self.enabled_vulkan12_features,
+            self.limits,
+            memory_properties,
+            format_properties,
             self.extensions_to_load,
             device,
+            pipeline_cache,
             self.vendor_id,
             self.device_id,
             self.api_version,
@@ -201,6 +283,26 @@ impl<'a> ConfigureDevice<'a> {
     }
 }
 
+// The formats used by textures, swapchains and depth attachments
+const QUERIED_FORMATS: [vk::Format; 16] = [
+    vk::Format::R8_UNORM,
+    vk::Format::R8_SRGB,
+    vk::Format::R8G8_UNORM,
+    vk::Format::R8G8B8A8_UNORM,
+    vk::Format::R8G8B8A8_SRGB,
+    vk::Format::B8G8R8A8_UNORM,
+    vk::Format::B8G8R8A8_SRGB,
+    vk::Format::R16_SFLOAT,
+    vk::Format::R16G16_SFLOAT,
+    vk::Format::R16G16B16A16_SFLOAT,
+    vk::Format::R32G32B32A32_SFLOAT,
+    vk::Format::D16_UNORM,
+    vk::Format::D32_SFLOAT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::S8_UINT,
+];
+
 impl<'a> std::fmt::Debug for ConfigureDevice<'a> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // TODO: Create a VulkanVersion struct
@@ -3,6 +3,8 @@ use std::ffi::CStr;
 pub enum DeviceExtensions {
     Swapchain,
     IndirectCount,
+    /// Renders without renderpass and framebuffer objects, only available on a Vulkan 1.2 instance and device
+    DynamicRendering,
 }
 
 impl DeviceExtensions {
@@ -15,6 +17,10 @@ impl DeviceExtensions {
             Self::IndirectCount => unsafe {
                 CStr::from_ptr(erupt::extensions::khr_draw_indirect_count::KHR_DRAW_INDIRECT_COUNT_EXTENSION_NAME)
             },
+            // Released after the Vulkan headers erupt is generated from
+            Self::DynamicRendering => unsafe {
+                CStr::from_bytes_with_nul_unchecked(b"VK_KHR_dynamic_rendering\0")
+            },
         }
     }
 }
@@ -6,11 +6,12 @@ use super::{
     DeviceQueue, ExtensionManager, Feature, Features, OperationQueue, PciVendor, QueueFamily,
     RendererQueues, RendererQueuesBuilder,
 };
-use crate::Version;
+use crate::{Version, Vulkan12Features};
 use erupt::extensions::khr_surface as surface;
 use erupt::vk1_0 as vk;
 pub use extensions::DeviceExtensions;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 // Responsible for configuring the underlying device, creating queues, enabling features, loading device extensions and specifying surface parameters
 pub struct ConfigureDevice<'a> {
@@ -33,6 +34,12 @@ pub struct ConfigureDevice<'a> {
     device_features: vk::PhysicalDeviceFeatures,
     // Enabled Features
     enabled_features: vk::PhysicalDeviceFeatures,
+    // Available and enabled features that were added in Vulkan 1.2
+    vulkan12_features: Vulkan12Features,
+    enabled_vulkan12_features: Vulkan12Features,
+    limits: vk::PhysicalDeviceLimits,
+    // Where the pipeline cache is persisted between runs
+    pipeline_cache_directory: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,322 @@
+use super::{
+    DescriptorPool, DescriptorSet, DescriptorSetLayout, PoolLifetime, TableIndices, TableStorage,
+    TextureIndex, TextureTable,
+};
+use crate::error::{Error, ErrorKind};
+use crate::{Features, Texture, VulkanDevice};
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+
+// Per texture sets are allocated in blocks of this many
+const PER_DRAW_SETS_PER_BLOCK: u32 = 64;
+
+impl TextureIndex {
+    /// The array element that holds the texture in the bindless table
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl TextureTable {
+    /// Creates a table with room for capacity textures that can be read by the given shader stages,
+    /// when bindless the capacity is limited by the number of update after bind images and samplers the device supports
+    pub fn new(
+        device: &VulkanDevice,
+        capacity: u32,
+        stages: vk::ShaderStageFlags,
+    ) -> Result<TextureTable, Error> {
+        if capacity == 0 {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"A texture table needs room for at least one texture"));
+        }
+        let (layout, storage, capacity) = if device.feature_enabled(&Features::DescriptorIndexing) {
+            let limits = &device.enabled_vulkan12_features;
+            let capacity = capacity
+                .min(limits.max_update_after_bind_sampled_images)
+                .min(limits.max_update_after_bind_samplers);
+            let (layout, pool, set) = Self::create_bindless(device, capacity, stages)?;
+            (layout, TableStorage::Bindless { pool, set }, capacity)
+        } else {
+            let layout = device
+                .create_descriptor_set_layout()
+                .combined_image_sampler(0, stages, 1)
+                .build()?;
+            let pool = DescriptorPool::new(
+                device,
+                PoolLifetime::Persistent,
+                &[&layout],
+                PER_DRAW_SETS_PER_BLOCK,
+            )?;
+            let storage = TableStorage::PerDraw {
+                pool,
+                sets: Vec::new(),
+            };
+            (layout, storage, capacity)
+        };
+        Ok(TextureTable {
+            layout,
+            storage,
+            indices: TableIndices::new(capacity),
+        })
+    }
+
+    // A single set with one large array of combined image samplers, elements that were never written are
+    // allowed as long as shaders don't read them and elements not in use can be written while a frame is in flight
+    fn create_bindless(
+        device: &VulkanDevice,
+        capacity: u32,
+        stages: vk::ShaderStageFlags,
+    ) -> Result<(DescriptorSetLayout, vk::DescriptorPool, DescriptorSet), Error> {
+        let layout = device
+            .create_descriptor_set_layout()
+            .combined_image_sampler(0, stages, capacity)
+            .binding_flags(
+                0,
+                vk1_2::DescriptorBindingFlags::PARTIALLY_BOUND
+                    | vk1_2::DescriptorBindingFlags::UPDATE_AFTER_BIND
+                    | vk1_2::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING,
+            )
+            .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            .build()?;
+        let sizes = [vk::DescriptorPoolSizeBuilder::new()
+            ._type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(capacity)];
+        let create_info = vk::DescriptorPoolCreateInfoBuilder::new()
+            .flags(vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+            .max_sets(1)
+            .pool_sizes(&sizes);
+        let pool = unsafe {
+            device
+                .handle()
+                .create_descriptor_pool(&create_info, None, None)
+        }
+        .result()?;
+        let layouts = [layout.handle()];
+        let allocate_info = vk::DescriptorSetAllocateInfoBuilder::new()
+            .descriptor_pool(pool)
+            .set_layouts(&layouts);
+        let handle =
+            match unsafe { device.handle().allocate_descriptor_sets(&allocate_info) }.result() {
+                Ok(sets) => sets[0],
+                Err(error) => {
+                    unsafe { device.handle().destroy_descriptor_pool(Some(pool), None) };
+                    layout.destroy(device);
+                    return Err(Error::from(error));
+                }
+            };
+        let set = DescriptorSet {
+            handle,
+            pool,
+            bindings: layout.bindings().to_vec(),
+        };
+        Ok((layout, pool, set))
+    }
+
+    /// The number of frames that can be in flight at once, removed indices are reused after this many frames
+    pub fn frames_in_flight(&mut self, frames: u64) {
+        self.indices.frames_in_flight = frames;
+    }
+
+    /// True if every texture is in one set, otherwise the set of each texture must be bound before drawing with it
+    pub fn is_bindless(&self) -> bool {
+        matches!(self.storage, TableStorage::Bindless { .. })
+    }
+
+    /// The layout pipelines that read the table must include
+    pub fn layout(&self) -> &DescriptorSetLayout {
+        &self.layout
+    }
+
+    /// The largest number of textures the table can hold at once
+    pub fn capacity(&self) -> u32 {
+        self.indices.capacity
+    }
+
+    /// Adds a texture to the table, the texture must outlive its index
+    pub fn insert(
+        &mut self,
+        device: &VulkanDevice,
+        texture: &Texture,
+    ) -> Result<TextureIndex, Error> {
+        let index = self.indices.allocate()?;
+        let result = match &mut self.storage {
+            TableStorage::Bindless { set, .. } => set
+                .write()
+                .texture(0, index, texture)
+                .map(|writes| writes.submit(device)),
+            TableStorage::PerDraw { pool, sets } => {
+                Self::write_per_draw(device, &self.layout, pool, sets, index, texture)
+            }
+        };
+        match result {
+            Ok(()) => Ok(TextureIndex(index)),
+            Err(error) => {
+                self.indices.release(index);
+                Err(error)
+            }
+        }
+    }
+
+    fn write_per_draw(
+        device: &VulkanDevice,
+        layout: &DescriptorSetLayout,
+        pool: &mut DescriptorPool,
+        sets: &mut Vec<Option<DescriptorSet>>,
+        index: u32,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let set = pool.allocate(device, layout)?;
+        set.write().texture(0, 0, texture)?.submit(device);
+        let slot = index as usize;
+        if sets.len() <= slot {
+            sets.resize_with(slot + 1, || None);
+        }
+        sets[slot] = Some(set);
+        Ok(())
+    }
+
+    /// Removes a texture from the table, the index is not reused until the frames in flight have finished
+    /// so the texture must not be destroyed before then, an index that was already removed is an error
+    pub fn remove(&mut self, index: TextureIndex) -> Result<(), Error> {
+        self.indices.retire(index.0)
+    }
+
+    /// Call once per frame after waiting for the oldest frame in flight, reuses indices nothing can read anymore
+    pub fn next_frame(&mut self, device: &VulkanDevice) -> Result<(), Error> {
+        for index in self.indices.next_frame() {
+            if let TableStorage::PerDraw { pool, sets } = &mut self.storage {
+                if let Some(set) = sets[index as usize].take() {
+                    pool.free(device, set)?;
+                }
+            }
+            self.indices.release(index);
+        }
+        Ok(())
+    }
+
+    /// The set to bind when drawing with the texture, when bindless this is the same set for every texture
+    pub fn descriptor_set(&self, index: TextureIndex) -> vk::DescriptorSet {
+        match &self.storage {
+            TableStorage::Bindless { set, .. } => set.handle(),
+            TableStorage::PerDraw { sets, .. } => sets
+                .get(index.0 as usize)
+                .and_then(|set| set.as_ref())
+                .map(|set| set.handle())
+                .unwrap_or_else(vk::DescriptorSet::null),
+        }
+    }
+
+    /// The array element shaders should read the texture from, per draw sets only hold one texture
+    /// so shaders can use the same lookup either way
+    pub fn shader_index(&self, index: TextureIndex) -> u32 {
+        match self.storage {
+            TableStorage::Bindless { .. } => index.0,
+            TableStorage::PerDraw { .. } => 0,
+        }
+    }
+
+    /// Destroys the table, the textures in it are not destroyed
+    pub fn destroy(self, device: &VulkanDevice) {
+        match self.storage {
+            TableStorage::Bindless { pool, .. } => unsafe {
+                device.handle().destroy_descriptor_pool(Some(pool), None)
+            },
+            TableStorage::PerDraw { pool, .. } => pool.destroy(device),
+        }
+        self.layout.destroy(device);
+    }
+}
+
+impl TableIndices {
+    fn new(capacity: u32) -> TableIndices {
+        TableIndices {
+            capacity,
+            next_index: 0,
+            free_indices: Vec::new(),
+            live: Vec::new(),
+            retired: Vec::new(),
+            frame: 0,
+            frames_in_flight: 2,
+        }
+    }
+
+    // Reuses a free index before handing out one that has never been used
+    fn allocate(&mut self) -> Result<u32, Error> {
+        let index = match self.free_indices.pop() {
+            Some(index) => index,
+            None if self.next_index < self.capacity => {
+                self.next_index += 1;
+                self.live.push(false);
+                self.next_index - 1
+            }
+            None => {
+                return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                    .with_context(&"The texture table is full"))
+            }
+        };
+        self.live[index as usize] = true;
+        Ok(index)
+    }
+
+    // Makes an index available straight away, used once nothing can read it anymore
+    fn release(&mut self, index: u32) {
+        self.live[index as usize] = false;
+        self.free_indices.push(index);
+    }
+
+    // Holds an index back until the frames in flight have finished
+    fn retire(&mut self, index: u32) -> Result<(), Error> {
+        match self.live.get_mut(index as usize) {
+            Some(live) if *live => {
+                *live = false;
+                self.retired.push((self.frame, index));
+                Ok(())
+            }
+            _ => Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"The texture index was already removed from the table")),
+        }
+    }
+
+    // Advances to the next frame and returns the retired indices that nothing can read anymore
+    fn next_frame(&mut self) -> Vec<u32> {
+        self.frame += 1;
+        let oldest_in_use = self.frame.saturating_sub(self.frames_in_flight);
+        let (finished, retired): (Vec<_>, Vec<_>) = self
+            .retired
+            .drain(..)
+            .partition(|(frame, _)| *frame <= oldest_in_use);
+        self.retired = retired;
+        finished.into_iter().map(|(_, index)| index).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removing_twice_is_an_error() {
+        let mut indices = TableIndices::new(2);
+        let first = indices.allocate().unwrap();
+        let second = indices.allocate().unwrap();
+        assert!(indices.allocate().is_err());
+        // Past the first frames so that every frame in flight could be reading the index
+        for _ in 0..2 {
+            assert!(indices.next_frame().is_empty());
+        }
+        indices.retire(first).unwrap();
+        assert_eq!(
+            indices.retire(first).unwrap_err().kind(),
+            &ErrorKind::InvalidDescriptorConfig
+        );
+        assert!(indices.retire(7).is_err());
+        // The index is only reused once the frames that may read it have finished
+        assert!(indices.next_frame().is_empty());
+        for index in indices.next_frame() {
+            indices.release(index);
+        }
+        assert_eq!(indices.allocate().unwrap(), first);
+        assert!(indices.allocate().is_err());
+        indices.retire(second).unwrap();
+    }
+}
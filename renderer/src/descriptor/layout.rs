@@ -0,0 +1,230 @@
+use super::{ConfigureDescriptorSetLayout, DescriptorSetLayout};
+use crate::error::{Error, ErrorKind};
+use crate::{Features, VulkanDevice};
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+use erupt::ExtendableFrom;
+
+impl<'a> ConfigureDescriptorSetLayout<'a> {
+    pub fn new(device: &'a VulkanDevice) -> ConfigureDescriptorSetLayout<'a> {
+        ConfigureDescriptorSetLayout {
+            device,
+            bindings: Vec::new(),
+            binding_flags: Vec::new(),
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+        }
+    }
+
+    /// Adds a binding of count descriptors that can be accessed by the given shader stages
+    pub fn binding(
+        mut self,
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        stages: vk::ShaderStageFlags,
+        count: u32,
+    ) -> Self {
+        let binding = vk::DescriptorSetLayoutBindingBuilder::new()
+            .binding(binding)
+            .descriptor_type(descriptor_type)
+            .descriptor_count(count)
+            .stage_flags(stages);
+        self.bindings.push(*binding);
+        self
+    }
+
+    pub fn uniform_buffer(self, binding: u32, stages: vk::ShaderStageFlags) -> Self {
+        self.binding(binding, vk::DescriptorType::UNIFORM_BUFFER, stages, 1)
+    }
+
+    pub fn storage_buffer(self, binding: u32, stages: vk::ShaderStageFlags) -> Self {
+        self.binding(binding, vk::DescriptorType::STORAGE_BUFFER, stages, 1)
+    }
+
+    /// An array of images that are each sampled with their own sampler
+    pub fn combined_image_sampler(
+        self,
+        binding: u32,
+        stages: vk::ShaderStageFlags,
+        count: u32,
+    ) -> Self {
+        self.binding(
+            binding,
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            stages,
+            count,
+        )
+    }
+
+    /// An array of images that are sampled using a separate sampler binding
+    pub fn sampled_image(self, binding: u32, stages: vk::ShaderStageFlags, count: u32) -> Self {
+        self.binding(binding, vk::DescriptorType::SAMPLED_IMAGE, stages, count)
+    }
+
+    pub fn sampler(self, binding: u32, stages: vk::ShaderStageFlags) -> Self {
+        self.binding(binding, vk::DescriptorType::SAMPLER, stages, 1)
+    }
+
+    pub fn flags(mut self, flags: vk::DescriptorSetLayoutCreateFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets descriptor indexing flags for a binding, such as allowing it to be partially bound or
+    /// updated after it is bound, needs the DescriptorIndexing feature
+    pub fn binding_flags(mut self, binding: u32, flags: vk1_2::DescriptorBindingFlags) -> Self {
+        self.binding_flags.push((binding, flags));
+        self
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for (index, binding) in self.bindings.iter().enumerate() {
+            if binding.descriptor_count == 0 {
+                return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                    .with_context(&"A binding must contain at least one descriptor"));
+            }
+            if binding.stage_flags.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                    .with_context(&"A binding must be accessible from at least one shader stage"));
+            }
+            if self.bindings[..index]
+                .iter()
+                .any(|other| other.binding == binding.binding)
+            {
+                return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                    .with_context(&"The same binding number was used more than once"));
+            }
+        }
+        if self.binding_flags.is_empty() {
+            return Ok(());
+        }
+        if !self.device.feature_enabled(&Features::DescriptorIndexing) {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"Binding flags need the DescriptorIndexing feature to be enabled"));
+        }
+        for (binding, flags) in self.binding_flags.iter() {
+            if !self.bindings.iter().any(|other| other.binding == *binding) {
+                return Err(
+                    Error::new(ErrorKind::InvalidDescriptorConfig, None).with_context(
+                        &"Binding flags were set for a binding that is not part of the layout",
+                    ),
+                );
+            }
+            if flags.contains(vk1_2::DescriptorBindingFlags::UPDATE_AFTER_BIND)
+                && !self
+                    .flags
+                    .contains(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            {
+                return Err(
+                    Error::new(ErrorKind::InvalidDescriptorConfig, None).with_context(
+                        &"Bindings updated after bind need a layout with the UPDATE_AFTER_BIND_POOL flag",
+                    ),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<DescriptorSetLayout, Error> {
+        self.validate()?;
+        let bindings: Vec<vk::DescriptorSetLayoutBindingBuilder> = self
+            .bindings
+            .iter()
+            .map(|binding| binding.into_builder())
+            .collect();
+        // Every binding needs an entry once any binding has flags
+        let binding_flags: Vec<vk1_2::DescriptorBindingFlags> = self
+            .bindings
+            .iter()
+            .map(|binding| {
+                self.binding_flags
+                    .iter()
+                    .filter(|(flagged, _)| *flagged == binding.binding)
+                    .fold(vk1_2::DescriptorBindingFlags::empty(), |all, (_, flags)| {
+                        all | *flags
+                    })
+            })
+            .collect();
+        let mut flags_info = vk1_2::DescriptorSetLayoutBindingFlagsCreateInfoBuilder::new()
+            .binding_flags(binding_flags.as_slice());
+        let mut create_info = vk::DescriptorSetLayoutCreateInfoBuilder::new()
+            .flags(self.flags)
+            .bindings(bindings.as_slice());
+        if !self.binding_flags.is_empty() {
+            create_info = create_info.extend_from(&mut flags_info);
+        }
+        let handle = unsafe {
+            self.device
+                .handle()
+                .create_descriptor_set_layout(&create_info, None, None)
+        }
+        .result()?;
+        Ok(DescriptorSetLayout {
+            handle,
+            bindings: self.bindings,
+        })
+    }
+}
+
+impl DescriptorSetLayout {
+    pub fn handle(&self) -> vk::DescriptorSetLayout {
+        self.handle
+    }
+
+    pub fn bindings(&self) -> &[vk::DescriptorSetLayoutBinding] {
+        self.bindings.as_slice()
+    }
+
+    /// The number of descriptors of each type that a set with this layout needs
+    pub fn descriptor_counts(&self) -> Vec<vk::DescriptorPoolSize> {
+        let mut counts: Vec<vk::DescriptorPoolSize> = Vec::new();
+        for binding in self.bindings.iter() {
+            match counts
+                .iter_mut()
+                .find(|size| size._type == binding.descriptor_type)
+            {
+                Some(size) => size.descriptor_count += binding.descriptor_count,
+                None => counts.push(vk::DescriptorPoolSize {
+                    _type: binding.descriptor_type,
+                    descriptor_count: binding.descriptor_count,
+                }),
+            }
+        }
+        counts
+    }
+
+    /// Destroys the layout, pipelines created using the layout can still be used
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe {
+            device
+                .handle()
+                .destroy_descriptor_set_layout(Some(self.handle), None)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptor_counts() {
+        let binding = |binding, descriptor_type, descriptor_count| vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type,
+            descriptor_count,
+            ..Default::default()
+        };
+        let layout = DescriptorSetLayout {
+            handle: vk::DescriptorSetLayout::null(),
+            bindings: vec![
+                binding(0, vk::DescriptorType::UNIFORM_BUFFER, 1),
+                binding(1, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 4),
+                binding(2, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 2),
+            ],
+        };
+        let counts = layout.descriptor_counts();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[1]._type, vk::DescriptorType::COMBINED_IMAGE_SAMPLER);
+        assert_eq!(counts[1].descriptor_count, 6);
+    }
+}
@@ -0,0 +1,106 @@
+mod bindless;
+mod layout;
+mod pool;
+mod writes;
+
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+
+/// Collects the bindings of a descriptor set layout before it is created
+pub struct ConfigureDescriptorSetLayout<'a> {
+    device: &'a VulkanDevice,
+    bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    // Descriptor indexing flags for individual bindings
+    binding_flags: Vec<(u32, vk1_2::DescriptorBindingFlags)>,
+    flags: vk::DescriptorSetLayoutCreateFlags,
+}
+
+/// Describes the resources a set provides to shaders, the bindings are kept so that pools can be sized
+/// and writes can be checked against the layout
+pub struct DescriptorSetLayout {
+    handle: vk::DescriptorSetLayout,
+    bindings: Vec<vk::DescriptorSetLayoutBinding>,
+}
+
+/// How long the sets allocated from a pool live
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PoolLifetime {
+    /// Sets are only valid until the pool is reset, usually once the frame that used them has finished
+    PerFrame,
+    /// Sets live until they are freed individually or the pool is destroyed
+    Persistent,
+}
+
+/// Allocates descriptor sets, when the current block runs out of space a larger one is created
+pub struct DescriptorPool {
+    lifetime: PoolLifetime,
+    // The number of descriptors of each type needed by a single set
+    descriptors_per_set: Vec<vk::DescriptorPoolSize>,
+    blocks: Vec<vk::DescriptorPool>,
+    // Blocks before this one have run out of space since the last reset
+    current_block: usize,
+    sets_per_block: u32,
+}
+
+/// A set allocated from a pool along with the bindings of its layout
+pub struct DescriptorSet {
+    handle: vk::DescriptorSet,
+    // The block the set was allocated from, needed to free persistent sets
+    pool: vk::DescriptorPool,
+    bindings: Vec<vk::DescriptorSetLayoutBinding>,
+}
+
+// Which of the info arrays a pending write reads from
+enum WriteInfo {
+    Image(usize),
+    Buffer(usize),
+}
+
+/// Collects writes to a descriptor set, each write is checked against the layout of the set
+pub struct DescriptorWrites<'a> {
+    set: &'a DescriptorSet,
+    writes: Vec<(u32, u32, vk::DescriptorType, WriteInfo)>,
+    image_infos: Vec<vk::DescriptorImageInfoBuilder<'a>>,
+    buffer_infos: Vec<vk::DescriptorBufferInfoBuilder<'a>>,
+}
+
+/// A stable slot for a texture in a TextureTable, the slot is not reused until the frames that may read it have finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureIndex(u32);
+
+// Where the descriptors of a texture table live
+enum TableStorage {
+    // Every texture is an element of one partially bound array that is updated after being bound
+    Bindless {
+        pool: vk::DescriptorPool,
+        set: DescriptorSet,
+    },
+    // Devices without descriptor indexing get a set per texture that is bound for each draw
+    PerDraw {
+        pool: DescriptorPool,
+        sets: Vec<Option<DescriptorSet>>,
+    },
+}
+
+/// Gives each texture a stable index that shaders use to look it up, uses a single bindless array when
+/// descriptor indexing is enabled and falls back to a set per texture otherwise
+pub struct TextureTable {
+    layout: DescriptorSetLayout,
+    storage: TableStorage,
+    indices: TableIndices,
+}
+
+// Hands out the indices of a texture table, removed indices are held back until the frames that may read them have finished
+struct TableIndices {
+    capacity: u32,
+    // The next index that has never been handed out
+    next_index: u32,
+    free_indices: Vec<u32>,
+    // Whether each index that has been handed out is in use, so removing an index twice is caught
+    live: Vec<bool>,
+    // Removed indices along with the frame they were removed in
+    retired: Vec<(u64, u32)>,
+    frame: u64,
+    frames_in_flight: u64,
+}
@@ -0,0 +1,158 @@
+use super::{DescriptorPool, DescriptorSet, DescriptorSetLayout, PoolLifetime};
+use crate::error::{Error, ErrorKind};
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+impl DescriptorPool {
+    /// Creates a pool sized for sets using any of the given layouts, each block of the pool can hold
+    /// sets_per_block sets and later blocks double in size when earlier ones are exhausted
+    pub fn new(
+        device: &VulkanDevice,
+        lifetime: PoolLifetime,
+        layouts: &[&DescriptorSetLayout],
+        sets_per_block: u32,
+    ) -> Result<DescriptorPool, Error> {
+        if sets_per_block == 0 || layouts.is_empty() {
+            return Err(
+                Error::new(ErrorKind::InvalidDescriptorConfig, None).with_context(
+                    &"A descriptor pool needs at least one layout and room for at least one set",
+                ),
+            );
+        }
+        // Each set may use the largest number of each descriptor type across the layouts
+        let mut descriptors_per_set: Vec<vk::DescriptorPoolSize> = Vec::new();
+        for size in layouts.iter().flat_map(|layout| layout.descriptor_counts()) {
+            match descriptors_per_set
+                .iter_mut()
+                .find(|existing| existing._type == size._type)
+            {
+                Some(existing) => {
+                    existing.descriptor_count = existing.descriptor_count.max(size.descriptor_count)
+                }
+                None => descriptors_per_set.push(size),
+            }
+        }
+        let mut pool = DescriptorPool {
+            lifetime,
+            descriptors_per_set,
+            blocks: Vec::new(),
+            current_block: 0,
+            sets_per_block,
+        };
+        pool.add_block(device)?;
+        Ok(pool)
+    }
+
+    fn add_block(&mut self, device: &VulkanDevice) -> Result<(), Error> {
+        let sets = self.sets_per_block << self.blocks.len().min(8);
+        let sizes: Vec<vk::DescriptorPoolSizeBuilder> = self
+            .descriptors_per_set
+            .iter()
+            .map(|size| {
+                vk::DescriptorPoolSizeBuilder::new()
+                    ._type(size._type)
+                    .descriptor_count(size.descriptor_count * sets)
+            })
+            .collect();
+        let flags = match self.lifetime {
+            PoolLifetime::PerFrame => vk::DescriptorPoolCreateFlags::empty(),
+            PoolLifetime::Persistent => vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET,
+        };
+        let create_info = vk::DescriptorPoolCreateInfoBuilder::new()
+            .flags(flags)
+            .max_sets(sets)
+            .pool_sizes(sizes.as_slice());
+        let block = unsafe {
+            device
+                .handle()
+                .create_descriptor_pool(&create_info, None, None)
+        }
+        .result()?;
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    /// Allocates a set with the given layout, a new block is added to the pool if the current ones are full
+    pub fn allocate(
+        &mut self,
+        device: &VulkanDevice,
+        layout: &DescriptorSetLayout,
+    ) -> Result<DescriptorSet, Error> {
+        // Otherwise new blocks would be added forever without ever having room for the set
+        let fits = layout.descriptor_counts().iter().all(|needed| {
+            self.descriptors_per_set.iter().any(|size| {
+                size._type == needed._type && size.descriptor_count >= needed.descriptor_count
+            })
+        });
+        if !fits {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"The layout needs descriptors that the pool was not sized for"));
+        }
+        let layouts = [layout.handle()];
+        loop {
+            let block = self.blocks[self.current_block];
+            let allocate_info = vk::DescriptorSetAllocateInfoBuilder::new()
+                .descriptor_pool(block)
+                .set_layouts(&layouts);
+            let result = unsafe { device.handle().allocate_descriptor_sets(&allocate_info) };
+            match result.raw {
+                vk::Result::SUCCESS => {
+                    let handle = result.result()?[0];
+                    return Ok(DescriptorSet {
+                        handle,
+                        pool: block,
+                        bindings: layout.bindings().to_vec(),
+                    });
+                }
+                vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL => {
+                    // Full blocks are revisited once sets are freed or the pool is reset
+                    self.current_block += 1;
+                    if self.current_block == self.blocks.len() {
+                        self.add_block(device)?;
+                    }
+                }
+                error => return Err(Error::from(error)),
+            }
+        }
+    }
+
+    /// Returns a persistent set to the pool, per frame sets are returned when the pool is reset
+    pub fn free(&mut self, device: &VulkanDevice, set: DescriptorSet) -> Result<(), Error> {
+        if self.lifetime == PoolLifetime::PerFrame {
+            return Err(
+                Error::new(ErrorKind::InvalidDescriptorConfig, None).with_context(
+                    &"Sets from a per frame pool can only be released by resetting the pool",
+                ),
+            );
+        }
+        unsafe {
+            device
+                .handle()
+                .free_descriptor_sets(set.pool, &[set.handle])
+        }
+        .result()?;
+        if let Some(block) = self.blocks.iter().position(|&block| block == set.pool) {
+            self.current_block = self.current_block.min(block);
+        }
+        Ok(())
+    }
+
+    /// Releases every set allocated from the pool, the sets must no longer be in use by the device
+    pub fn reset(&mut self, device: &VulkanDevice) -> Result<(), Error> {
+        for &block in self.blocks.iter() {
+            unsafe { device.handle().reset_descriptor_pool(block, None) }.result()?;
+        }
+        self.current_block = 0;
+        Ok(())
+    }
+
+    pub fn lifetime(&self) -> PoolLifetime {
+        self.lifetime
+    }
+
+    pub fn destroy(self, device: &VulkanDevice) {
+        for block in self.blocks {
+            unsafe { device.handle().destroy_descriptor_pool(Some(block), None) };
+        }
+    }
+}
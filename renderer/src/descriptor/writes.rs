@@ -0,0 +1,221 @@
+use super::{DescriptorSet, DescriptorWrites, WriteInfo};
+use crate::error::{Error, ErrorKind};
+use crate::{Buffer, ImageView, Sampler, Texture, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl DescriptorSet {
+    pub fn handle(&self) -> vk::DescriptorSet {
+        self.handle
+    }
+
+    /// The bindings of the layout the set was allocated with
+    pub fn bindings(&self) -> &[vk::DescriptorSetLayoutBinding] {
+        self.bindings.as_slice()
+    }
+
+    /// Start collecting writes to the set, they are applied together by DescriptorWrites::submit
+    pub fn write(&self) -> DescriptorWrites<'_> {
+        DescriptorWrites {
+            set: self,
+            writes: Vec::new(),
+            image_infos: Vec::new(),
+            buffer_infos: Vec::new(),
+        }
+    }
+}
+
+impl<'a> DescriptorWrites<'a> {
+    // Checks that the binding exists, has the expected type and that the array element is in range
+    fn check(
+        &self,
+        binding: u32,
+        array_element: u32,
+        descriptor_type: vk::DescriptorType,
+    ) -> Result<(), Error> {
+        let layout_binding = self
+            .set
+            .bindings
+            .iter()
+            .find(|layout_binding| layout_binding.binding == binding)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                    .with_context(&"The binding being written is not part of the set layout")
+            })?;
+        if layout_binding.descriptor_type != descriptor_type {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"The type of the write does not match the type of the binding"));
+        }
+        if array_element >= layout_binding.descriptor_count {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"The array element being written is past the end of the binding"));
+        }
+        Ok(())
+    }
+
+    fn buffer(
+        mut self,
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        buffer: &Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Result<Self, Error> {
+        self.check(binding, 0, descriptor_type)?;
+        if range != vk::WHOLE_SIZE && offset + range > buffer.size() {
+            return Err(Error::new(ErrorKind::InvalidDescriptorConfig, None)
+                .with_context(&"The range being bound is past the end of the buffer"));
+        }
+        self.buffer_infos.push(
+            vk::DescriptorBufferInfoBuilder::new()
+                .buffer(buffer.handle())
+                .offset(offset)
+                .range(range),
+        );
+        let info = WriteInfo::Buffer(self.buffer_infos.len() - 1);
+        self.writes.push((binding, 0, descriptor_type, info));
+        Ok(self)
+    }
+
+    fn image(
+        mut self,
+        binding: u32,
+        array_element: u32,
+        descriptor_type: vk::DescriptorType,
+        image_info: vk::DescriptorImageInfo,
+    ) -> Result<Self, Error> {
+        self.check(binding, array_element, descriptor_type)?;
+        self.image_infos.push(image_info.into_builder());
+        let info = WriteInfo::Image(self.image_infos.len() - 1);
+        self.writes
+            .push((binding, array_element, descriptor_type, info));
+        Ok(self)
+    }
+
+    /// Binds a range of a buffer to a uniform buffer binding, use vk::WHOLE_SIZE to bind the rest of the buffer
+    pub fn uniform_buffer(
+        self,
+        binding: u32,
+        buffer: &Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Result<Self, Error> {
+        self.buffer(
+            binding,
+            vk::DescriptorType::UNIFORM_BUFFER,
+            buffer,
+            offset,
+            range,
+        )
+    }
+
+    pub fn storage_buffer(
+        self,
+        binding: u32,
+        buffer: &Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    ) -> Result<Self, Error> {
+        self.buffer(
+            binding,
+            vk::DescriptorType::STORAGE_BUFFER,
+            buffer,
+            offset,
+            range,
+        )
+    }
+
+    /// Writes an image and the sampler used to read it into an element of a combined image sampler binding
+    pub fn combined_image_sampler(
+        self,
+        binding: u32,
+        array_element: u32,
+        view: &ImageView,
+        sampler: &Sampler,
+        layout: vk::ImageLayout,
+    ) -> Result<Self, Error> {
+        let image_info = vk::DescriptorImageInfo {
+            sampler: sampler.handle(),
+            image_view: view.handle(),
+            image_layout: layout,
+        };
+        self.image(
+            binding,
+            array_element,
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            image_info,
+        )
+    }
+
+    pub fn sampled_image(
+        self,
+        binding: u32,
+        array_element: u32,
+        view: &ImageView,
+        layout: vk::ImageLayout,
+    ) -> Result<Self, Error> {
+        let image_info = vk::DescriptorImageInfo {
+            sampler: vk::Sampler::null(),
+            image_view: view.handle(),
+            image_layout: layout,
+        };
+        self.image(
+            binding,
+            array_element,
+            vk::DescriptorType::SAMPLED_IMAGE,
+            image_info,
+        )
+    }
+
+    pub fn sampler(self, binding: u32, sampler: &Sampler) -> Result<Self, Error> {
+        let image_info = vk::DescriptorImageInfo {
+            sampler: sampler.handle(),
+            image_view: vk::ImageView::null(),
+            image_layout: vk::ImageLayout::UNDEFINED,
+        };
+        self.image(binding, 0, vk::DescriptorType::SAMPLER, image_info)
+    }
+
+    /// Writes a texture using its own sampler into a combined image sampler binding
+    pub fn texture(
+        self,
+        binding: u32,
+        array_element: u32,
+        texture: &Texture,
+    ) -> Result<Self, Error> {
+        self.combined_image_sampler(
+            binding,
+            array_element,
+            texture.view(),
+            texture.sampler(),
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        )
+    }
+
+    /// Applies the writes, the set must not be in use by a command buffer that is executing
+    pub fn submit(self, device: &VulkanDevice) {
+        let writes: Vec<vk::WriteDescriptorSetBuilder> = self
+            .writes
+            .iter()
+            .map(|(binding, array_element, descriptor_type, info)| {
+                let write = vk::WriteDescriptorSetBuilder::new()
+                    .dst_set(self.set.handle)
+                    .dst_binding(*binding)
+                    .dst_array_element(*array_element)
+                    .descriptor_type(*descriptor_type);
+                match info {
+                    WriteInfo::Image(index) => {
+                        write.image_info(std::slice::from_ref(&self.image_infos[*index]))
+                    }
+                    WriteInfo::Buffer(index) => {
+                        write.buffer_info(std::slice::from_ref(&self.buffer_infos[*index]))
+                    }
+                }
+            })
+            .collect();
+        unsafe {
+            device
+                .handle()
+                .update_descriptor_sets(writes.as_slice(), &[])
+        };
+    }
+}
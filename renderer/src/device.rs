@@ -1,4 +1,13 @@
-use crate::{ConfigurePipeline, DeviceExtensions, PciVendor, RendererQueues, Renderpass, Version};
+use crate::error::{Error, ErrorKind};
+use crate::rendering::DynamicRendering;
+use crate::{
+    Buffer, CommandPool, CommandRecorder, ConfigureComputePipeline, ConfigureDescriptorSetLayout,
+    ConfigureImage, ConfigurePipeline, ConfigureRenderpass, DecodedImage, DescriptorPool,
+    DescriptorSetLayout, DeviceExtensions, Features, Framebuffer, Image, ImageView,
+    ImageViewSettings, OperationQueue, PciVendor, PipelineCache, PoolLifetime, RenderTarget,
+    RendererQueues, Renderpass, Sampler, SamplerSettings, Texture, TextureSettings, Version,
+    Vulkan12Features,
+};
 use erupt::vk1_0 as vk;
 
 use std::collections::HashMap;
@@ -11,8 +20,15 @@ pub struct VulkanDevice {
     // A structure that contains information regarding all the Vulkan queues we created
     pub(crate) render_queues: RendererQueues,
     pub(crate) device: erupt::DeviceLoader,
+    pub(crate) pipeline_cache: PipelineCache,
     pub(crate) enabled_features: vk::PhysicalDeviceFeatures,
+    pub(crate) enabled_vulkan12_features: Vulkan12Features,
+    pub(crate) limits: vk::PhysicalDeviceLimits,
+    pub(crate) memory_properties: vk::PhysicalDeviceMemoryProperties,
+    pub(crate) format_properties: HashMap<vk::Format, vk::FormatProperties>,
     pub(crate) extensions_loaded: HashMap<DeviceExtensions, bool>,
+    // Only loaded when VK_KHR_dynamic_rendering was enabled
+    pub(crate) dynamic_rendering: Option<DynamicRendering>,
     pub(crate) api_version: Version,
     pub(crate) driver_version: u32,
     pub(crate) vendor_id: PciVendor,
@@ -21,23 +37,40 @@ pub struct VulkanDevice {
 }
 
 impl VulkanDevice {
-    pub fn new(
+    pub(crate) fn new(
         physical_device: vk::PhysicalDevice,
         render_queues: RendererQueues,
         enabled_features: vk::PhysicalDeviceFeatures,
+        enabled_vulkan12_features: Vulkan12Features,
+        limits: vk::PhysicalDeviceLimits,
+        memory_properties: vk::PhysicalDeviceMemoryProperties,
+        format_properties: HashMap<vk::Format, vk::FormatProperties>,
         extensions_loaded: HashMap<DeviceExtensions, bool>,
         device: erupt::DeviceLoader,
+        pipeline_cache: PipelineCache,
         vendor_id: PciVendor,
         device_id: u32,
         api_version: Version,
         driver_version: u32,
         device_name: [c_char; erupt::vk1_0::MAX_PHYSICAL_DEVICE_NAME_SIZE as usize],
     ) -> VulkanDevice {
+        let dynamic_rendering =
+            if extensions_loaded.get(&DeviceExtensions::DynamicRendering) == Some(&true) {
+                DynamicRendering::load(&device)
+            } else {
+                None
+            };
         VulkanDevice {
+            dynamic_rendering,
             render_queues,
             enabled_features,
+            enabled_vulkan12_features,
+            limits,
+            memory_properties,
+            format_properties,
             extensions_loaded,
             device,
+            pipeline_cache,
             api_version,
             physical_device,
             vendor_id,
@@ -54,13 +87,289 @@ impl VulkanDevice {
     pub fn queues(&self) -> &RendererQueues {
         &self.render_queues
     }
-    // TODO: Do we need seperate config for graphics or compute pipelines
     pub fn create_pipeline(&self) -> ConfigurePipeline {
-        ConfigurePipeline::new(&self.device)
+        ConfigurePipeline::new(
+            &self.device,
+            self.pipeline_cache.handle(),
+            self.enabled_features,
+            self.limits,
+            self.supports_dynamic_rendering(),
+        )
     }
 
-    pub fn create_renderpass(&self) -> Renderpass {
-        Renderpass::new(&self.device)
+    /// True when VK_KHR_dynamic_rendering was loaded, otherwise render with a renderpass and framebuffers
+    pub fn supports_dynamic_rendering(&self) -> bool {
+        self.dynamic_rendering.is_some()
+    }
+
+    /// Start configuring a compute pipeline
+    pub fn create_compute_pipeline(&self) -> ConfigureComputePipeline {
+        ConfigureComputePipeline::new(
+            &self.device,
+            self.pipeline_cache.handle(),
+            self.limits.max_push_constants_size,
+        )
+    }
+
+    /// The cache every pipeline created by the device is added to
+    pub fn pipeline_cache(&self) -> &PipelineCache {
+        &self.pipeline_cache
+    }
+
+    /// Writes the pipeline cache to its file now rather than waiting for the device to be destroyed
+    pub fn save_pipeline_cache(&self) -> Result<(), Error> {
+        self.pipeline_cache.save(&self.device)
+    }
+
+    /// Start configuring the attachments and subpasses of a renderpass
+    pub fn create_renderpass(&self) -> ConfigureRenderpass<'_> {
+        ConfigureRenderpass::new(self)
+    }
+
+    /// Creates a framebuffer of any size for rendering offscreen, there must be a view for each attachment of
+    /// the renderpass in the order they were added and each view must be at least as large as the framebuffer
+    pub fn create_framebuffer(
+        &self,
+        renderpass: &Renderpass,
+        attachments: &[&ImageView],
+        width: u32,
+        height: u32,
+    ) -> Result<Framebuffer, Error> {
+        Framebuffer::new(
+            self,
+            renderpass,
+            attachments,
+            vk::Extent2D { width, height },
+        )
+    }
+
+    /// Creates offscreen colour and depth images at the largest supported sample count up to samples, a
+    /// multisampled target is resolved to a single sampled image
+    pub fn create_render_target(
+        &self,
+        width: u32,
+        height: u32,
+        color_format: vk::Format,
+        depth_format: Option<vk::Format>,
+        samples: vk::SampleCountFlagBits,
+    ) -> Result<RenderTarget, Error> {
+        RenderTarget::new(
+            self,
+            vk::Extent2D { width, height },
+            color_format,
+            depth_format,
+            samples,
+        )
+    }
+
+    /// The largest sample count up to requested that colour attachments support, and depth attachments as well
+    /// when with_depth is set
+    pub fn supported_sample_count(
+        &self,
+        requested: vk::SampleCountFlagBits,
+        with_depth: bool,
+    ) -> vk::SampleCountFlagBits {
+        let mut counts = self.limits.framebuffer_color_sample_counts;
+        if with_depth {
+            counts &= self.limits.framebuffer_depth_sample_counts;
+        }
+        crate::renderpass::cap_sample_count(counts, requested)
+    }
+
+    pub fn limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.limits
+    }
+
+    /// The features the device supports for an optimally tiled image of the given format,
+    /// only the common texture, swapchain and depth formats are queried when the device is created
+    pub fn optimal_tiling_features(&self, format: vk::Format) -> vk::FormatFeatureFlags {
+        self.format_properties
+            .get(&format)
+            .map(|properties| properties.optimal_tiling_features)
+            .unwrap_or_else(vk::FormatFeatureFlags::empty)
+    }
+
+    /// Returns true if the feature was enabled when the device was configured
+    pub fn feature_enabled(&self, feature: &Features) -> bool {
+        feature.is_set_in(&self.enabled_features, &self.enabled_vulkan12_features)
+    }
+
+    /// Finds a memory type that is allowed by type_bits and has all the requested properties
+    pub fn find_memory_type(
+        &self,
+        type_bits: u32,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Option<u32> {
+        let memory_types = &self.memory_properties.memory_types
+            [..self.memory_properties.memory_type_count as usize];
+        memory_types
+            .iter()
+            .enumerate()
+            .find(|(index, memory_type)| {
+                type_bits & (1 << index) != 0 && memory_type.property_flags.contains(properties)
+            })
+            .map(|(index, _)| index as u32)
+    }
+
+    /// Allocates a block of memory that satisfies the requirements of a buffer or image
+    pub fn allocate_memory(
+        &self,
+        requirements: &vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Result<vk::DeviceMemory, Error> {
+        let memory_type = self
+            .find_memory_type(requirements.memory_type_bits, properties)
+            .ok_or_else(|| Error::new(ErrorKind::NoSuitableMemoryType(properties), None))?;
+        let allocate_info = vk::MemoryAllocateInfoBuilder::new()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type);
+        let memory = unsafe { self.device.allocate_memory(&allocate_info, None, None) }.result()?;
+        Ok(memory)
+    }
+
+    /// Start configuring a 2D, array or cube image
+    pub fn create_image(&self) -> ConfigureImage<'_> {
+        ConfigureImage::new(self)
+    }
+
+    /// Creates a view of an image, by default the view covers the entire image using the images format
+    pub fn create_image_view<F>(&self, image: &Image, configure_view: F) -> Result<ImageView, Error>
+    where
+        F: Fn(&mut ImageViewSettings),
+    {
+        let mut settings = ImageViewSettings::new(image);
+        configure_view(&mut settings);
+        settings.create_view(self)
+    }
+
+    /// Creates a sampler, anisotropic filtering requires the SamplerAnisotropy feature to be enabled
+    pub fn create_sampler<F>(&self, configure_sampler: F) -> Result<Sampler, Error>
+    where
+        F: Fn(&mut SamplerSettings),
+    {
+        let mut settings = SamplerSettings::new();
+        configure_sampler(&mut settings);
+        settings.create_sampler(self)
+    }
+
+    /// Start configuring the bindings of a descriptor set layout
+    pub fn create_descriptor_set_layout(&self) -> ConfigureDescriptorSetLayout<'_> {
+        ConfigureDescriptorSetLayout::new(self)
+    }
+
+    /// Creates a growable pool that can allocate sets using any of the layouts
+    pub fn create_descriptor_pool(
+        &self,
+        lifetime: PoolLifetime,
+        layouts: &[&DescriptorSetLayout],
+        sets_per_block: u32,
+    ) -> Result<DescriptorPool, Error> {
+        DescriptorPool::new(self, lifetime, layouts, sets_per_block)
+    }
+
+    /// Retrieves the handle of a queue that was created along with the device
+    pub fn get_queue(&self, queue: &OperationQueue) -> vk::Queue {
+        unsafe {
+            self.device
+                .get_device_queue(queue.family_index(), queue.get_queue(), None)
+        }
+    }
+
+    pub fn create_buffer(
+        &self,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        memory_properties: vk::MemoryPropertyFlags,
+    ) -> Result<Buffer, Error> {
+        Buffer::new(self, size, usage, memory_properties)
+    }
+
+    /// Creates a pool for command buffers that will be submitted to the given queue
+    pub fn create_command_pool(
+        &self,
+        queue: &OperationQueue,
+        flags: vk::CommandPoolCreateFlags,
+    ) -> Result<CommandPool, Error> {
+        CommandPool::new(self, queue, flags)
+    }
+
+    /// Records a one time command buffer, submits it to the queue and blocks until it has finished executing
+    pub fn submit_and_wait<F>(&self, queue: &OperationQueue, record: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut CommandRecorder) -> Result<(), Error>,
+    {
+        let pool = self.create_command_pool(queue, vk::CommandPoolCreateFlags::TRANSIENT)?;
+        let result = self.record_and_submit(&pool, queue, record);
+        pool.destroy(self);
+        result
+    }
+
+    fn record_and_submit<F>(
+        &self,
+        pool: &CommandPool,
+        queue: &OperationQueue,
+        record: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut CommandRecorder) -> Result<(), Error>,
+    {
+        let command_buffer = pool.allocate(self, 1)?[0];
+        let begin_info = vk::CommandBufferBeginInfoBuilder::new()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe {
+            self.device
+                .begin_command_buffer(command_buffer, &begin_info)
+        }
+        .result()?;
+        record(&mut CommandRecorder::new(self, command_buffer))?;
+        unsafe { self.device.end_command_buffer(command_buffer) }.result()?;
+        let fence_info = vk::FenceCreateInfoBuilder::new();
+        let fence = unsafe { self.device.create_fence(&fence_info, None, None) }.result()?;
+        let command_buffers = [command_buffer];
+        let submit_info = vk::SubmitInfoBuilder::new().command_buffers(&command_buffers);
+        let result = unsafe {
+            self.device
+                .queue_submit(self.get_queue(queue), &[submit_info], Some(fence))
+                .result()
+                .and_then(|_| {
+                    self.device
+                        .wait_for_fences(&[fence], true, u64::MAX)
+                        .result()
+                })
+        };
+        unsafe { self.device.destroy_fence(Some(fence), None) };
+        result?;
+        Ok(())
+    }
+
+    /// Uploads a decoded image to a device local texture that can be sampled by fragment shaders,
+    /// the queue must support transfer and graphics operations and this blocks until the upload completes.
+    /// By default the texture has a single mip level and is filtered as smoothly as the device allows
+    pub fn create_texture<F>(
+        &self,
+        queue: &OperationQueue,
+        decoded: &DecodedImage,
+        configure_texture: F,
+    ) -> Result<Texture, Error>
+    where
+        F: Fn(&mut TextureSettings),
+    {
+        let mut settings = TextureSettings::new();
+        configure_texture(&mut settings);
+        Texture::upload(self, queue, decoded, settings)
+    }
+
+    /// Waits for the device to finish, writes the pipeline cache to its file and destroys the device,
+    /// everything created by the device must be destroyed first. The device is destroyed even if the cache
+    /// could not be written
+    pub fn destroy(self) -> Result<(), Error> {
+        let result = unsafe { self.device.device_wait_idle() }
+            .result()
+            .map_err(Error::from)
+            .and_then(|_| self.pipeline_cache.save(&self.device));
+        self.pipeline_cache.destroy(&self.device);
+        unsafe { self.device.destroy_device(None) };
+        result
     }
 }
 
@@ -10,11 +10,20 @@ impl Error {
     pub fn kind(&self) -> &ErrorKind {
         self.error.kind()
     }
+
+    /// Attach a description of what went wrong to the error
+    pub fn with_context(
+        mut self,
+        context: &'static (dyn DisplayDebug + Send + Sync + 'static),
+    ) -> Error {
+        self.error.context.add_context(context);
+        self
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{}", self.error.kind))
+        self.error.fmt(f)
     }
 }
 
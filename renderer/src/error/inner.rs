@@ -17,13 +17,19 @@ impl InnerError {
 impl std::fmt::Display for InnerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{}", self.kind))?;
-        self.context.fmt(f)
+        if self.context.display.is_some() {
+            f.write_str(": ")?;
+        }
+        std::fmt::Display::fmt(&self.context, f)
     }
 }
 
 impl std::fmt::Debug for InnerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{}", self.kind))?;
-        self.context.fmt(f)
+        if self.context.debug.is_some() {
+            f.write_str(": ")?;
+        }
+        std::fmt::Debug::fmt(&self.context, f)
     }
 }
@@ -31,13 +31,29 @@ impl PartialEq for ErrorKind {
             (ErrorKind::OutOfHostMemory, ErrorKind::OutOfHostMemory) => true,
             (ErrorKind::SurfaceLost, ErrorKind::SurfaceLost) => true,
             (
-                ErrorKind::SwapchainConfigurationMissing,
-                ErrorKind::SwapchainConfigurationMissing,
-            ) => true,
+                ErrorKind::SwapchainConfigurationMissing(step_a),
+                ErrorKind::SwapchainConfigurationMissing(step_b),
+            ) => step_a == step_b,
             (ErrorKind::VulkanApiError(error_a), ErrorKind::VulkanApiError(error_b)) => {
                 error_a.eq(error_b)
             }
             (ErrorKind::VulkanNotInstalled, ErrorKind::VulkanNotInstalled) => true,
+            (ErrorKind::InvalidImageConfig, ErrorKind::InvalidImageConfig) => true,
+            (ErrorKind::NoSuitableMemoryType(_), ErrorKind::NoSuitableMemoryType(_)) => true,
+            (ErrorKind::InvalidBufferAccess, ErrorKind::InvalidBufferAccess) => true,
+            (ErrorKind::ImageDecodeFailed(_), ErrorKind::ImageDecodeFailed(_)) => true,
+            (ErrorKind::InvalidDescriptorConfig, ErrorKind::InvalidDescriptorConfig) => true,
+            (ErrorKind::PipelineCacheWriteFailed(_), ErrorKind::PipelineCacheWriteFailed(_)) => {
+                true
+            }
+            (ErrorKind::ShaderReflectionFailed(_), ErrorKind::ShaderReflectionFailed(_)) => true,
+            (ErrorKind::ShaderInterfaceMismatch(_), ErrorKind::ShaderInterfaceMismatch(_)) => true,
+            (ErrorKind::ShaderCompileFailed(..), ErrorKind::ShaderCompileFailed(..)) => true,
+            (ErrorKind::InvalidRenderpassConfig, ErrorKind::InvalidRenderpassConfig) => true,
+            (ErrorKind::InvalidFramebuffer, ErrorKind::InvalidFramebuffer) => true,
+            (ErrorKind::InvalidRenderGraph, ErrorKind::InvalidRenderGraph) => true,
+            (ErrorKind::TileStorageFailed(_), ErrorKind::TileStorageFailed(_)) => true,
+            (ErrorKind::ShaderWatchFailed(_), ErrorKind::ShaderWatchFailed(_)) => true,
             _ => false,
         }
     }
@@ -88,9 +104,8 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::InitializationFailed => {
                 f.write_fmt(format_args!("Failed to initialize Vulkan"))
             },
-            ErrorKind::SwapchainConfigurationMissing => {
-                // TODO: Can we pass more info here
-                f.write_fmt(format_args!("ConfigureSwapchain was missing which is a required component"))
+            ErrorKind::SwapchainConfigurationMissing(step) => {
+                f.write_fmt(format_args!("{} was not called before creating the presenter", step))
             },
             ErrorKind::InvalidPipelineConfig => {
                 // TODO: Pipelines should have names associated with them
@@ -116,7 +131,21 @@ impl std::fmt::Display for ErrorKind {
                 }
             },
             ErrorKind::VulkanNotInstalled => f.write_fmt(format_args!("Failed to load the Vulkan library, is Vulkan not installed?")),
-            ErrorKind::InvalidShaderEntryMethodName(name) => f.write_fmt(format_args!("An invalid C string was used as the entry name for a shader, name was {:?}", name))
+            ErrorKind::InvalidShaderEntryMethodName(name) => f.write_fmt(format_args!("An invalid C string was used as the entry name for a shader, name was {:?}", name)),
+            ErrorKind::InvalidImageConfig => f.write_fmt(format_args!("The image being created was configured incorrectly")),
+            ErrorKind::NoSuitableMemoryType(properties) => f.write_fmt(format_args!("No memory type with the properties {:?} could be found", properties)),
+            ErrorKind::InvalidBufferAccess => f.write_fmt(format_args!("The buffer was accessed incorrectly")),
+            ErrorKind::ImageDecodeFailed(message) => f.write_fmt(format_args!("Failed to decode the image: {}", message)),
+            ErrorKind::InvalidDescriptorConfig => f.write_fmt(format_args!("A descriptor set layout, pool or write was configured incorrectly")),
+            ErrorKind::PipelineCacheWriteFailed(message) => f.write_fmt(format_args!("Failed to write the pipeline cache: {}", message)),
+            ErrorKind::ShaderReflectionFailed(message) => f.write_fmt(format_args!("Failed to reflect the shader: {}", message)),
+            ErrorKind::ShaderInterfaceMismatch(message) => f.write_fmt(format_args!("The shaders do not match the pipeline: {}", message)),
+            ErrorKind::ShaderCompileFailed(file, line, message) => f.write_fmt(format_args!("Failed to compile {}:{}: {}", file, line, message)),
+            ErrorKind::InvalidRenderpassConfig => f.write_fmt(format_args!("The attachments, subpasses or dependencies of the renderpass were configured incorrectly")),
+            ErrorKind::InvalidFramebuffer => f.write_fmt(format_args!("The image views can't be used as a framebuffer of the renderpass")),
+            ErrorKind::InvalidRenderGraph => f.write_fmt(format_args!("The passes of the render graph use their resources incorrectly")),
+            ErrorKind::TileStorageFailed(message) => f.write_fmt(format_args!("Failed to store the tiles of the image: {}", message)),
+            ErrorKind::ShaderWatchFailed(message) => f.write_fmt(format_args!("Shader files won't be reloaded when they change: {}", message)),
         }
     }
 }
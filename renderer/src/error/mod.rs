@@ -53,10 +53,33 @@ pub enum ErrorKind {
     NoDevicesFound,
     /// You're unlikely to see this error as its handled by the renderer
     SurfaceLost,
-    InitializationFailed,          // Vulkan initialization failed
-    SwapchainConfigurationMissing, // TODO: Can we pass Swapchain Configuration here?
+    InitializationFailed, // Vulkan initialization failed
+    /// The presenter was created without calling a required select step, contains the name of the step
+    SwapchainConfigurationMissing(&'static str),
     InvalidPipelineConfig, // Is the best we can do a context message about what was misconfigured
     FailedToGetDeviceExtensions(Option<String>),
     VulkanNotInstalled,
     InvalidShaderEntryMethodName(Vec<u8>),
+    InvalidImageConfig,
+    NoSuitableMemoryType(erupt::vk1_0::MemoryPropertyFlags),
+    InvalidBufferAccess,
+    /// The image could not be read or decoded, contains the message from the decoder
+    ImageDecodeFailed(String),
+    InvalidDescriptorConfig,
+    /// The pipeline cache file could not be written, contains the message from the file system
+    PipelineCacheWriteFailed(String),
+    /// The SPIR-V of a shader could not be reflected, contains what was wrong with it
+    ShaderReflectionFailed(String),
+    /// A shader reads something the pipeline does not provide, contains the mismatch
+    ShaderInterfaceMismatch(String),
+    /// A shader failed to compile, contains the file, the line of the first error and the message from the compiler
+    ShaderCompileFailed(String, u32, String),
+    InvalidRenderpassConfig,
+    /// The views of a framebuffer don't match the renderpass or are smaller than the framebuffer
+    InvalidFramebuffer,
+    InvalidRenderGraph,
+    /// The tiles of a tiled image could not be written to or read back from disk, contains the message from the file system
+    TileStorageFailed(String),
+    /// Shader files can't be watched for changes, contains the message from the watcher
+    ShaderWatchFailed(String),
 }
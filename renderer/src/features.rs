@@ -1,9 +1,40 @@
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+use erupt::ExtendableFrom;
+
 #[derive(Clone, Copy)]
 pub enum Features {
     /// Enables the geoetry shader stage in the graphis pipeline
     GeometryShader,
     /// Enables the tesselation shader stage in the graphics pipeline
     TesselationShader,
+    /// Enables anisotropic filtering in samplers
+    SamplerAnisotropy,
+    /// Enables partially bound, update after bind arrays of sampled images that shaders can index freely,
+    /// requires a Vulkan 1.2 instance and device
+    DescriptorIndexing,
+    /// Enables line widths other than 1.0
+    WideLines,
+    /// Enables drawing polygons as lines or points, ie a wireframe
+    FillModeNonSolid,
+    /// Enables discarding fragments outside of a range of depths in the depth buffer
+    DepthBounds,
+    /// Enables clamping depth to the viewport instead of clipping primitives
+    DepthClamp,
+    /// Enables limiting the depth bias added to fragments
+    DepthBiasClamp,
+    /// Enables combining colour attachments with a logical operation instead of blending
+    LogicOp,
+    /// Enables running the fragment shader for each sample of a multisampled attachment
+    SampleRateShading,
+    /// Enables replacing the alpha of fragments with one after multisample coverage is calculated
+    AlphaToOne,
+    /// Enables blend factors that use a second colour output of the fragment shader
+    DualSrcBlend,
+    /// Enables pipelines with more than one viewport, ie to compare images side by side
+    MultiViewport,
+    /// Enables blending each colour attachment of a subpass differently
+    IndependentBlend,
 }
 
 impl std::fmt::Debug for Features {
@@ -11,6 +42,19 @@ impl std::fmt::Debug for Features {
         match self {
             Features::GeometryShader => f.write_str("Geometry Shader"),
             Features::TesselationShader => f.write_str("Tesselation Shader"),
+            Features::SamplerAnisotropy => f.write_str("Sampler Anisotropy"),
+            Features::DescriptorIndexing => f.write_str("Descriptor Indexing"),
+            Features::WideLines => f.write_str("Wide Lines"),
+            Features::FillModeNonSolid => f.write_str("Fill Mode Non Solid"),
+            Features::DepthBounds => f.write_str("Depth Bounds"),
+            Features::DepthClamp => f.write_str("Depth Clamp"),
+            Features::DepthBiasClamp => f.write_str("Depth Bias Clamp"),
+            Features::LogicOp => f.write_str("Logic Op"),
+            Features::SampleRateShading => f.write_str("Sample Rate Shading"),
+            Features::AlphaToOne => f.write_str("Alpha To One"),
+            Features::DualSrcBlend => f.write_str("Dual Source Blend"),
+            Features::MultiViewport => f.write_str("Multi Viewport"),
+            Features::IndependentBlend => f.write_str("Independent Blend"),
         }
     }
 }
@@ -20,7 +64,136 @@ impl std::fmt::Display for Features {
         match self {
             Features::GeometryShader => f.write_str("Geometry Shader"),
             Features::TesselationShader => f.write_str("Tesselation Shader"),
+            Features::SamplerAnisotropy => f.write_str("Sampler Anisotropy"),
+            Features::DescriptorIndexing => f.write_str("Descriptor Indexing"),
+            Features::WideLines => f.write_str("Wide Lines"),
+            Features::FillModeNonSolid => f.write_str("Fill Mode Non Solid"),
+            Features::DepthBounds => f.write_str("Depth Bounds"),
+            Features::DepthClamp => f.write_str("Depth Clamp"),
+            Features::DepthBiasClamp => f.write_str("Depth Bias Clamp"),
+            Features::LogicOp => f.write_str("Logic Op"),
+            Features::SampleRateShading => f.write_str("Sample Rate Shading"),
+            Features::AlphaToOne => f.write_str("Alpha To One"),
+            Features::DualSrcBlend => f.write_str("Dual Source Blend"),
+            Features::MultiViewport => f.write_str("Multi Viewport"),
+            Features::IndependentBlend => f.write_str("Independent Blend"),
+        }
+    }
+}
+
+impl Features {
+    // The flag of the feature in the Vulkan 1.0 feature structure, none for features that are enabled through
+    // the Vulkan 1.2 features
+    pub(crate) fn device_feature(
+        self,
+        features: &mut vk::PhysicalDeviceFeatures,
+    ) -> Option<&mut vk::Bool32> {
+        match self {
+            Features::GeometryShader => Some(&mut features.geometry_shader),
+            Features::TesselationShader => Some(&mut features.tessellation_shader),
+            Features::SamplerAnisotropy => Some(&mut features.sampler_anisotropy),
+            Features::DescriptorIndexing => None,
+            Features::WideLines => Some(&mut features.wide_lines),
+            Features::FillModeNonSolid => Some(&mut features.fill_mode_non_solid),
+            Features::DepthBounds => Some(&mut features.depth_bounds),
+            Features::DepthClamp => Some(&mut features.depth_clamp),
+            Features::DepthBiasClamp => Some(&mut features.depth_bias_clamp),
+            Features::LogicOp => Some(&mut features.logic_op),
+            Features::SampleRateShading => Some(&mut features.sample_rate_shading),
+            Features::AlphaToOne => Some(&mut features.alpha_to_one),
+            Features::DualSrcBlend => Some(&mut features.dual_src_blend),
+            Features::MultiViewport => Some(&mut features.multi_viewport),
+            Features::IndependentBlend => Some(&mut features.independent_blend),
+        }
+    }
+
+    // Returns true if the feature is set in the given Vulkan feature structure
+    pub(crate) fn is_set_in(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        vulkan12_features: &Vulkan12Features,
+    ) -> bool {
+        let mut features = *features;
+        match self.device_feature(&mut features) {
+            Some(enabled) => *enabled > 0,
+            None => vulkan12_features.descriptor_indexing > 0,
+        }
+    }
+}
+
+// The parts of Vulkan12Features that the renderer uses, copied out of the Vulkan structure so that
+// no pointer chain is kept around
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct Vulkan12Features {
+    pub(crate) descriptor_indexing: vk::Bool32,
+    pub(crate) sampled_image_non_uniform_indexing: vk::Bool32,
+    pub(crate) sampled_image_update_after_bind: vk::Bool32,
+    pub(crate) update_unused_while_pending: vk::Bool32,
+    pub(crate) partially_bound: vk::Bool32,
+    pub(crate) runtime_descriptor_array: vk::Bool32,
+    // The largest update after bind sampled image array a single stage can access
+    pub(crate) max_update_after_bind_sampled_images: u32,
+    // The most update after bind samplers a single stage can access, combined image samplers count against both
+    pub(crate) max_update_after_bind_samplers: u32,
+}
+
+impl Vulkan12Features {
+    // Queries the Vulkan 1.2 features of a device, nothing is supported unless both the instance and
+    // the device were created for Vulkan 1.2 or later
+    pub(crate) fn query(
+        instance: &erupt::InstanceLoader,
+        physical_device: vk::PhysicalDevice,
+        device_api_version: u32,
+    ) -> Vulkan12Features {
+        if !instance.enabled().vk1_2 || device_api_version < vk::make_version(1, 2, 0) {
+            return Vulkan12Features::default();
         }
+        let mut features12 = vk1_2::PhysicalDeviceVulkan12FeaturesBuilder::new();
+        let features =
+            erupt::vk1_1::PhysicalDeviceFeatures2Builder::new().extend_from(&mut features12);
+        unsafe { instance.get_physical_device_features2(physical_device, Some(*features)) };
+        let mut properties12 = vk1_2::PhysicalDeviceVulkan12PropertiesBuilder::new();
+        let properties =
+            erupt::vk1_1::PhysicalDeviceProperties2Builder::new().extend_from(&mut properties12);
+        unsafe { instance.get_physical_device_properties2(physical_device, Some(*properties)) };
+        Vulkan12Features {
+            descriptor_indexing: features12.descriptor_indexing,
+            sampled_image_non_uniform_indexing: features12
+                .shader_sampled_image_array_non_uniform_indexing,
+            sampled_image_update_after_bind: features12
+                .descriptor_binding_sampled_image_update_after_bind,
+            update_unused_while_pending: features12.descriptor_binding_update_unused_while_pending,
+            partially_bound: features12.descriptor_binding_partially_bound,
+            runtime_descriptor_array: features12.runtime_descriptor_array,
+            max_update_after_bind_sampled_images: properties12
+                .max_per_stage_descriptor_update_after_bind_sampled_images
+                .min(properties12.max_descriptor_set_update_after_bind_sampled_images),
+            max_update_after_bind_samplers: properties12
+                .max_per_stage_descriptor_update_after_bind_samplers
+                .min(properties12.max_descriptor_set_update_after_bind_samplers),
+        }
+    }
+
+    // True if everything a bindless texture table needs is supported
+    pub(crate) fn has_descriptor_indexing(&self) -> bool {
+        self.descriptor_indexing > 0
+            && self.sampled_image_non_uniform_indexing > 0
+            && self.sampled_image_update_after_bind > 0
+            && self.update_unused_while_pending > 0
+            && self.partially_bound > 0
+            && self.runtime_descriptor_array > 0
+    }
+
+    // Builds the structure passed to device creation, descriptor indexing enables all the features it depends on
+    pub(crate) fn to_vulkan(self) -> vk1_2::PhysicalDeviceVulkan12Features {
+        let enabled = self.descriptor_indexing;
+        *vk1_2::PhysicalDeviceVulkan12FeaturesBuilder::new()
+            .descriptor_indexing(enabled > 0)
+            .shader_sampled_image_array_non_uniform_indexing(enabled > 0)
+            .descriptor_binding_sampled_image_update_after_bind(enabled > 0)
+            .descriptor_binding_update_unused_while_pending(enabled > 0)
+            .descriptor_binding_partially_bound(enabled > 0)
+            .runtime_descriptor_array(enabled > 0)
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::{DeviceExtensions, Features, Gpu, PciVendor, QueueFamily, Version};
+use crate::{DeviceExtensions, Features, Gpu, PciVendor, QueueFamily, Version, Vulkan12Features};
 use erupt::extensions::khr_surface as surface;
 use erupt::vk1_0 as vk;
 use std::ffi::CStr;
@@ -8,12 +8,13 @@ use std::ffi::CStr;
 // and must take ownership of a borrowed value
 
 impl Gpu {
-    pub fn new(
+    pub(crate) fn new(
         physical_device: vk::PhysicalDevice,
         properties: vk::PhysicalDeviceProperties,
         device_queues: Vec<QueueFamily>,
         available_extensions: Vec<vk::ExtensionProperties>,
         device_features: vk::PhysicalDeviceFeatures,
+        vulkan12_features: Vulkan12Features,
         surface_capabilities: surface::SurfaceCapabilitiesKHR,
         surface_formats: Vec<surface::SurfaceFormatKHR>,
         present_modes: Vec<surface::PresentModeKHR>,
@@ -30,6 +31,8 @@ impl Gpu {
             driver_version: properties.driver_version,
             available_extensions,
             device_features,
+            vulkan12_features,
+            limits: properties.limits,
             surface_capabilities,
             surface_formats,
             present_modes,
@@ -53,8 +56,8 @@ impl Gpu {
 
     pub fn has_feature(&self, feature: &Features) -> bool {
         match feature {
-            Features::GeometryShader => self.device_features.geometry_shader > 0,
-            Features::TesselationShader => self.device_features.tessellation_shader > 0,
+            Features::DescriptorIndexing => self.vulkan12_features.has_descriptor_indexing(),
+            feature => feature.is_set_in(&self.device_features, &self.vulkan12_features),
         }
     }
 
@@ -127,15 +130,16 @@ impl Gpu {
     }
 
     pub fn feature(&mut self, feature: &Features) -> bool {
-        match feature {
-            Features::GeometryShader => self.device_features.geometry_shader > 0,
-            Features::TesselationShader => self.device_features.tessellation_shader > 0,
-        }
+        self.has_feature(feature)
     }
 
     pub fn get_features(&self) -> &vk::PhysicalDeviceFeatures {
         &self.device_features
     }
+
+    pub fn get_limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.limits
+    }
 }
 
 impl std::fmt::Debug for Gpu {
@@ -0,0 +1,114 @@
+use super::{MemoryBlock, ResourceId};
+use erupt::vk1_0 as vk;
+
+fn overlaps(a: (usize, usize), b: (usize, usize)) -> bool {
+    a.0 <= b.1 && b.0 <= a.1
+}
+
+/// Places transient images in blocks of memory, images share a block when none of the passes that use them
+/// overlap and they can use the same memory type. Each image is bound at the start of its block so the block
+/// is as large as its largest image. The lifetimes are the positions of the first and last passes using each image
+pub(super) fn assign_memory(
+    images: &[(ResourceId, vk::MemoryRequirements, (usize, usize))],
+) -> Vec<MemoryBlock> {
+    // Placing the largest images first keeps the smaller images from growing the blocks
+    let mut sorted: Vec<&(ResourceId, vk::MemoryRequirements, (usize, usize))> =
+        images.iter().collect();
+    sorted.sort_by(|a, b| b.1.size.cmp(&a.1.size).then(a.0.cmp(&b.0)));
+    let mut blocks: Vec<MemoryBlock> = Vec::new();
+    let mut block_lifetimes: Vec<Vec<(usize, usize)>> = Vec::new();
+    for (id, requirements, lifetime) in sorted {
+        let shared = blocks
+            .iter()
+            .zip(block_lifetimes.iter())
+            .position(|(block, lifetimes)| {
+                block.memory_type_bits & requirements.memory_type_bits != 0
+                    && !lifetimes.iter().any(|other| overlaps(*other, *lifetime))
+            });
+        match shared {
+            Some(index) => {
+                let block = &mut blocks[index];
+                block.size = block.size.max(requirements.size);
+                block.alignment = block.alignment.max(requirements.alignment);
+                block.memory_type_bits &= requirements.memory_type_bits;
+                block.images.push(*id);
+                block_lifetimes[index].push(*lifetime);
+            }
+            None => {
+                blocks.push(MemoryBlock {
+                    size: requirements.size,
+                    alignment: requirements.alignment,
+                    memory_type_bits: requirements.memory_type_bits,
+                    images: vec![*id],
+                });
+                block_lifetimes.push(vec![*lifetime]);
+            }
+        }
+    }
+    blocks
+}
+
+/// The image that used the memory of each image before it, the first use of an image waits for the last use
+/// of the image it replaces
+pub(super) fn aliases(
+    blocks: &[MemoryBlock],
+    lifetimes: &[Option<(usize, usize)>],
+) -> Vec<Option<ResourceId>> {
+    let mut aliases = vec![None; lifetimes.len()];
+    for block in blocks {
+        for image in block.images.iter() {
+            let start = match lifetimes[image.0] {
+                Some((start, _)) => start,
+                None => continue,
+            };
+            aliases[image.0] = block
+                .images
+                .iter()
+                .filter_map(|other| lifetimes[other.0].map(|lifetime| (*other, lifetime)))
+                .filter(|(_, (_, end))| *end < start)
+                .max_by_key(|(_, (_, end))| *end)
+                .map(|(other, _)| other);
+        }
+    }
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirements(size: vk::DeviceSize, memory_type_bits: u32) -> vk::MemoryRequirements {
+        vk::MemoryRequirements {
+            size,
+            alignment: 256,
+            memory_type_bits,
+        }
+    }
+
+    #[test]
+    fn test_assign_memory() {
+        // The first two images are used by different passes, the third overlaps both
+        let images = [
+            (ResourceId(0), requirements(1024, 0b11), (0, 1)),
+            (ResourceId(1), requirements(4096, 0b01), (2, 3)),
+            (ResourceId(2), requirements(2048, 0b11), (1, 2)),
+        ];
+        let blocks = assign_memory(&images);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].images, vec![ResourceId(1), ResourceId(0)]);
+        assert_eq!(blocks[0].size, 4096);
+        assert_eq!(blocks[0].memory_type_bits, 0b01);
+        assert_eq!(blocks[1].images, vec![ResourceId(2)]);
+
+        let lifetimes = vec![Some((0, 1)), Some((2, 3)), Some((1, 2))];
+        let aliases = aliases(&blocks, &lifetimes);
+        assert_eq!(aliases, vec![None, Some(ResourceId(0)), None]);
+
+        // Images that can't share a memory type are never aliased
+        let incompatible = [
+            (ResourceId(0), requirements(1024, 0b01), (0, 0)),
+            (ResourceId(1), requirements(1024, 0b10), (1, 1)),
+        ];
+        assert_eq!(assign_memory(&incompatible).len(), 2);
+    }
+}
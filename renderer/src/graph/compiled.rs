@@ -0,0 +1,173 @@
+use super::{CompiledGraph, GraphBarrier, GraphResource, GraphResources, ResourceId};
+use crate::error::{Error, ErrorKind};
+use crate::{CommandRecorder, ImageView, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl<'a> CompiledGraph<'a> {
+    /// Records every pass in order along with the barriers before each pass and the transitions of the
+    /// imported images into their final layouts
+    pub fn record(&mut self, recorder: &mut CommandRecorder) {
+        for pass in self.passes.iter_mut() {
+            self.resources.record_barriers(recorder, &pass.barriers);
+            (pass.record)(recorder, &self.resources);
+        }
+        self.resources
+            .record_barriers(recorder, &self.final_barriers);
+    }
+
+    /// The names of the passes that are recorded in the order they are recorded, culled passes are not included
+    pub fn pass_names(&self) -> Vec<&str> {
+        self.passes.iter().map(|pass| pass.name.as_str()).collect()
+    }
+
+    pub fn resources(&self) -> &GraphResources {
+        &self.resources
+    }
+
+    /// Replaces an imported image with another image of the same format, ie the swapchain image acquired
+    /// this frame, so that the graph doesn't need to be compiled again
+    pub fn replace_imported_image(
+        &mut self,
+        resource: ResourceId,
+        view: &ImageView,
+    ) -> Result<(), Error> {
+        let image = match self.resources.resources.get_mut(resource.0) {
+            Some(GraphResource::Image(image)) if image.format == view.format() => image,
+            _ => {
+                return Err(
+                    Error::new(ErrorKind::InvalidRenderGraph, None).with_context(
+                        &"Only an imported image can be replaced by a view of the same format",
+                    ),
+                )
+            }
+        };
+        match image.imported.as_mut() {
+            Some(imported) => {
+                imported.image = view.image();
+                imported.view = view.handle();
+                imported.subresource_range = view.subresource_range();
+                Ok(())
+            }
+            None => Err(
+                Error::new(ErrorKind::InvalidRenderGraph, None).with_context(
+                    &"Only an imported image can be replaced by a view of the same format",
+                ),
+            ),
+        }
+    }
+
+    /// Destroys the transient images and their memory, the graph must not be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        self.resources.destroy(device);
+    }
+}
+
+impl GraphResources {
+    /// The view of a transient image the graph created, None for imported images and images no pass used
+    pub fn image_view(&self, resource: ResourceId) -> Option<&ImageView> {
+        self.views.get(resource.0).and_then(|view| view.as_ref())
+    }
+
+    /// The view of any image of the graph, used to create framebuffers for a pass
+    pub fn view_handle(&self, resource: ResourceId) -> Option<vk::ImageView> {
+        match self.resources.get(resource.0) {
+            Some(GraphResource::Image(image)) => match image.imported {
+                Some(imported) => Some(imported.view),
+                None => self.image_view(resource).map(|view| view.handle()),
+            },
+            _ => None,
+        }
+    }
+
+    pub fn extent(&self, resource: ResourceId) -> Option<vk::Extent2D> {
+        match self.resources.get(resource.0) {
+            Some(GraphResource::Image(image)) => Some(image.extent),
+            _ => None,
+        }
+    }
+
+    pub fn buffer(&self, resource: ResourceId) -> Option<vk::Buffer> {
+        match self.resources.get(resource.0) {
+            Some(GraphResource::Buffer(buffer, _)) => Some(*buffer),
+            _ => None,
+        }
+    }
+
+    // The image and subresources a barrier applies to
+    fn image_range(&self, resource: ResourceId) -> Option<(vk::Image, vk::ImageSubresourceRange)> {
+        match self.resources.get(resource.0) {
+            Some(GraphResource::Image(image)) => match image.imported {
+                Some(imported) => Some((imported.image, imported.subresource_range)),
+                None => {
+                    let image = self.images[resource.0].as_ref()?;
+                    let view = self.views[resource.0].as_ref()?;
+                    Some((image.handle(), view.subresource_range()))
+                }
+            },
+            _ => None,
+        }
+    }
+
+    fn record_barriers(&self, recorder: &mut CommandRecorder, barriers: &[GraphBarrier]) {
+        if barriers.is_empty() {
+            return;
+        }
+        let mut src_stage = vk::PipelineStageFlags::empty();
+        let mut dst_stage = vk::PipelineStageFlags::empty();
+        let mut image_barriers = Vec::new();
+        let mut buffer_barriers = Vec::new();
+        for barrier in barriers {
+            src_stage |= barrier.src_stage;
+            dst_stage |= barrier.dst_stage;
+            if let Some((image, subresource_range)) = self.image_range(barrier.resource) {
+                image_barriers.push(
+                    vk::ImageMemoryBarrierBuilder::new()
+                        .src_access_mask(barrier.src_access)
+                        .dst_access_mask(barrier.dst_access)
+                        .old_layout(barrier.old_layout)
+                        .new_layout(barrier.new_layout)
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .image(image)
+                        .subresource_range(subresource_range),
+                );
+            } else if let Some(GraphResource::Buffer(buffer, size)) =
+                self.resources.get(barrier.resource.0)
+            {
+                buffer_barriers.push(
+                    vk::BufferMemoryBarrierBuilder::new()
+                        .src_access_mask(barrier.src_access)
+                        .dst_access_mask(barrier.dst_access)
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .buffer(*buffer)
+                        .offset(0)
+                        .size(*size),
+                );
+            }
+        }
+        unsafe {
+            recorder.device().handle().cmd_pipeline_barrier(
+                recorder.command_buffer(),
+                src_stage,
+                dst_stage,
+                None,
+                &[],
+                buffer_barriers.as_slice(),
+                image_barriers.as_slice(),
+            )
+        };
+    }
+
+    pub(super) fn destroy(self, device: &VulkanDevice) {
+        for view in self.views.into_iter().flatten() {
+            view.destroy(device);
+        }
+        for image in self.images.into_iter().flatten() {
+            image.destroy(device);
+        }
+        for memory in self.memory {
+            unsafe { device.handle().free_memory(Some(memory), None) };
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use super::alias::{aliases, assign_memory};
+use super::schedule::{barriers, lifetimes, schedule};
+use super::{
+    CompiledGraph, CompiledPass, GraphImage, GraphPass, GraphResource, GraphResources,
+    ImportedImage, MemoryBlock, PassSettings, RenderGraph, ResourceId, ResourceUsage,
+};
+use crate::error::{Error, ErrorKind};
+use crate::{Buffer, CommandRecorder, ImageView, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+fn invalid_graph(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidRenderGraph, None).with_context(context)
+}
+
+impl PassSettings {
+    pub fn new() -> PassSettings {
+        PassSettings::default()
+    }
+
+    /// Declares that the pass reads the resource, the pass runs after the passes that write it
+    pub fn read(&mut self, resource: ResourceId, usage: ResourceUsage) -> &mut Self {
+        self.uses.push((resource, usage, false));
+        self
+    }
+
+    /// Declares that the pass writes the resource, passes that read it afterwards run after this pass
+    pub fn write(&mut self, resource: ResourceId, usage: ResourceUsage) -> &mut Self {
+        self.uses.push((resource, usage, true));
+        self
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> RenderGraph<'a> {
+        RenderGraph::default()
+    }
+
+    /// Adds a transient image that the graph creates when it is compiled, images used by passes that don't
+    /// overlap share memory so the contents only last from the first pass that writes the image to the last
+    /// pass that reads it
+    pub fn create_image(&mut self, width: u32, height: u32, format: vk::Format) -> ResourceId {
+        self.resources.push(GraphResource::Image(GraphImage {
+            format,
+            extent: vk::Extent2D { width, height },
+            samples: vk::SampleCountFlagBits::_1,
+            imported: None,
+        }));
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds an image created outside the graph such as a swapchain image, the image is in the initial layout
+    /// before the first pass and is transitioned to the final layout after the last pass. The layout the image
+    /// is left in must be assumed by the image afterwards
+    pub fn import_image(
+        &mut self,
+        view: &ImageView,
+        extent: vk::Extent2D,
+        initial_layout: vk::ImageLayout,
+        final_layout: Option<vk::ImageLayout>,
+    ) -> ResourceId {
+        self.resources.push(GraphResource::Image(GraphImage {
+            format: view.format(),
+            extent,
+            samples: vk::SampleCountFlagBits::_1,
+            imported: Some(ImportedImage {
+                image: view.image(),
+                view: view.handle(),
+                subresource_range: view.subresource_range(),
+                initial_layout,
+                final_layout,
+            }),
+        }));
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds a buffer created outside the graph, passes that read it wait for passes that write it
+    pub fn import_buffer(&mut self, buffer: &Buffer) -> ResourceId {
+        self.resources
+            .push(GraphResource::Buffer(buffer.handle(), buffer.size()));
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds a pass that uses the resources declared by configure_pass, record is called with the pass's
+    /// barriers already recorded each time the compiled graph is recorded
+    pub fn add_pass<C, R>(&mut self, name: &str, configure_pass: C, record: R) -> &mut Self
+    where
+        C: FnOnce(&mut PassSettings),
+        R: FnMut(&mut CommandRecorder, &GraphResources) + 'a,
+    {
+        let mut settings = PassSettings::new();
+        configure_pass(&mut settings);
+        self.passes.push(GraphPass {
+            name: name.to_string(),
+            uses: settings.uses,
+            record: Box::new(record),
+        });
+        self
+    }
+
+    // Checks that each pass uses resources of this graph in a way that suits them
+    fn validate(&self) -> Result<(), Error> {
+        for pass in self.passes.iter() {
+            for (resource, usage, write) in pass.uses.iter().copied() {
+                let resource = self
+                    .resources
+                    .get(resource.0)
+                    .ok_or_else(|| invalid_graph(&"A pass uses a resource of another graph"))?;
+                let is_image = matches!(resource, GraphResource::Image(_));
+                if is_image != usage.is_image_usage() {
+                    return Err(invalid_graph(
+                        &"A pass uses an image as a buffer or a buffer as an image",
+                    ));
+                }
+                if write != usage.is_write() {
+                    return Err(invalid_graph(
+                        &"A pass reads a resource with a usage that writes or writes with a usage that reads",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Orders and culls the passes, creates the transient images that are used and works out the barriers
+    /// between the passes
+    pub fn compile(self, device: &VulkanDevice) -> Result<CompiledGraph<'a>, Error> {
+        self.validate()?;
+        let pass_uses: Vec<Vec<(ResourceId, ResourceUsage)>> = self
+            .passes
+            .iter()
+            .map(|pass| {
+                pass.uses
+                    .iter()
+                    .map(|(resource, usage, _)| (*resource, *usage))
+                    .collect()
+            })
+            .collect();
+        let order = schedule(pass_uses.as_slice(), self.resources.as_slice())?;
+        let lifetimes = lifetimes(order.as_slice(), pass_uses.as_slice(), self.resources.len());
+
+        let mut resources = GraphResources {
+            resources: self.resources.clone(),
+            images: (0..self.resources.len()).map(|_| None).collect(),
+            views: (0..self.resources.len()).map(|_| None).collect(),
+            memory: Vec::new(),
+        };
+        let blocks = match resources.create_images(device, &order, &pass_uses, &lifetimes) {
+            Ok(blocks) => blocks,
+            Err(error) => {
+                resources.destroy(device);
+                return Err(error);
+            }
+        };
+        let aliases = aliases(blocks.as_slice(), lifetimes.as_slice());
+        let (pass_barriers, final_barriers) = barriers(
+            order.as_slice(),
+            pass_uses.as_slice(),
+            self.resources.as_slice(),
+            aliases.as_slice(),
+        );
+
+        let mut graph_passes: Vec<Option<GraphPass<'a>>> =
+            self.passes.into_iter().map(Some).collect();
+        let passes = order
+            .iter()
+            .zip(pass_barriers)
+            .filter_map(|(index, barriers)| {
+                graph_passes[*index].take().map(|pass| CompiledPass {
+                    name: pass.name,
+                    barriers,
+                    record: pass.record,
+                })
+            })
+            .collect();
+        Ok(CompiledGraph {
+            passes,
+            final_barriers,
+            resources,
+        })
+    }
+}
+
+impl GraphResources {
+    // Creates the transient images used by the ordered passes and binds them to shared blocks of memory
+    fn create_images(
+        &mut self,
+        device: &VulkanDevice,
+        order: &[usize],
+        pass_uses: &[Vec<(ResourceId, ResourceUsage)>],
+        lifetimes: &[Option<(usize, usize)>],
+    ) -> Result<Vec<MemoryBlock>, Error> {
+        let mut requirements = Vec::new();
+        for (index, resource) in self.resources.iter().enumerate() {
+            let image = match resource {
+                GraphResource::Image(image) if image.imported.is_none() => image,
+                _ => continue,
+            };
+            let lifetime = match lifetimes[index] {
+                Some(lifetime) => lifetime,
+                None => continue,
+            };
+            let usage = order
+                .iter()
+                .flat_map(|pass| pass_uses[*pass].iter())
+                .filter(|(id, _)| id.0 == index)
+                .fold(vk::ImageUsageFlags::empty(), |flags, (_, usage)| {
+                    flags | usage.image_usage()
+                });
+            let (created, image_requirements) = device
+                .create_image()
+                .extent(image.extent.width, image.extent.height)
+                .format(image.format)
+                .samples(image.samples)
+                .usage(usage)
+                .build_unbound()?;
+            self.images[index] = Some(created);
+            requirements.push((ResourceId(index), image_requirements, lifetime));
+        }
+        let blocks = assign_memory(requirements.as_slice());
+        for block in blocks.iter() {
+            let block_requirements = vk::MemoryRequirements {
+                size: block.size,
+                alignment: block.alignment,
+                memory_type_bits: block.memory_type_bits,
+            };
+            let memory = device
+                .allocate_memory(&block_requirements, vk::MemoryPropertyFlags::DEVICE_LOCAL)?;
+            self.memory.push(memory);
+            for id in block.images.iter() {
+                if let Some(image) = &self.images[id.0] {
+                    image.bind_alias(device, memory, 0)?;
+                }
+            }
+        }
+        // Views can only be created once the images are bound to memory
+        for (image, view) in self.images.iter().zip(self.views.iter_mut()) {
+            if let Some(image) = image {
+                *view = Some(device.create_image_view(image, |_| {})?);
+            }
+        }
+        Ok(blocks)
+    }
+}
@@ -0,0 +1,127 @@
+mod alias;
+mod compiled;
+mod config;
+mod schedule;
+mod usage;
+
+use crate::{CommandRecorder, Image, ImageView};
+use erupt::vk1_0 as vk;
+
+/// Identifies an image or buffer of a render graph, returned when the resource is added to the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId(usize);
+
+/// How a pass uses a resource, determines the barrier and layout transition recorded before the pass.
+/// Passes that render with a renderpass should leave their attachments in the layout of the usage,
+/// which is what Attachment::new does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceUsage {
+    ColorAttachment,
+    DepthStencilAttachment,
+    /// Depth testing without writing depth, or sampling depth
+    DepthStencilReadOnly,
+    InputAttachment,
+    /// Sampled by a fragment or compute shader
+    Sampled,
+    StorageRead,
+    StorageWrite,
+    TransferSrc,
+    TransferDst,
+    VertexBuffer,
+    IndexBuffer,
+    UniformBuffer,
+}
+
+/// An image used by the graph, the graph creates and aliases the images that were not imported
+#[derive(Debug, Clone, Copy)]
+struct GraphImage {
+    format: vk::Format,
+    extent: vk::Extent2D,
+    samples: vk::SampleCountFlagBits,
+    imported: Option<ImportedImage>,
+}
+
+/// An image created outside the graph, ie a swapchain image
+#[derive(Debug, Clone, Copy)]
+struct ImportedImage {
+    image: vk::Image,
+    view: vk::ImageView,
+    subresource_range: vk::ImageSubresourceRange,
+    initial_layout: vk::ImageLayout,
+    // The layout the image is left in after the last pass, ie PRESENT_SRC_KHR
+    final_layout: Option<vk::ImageLayout>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GraphResource {
+    Image(GraphImage),
+    Buffer(vk::Buffer, vk::DeviceSize),
+}
+
+/// The resources a pass reads and writes
+#[derive(Debug, Clone, Default)]
+pub struct PassSettings {
+    // Whether each use was declared as a write
+    uses: Vec<(ResourceId, ResourceUsage, bool)>,
+}
+
+type RecordPass<'a> = Box<dyn FnMut(&mut CommandRecorder, &GraphResources) + 'a>;
+
+struct GraphPass<'a> {
+    name: String,
+    uses: Vec<(ResourceId, ResourceUsage, bool)>,
+    record: RecordPass<'a>,
+}
+
+/// Passes and the resources they read and write, compiling the graph orders the passes, creates the
+/// transient images and works out the barriers and layout transitions between the passes
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<GraphResource>,
+    passes: Vec<GraphPass<'a>>,
+}
+
+/// A barrier recorded before a pass, the layouts are ignored for buffers
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GraphBarrier {
+    resource: ResourceId,
+    src_stage: vk::PipelineStageFlags,
+    src_access: vk::AccessFlags,
+    dst_stage: vk::PipelineStageFlags,
+    dst_access: vk::AccessFlags,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+}
+
+/// A block of memory shared by transient images whose passes don't overlap
+#[derive(Debug, Clone, PartialEq)]
+struct MemoryBlock {
+    size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+    memory_type_bits: u32,
+    images: Vec<ResourceId>,
+}
+
+/// The images a graph created along with the handles of the imported resources, passed to each pass when
+/// it is recorded
+pub struct GraphResources {
+    resources: Vec<GraphResource>,
+    // Only transient images that are used by a pass are created
+    images: Vec<Option<Image>>,
+    views: Vec<Option<ImageView>>,
+    memory: Vec<vk::DeviceMemory>,
+}
+
+struct CompiledPass<'a> {
+    name: String,
+    barriers: Vec<GraphBarrier>,
+    record: RecordPass<'a>,
+}
+
+/// A render graph that is ready to be recorded, it can be recorded every frame until the transient images
+/// need to change
+pub struct CompiledGraph<'a> {
+    passes: Vec<CompiledPass<'a>>,
+    final_barriers: Vec<GraphBarrier>,
+    resources: GraphResources,
+}
@@ -0,0 +1,404 @@
+use super::usage::write_access;
+use super::{GraphBarrier, GraphResource, ResourceId, ResourceUsage};
+use crate::error::{Error, ErrorKind};
+use crate::image::access_for_layout;
+use erupt::vk1_0 as vk;
+use std::collections::BTreeSet;
+
+fn invalid_graph(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidRenderGraph, None).with_context(context)
+}
+
+/// Every usage of a resource by a pass combined into one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct PassUse {
+    pub stages: vk::PipelineStageFlags,
+    pub access: vk::AccessFlags,
+    pub layout: vk::ImageLayout,
+    pub write: bool,
+}
+
+/// Combines the usages of each resource a pass uses, a resource used in two layouts uses the general layout
+pub(super) fn combined_uses(uses: &[(ResourceId, ResourceUsage)]) -> Vec<(ResourceId, PassUse)> {
+    let mut combined: Vec<(ResourceId, PassUse)> = Vec::new();
+    for (resource, usage) in uses.iter().copied() {
+        let pass_use = PassUse {
+            stages: usage.stages(),
+            access: usage.access(),
+            layout: usage.layout(),
+            write: usage.is_write(),
+        };
+        match combined.iter_mut().find(|(id, _)| *id == resource) {
+            Some((_, existing)) => {
+                existing.stages |= pass_use.stages;
+                existing.access |= pass_use.access;
+                existing.write |= pass_use.write;
+                if existing.layout != pass_use.layout {
+                    existing.layout = vk::ImageLayout::GENERAL;
+                }
+            }
+            None => combined.push((resource, pass_use)),
+        }
+    }
+    combined
+}
+
+fn is_imported(resource: &GraphResource) -> bool {
+    match resource {
+        GraphResource::Image(image) => image.imported.is_some(),
+        GraphResource::Buffer(..) => true,
+    }
+}
+
+fn add_edge(edges: &mut [Vec<usize>], before: usize, after: usize) {
+    if before != after && !edges[before].contains(&after) {
+        edges[before].push(after);
+    }
+}
+
+/// Orders the passes so that each pass runs after the passes that write what it reads, passes whose writes
+/// never reach an imported resource are culled. Passes that write the same resource or read it before another
+/// pass writes it keep the order they were added in, a transient image can be read by a pass added before
+/// the pass that writes it. Returns the indices of the passes to run in order
+pub(super) fn schedule(
+    passes: &[Vec<(ResourceId, ResourceUsage)>],
+    resources: &[GraphResource],
+) -> Result<Vec<usize>, Error> {
+    let count = passes.len();
+    // The passes that must run after each pass
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); count];
+    // The passes whose writes each pass depends on
+    let mut producers: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let uses: Vec<Vec<(ResourceId, PassUse)>> =
+        passes.iter().map(|pass| combined_uses(pass)).collect();
+    for (index, resource) in resources.iter().enumerate() {
+        let id = ResourceId(index);
+        let mut last_writer: Option<usize> = None;
+        // Passes that read the resource since it was last written
+        let mut readers: Vec<usize> = Vec::new();
+        // Passes that read a transient image before any pass that writes it was added
+        let mut early_readers: Vec<usize> = Vec::new();
+        for (pass, pass_uses) in uses.iter().enumerate() {
+            let pass_use = match pass_uses.iter().find(|(resource, _)| *resource == id) {
+                Some((_, pass_use)) => pass_use,
+                None => continue,
+            };
+            if pass_use.write {
+                match last_writer {
+                    Some(writer) => {
+                        add_edge(&mut edges, writer, pass);
+                        producers[pass].push(writer);
+                    }
+                    None => {
+                        for reader in early_readers.drain(..) {
+                            add_edge(&mut edges, pass, reader);
+                            producers[reader].push(pass);
+                        }
+                    }
+                }
+                for reader in readers.drain(..) {
+                    add_edge(&mut edges, reader, pass);
+                }
+                last_writer = Some(pass);
+            } else {
+                match last_writer {
+                    Some(writer) => {
+                        add_edge(&mut edges, writer, pass);
+                        producers[pass].push(writer);
+                        readers.push(pass);
+                    }
+                    // Reads the contents the resource had before the graph
+                    None if is_imported(resource) => readers.push(pass),
+                    None => early_readers.push(pass),
+                }
+            }
+        }
+        if !early_readers.is_empty() {
+            return Err(invalid_graph(
+                &"A pass reads a transient image that no pass writes",
+            ));
+        }
+    }
+
+    // Keep the passes that write imported resources or have no writes the graph knows about, along with
+    // every pass they depend on
+    let mut kept = vec![false; count];
+    let mut stack: Vec<usize> = (0..count)
+        .filter(|pass| {
+            let writes: Vec<ResourceId> = uses[*pass]
+                .iter()
+                .filter(|(_, pass_use)| pass_use.write)
+                .map(|(id, _)| *id)
+                .collect();
+            writes.is_empty() || writes.iter().any(|id| is_imported(&resources[id.0]))
+        })
+        .collect();
+    while let Some(pass) = stack.pop() {
+        if !kept[pass] {
+            kept[pass] = true;
+            stack.extend(producers[pass].iter().copied());
+        }
+    }
+
+    // Sort the kept passes, when several passes are ready the one added first runs first
+    let mut incoming = vec![0; count];
+    for pass in (0..count).filter(|pass| kept[*pass]) {
+        for next in edges[pass].iter().filter(|next| kept[**next]) {
+            incoming[*next] += 1;
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..count)
+        .filter(|pass| kept[*pass] && incoming[*pass] == 0)
+        .collect();
+    let mut order = Vec::new();
+    while let Some(pass) = ready.iter().next().copied() {
+        ready.remove(&pass);
+        order.push(pass);
+        for next in edges[pass].iter().filter(|next| kept[**next]) {
+            incoming[*next] -= 1;
+            if incoming[*next] == 0 {
+                ready.insert(*next);
+            }
+        }
+    }
+    if order.len() != kept.iter().filter(|kept| **kept).count() {
+        return Err(invalid_graph(&"The passes depend on each other in a cycle"));
+    }
+    Ok(order)
+}
+
+/// The positions in the order of the first and last passes that use each resource, None if no pass uses it
+pub(super) fn lifetimes(
+    order: &[usize],
+    passes: &[Vec<(ResourceId, ResourceUsage)>],
+    resource_count: usize,
+) -> Vec<Option<(usize, usize)>> {
+    let mut lifetimes = vec![None; resource_count];
+    for (position, pass) in order.iter().enumerate() {
+        for (id, _) in passes[*pass].iter() {
+            lifetimes[id.0] = match lifetimes[id.0] {
+                Some((first, _)) => Some((first, position)),
+                None => Some((position, position)),
+            };
+        }
+    }
+    lifetimes
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ResourceState {
+    layout: vk::ImageLayout,
+    stages: vk::PipelineStageFlags,
+    access: vk::AccessFlags,
+    written: bool,
+}
+
+/// The barriers to record before each pass in the order and after the last pass. Transient images start out
+/// undefined, an aliased image waits for the image that used the memory before it. Imported images start in
+/// their initial layout and are left in their final layout
+pub(super) fn barriers(
+    order: &[usize],
+    passes: &[Vec<(ResourceId, ResourceUsage)>],
+    resources: &[GraphResource],
+    aliases: &[Option<ResourceId>],
+) -> (Vec<Vec<GraphBarrier>>, Vec<GraphBarrier>) {
+    let mut states: Vec<ResourceState> = resources
+        .iter()
+        .map(|resource| match resource {
+            GraphResource::Image(image) => {
+                let layout = image
+                    .imported
+                    .map_or(vk::ImageLayout::UNDEFINED, |imported| {
+                        imported.initial_layout
+                    });
+                let (access, stages) = access_for_layout(layout);
+                ResourceState {
+                    layout,
+                    stages,
+                    access,
+                    written: !write_access(access).is_empty(),
+                }
+            }
+            GraphResource::Buffer(..) => ResourceState {
+                layout: vk::ImageLayout::UNDEFINED,
+                stages: vk::PipelineStageFlags::TOP_OF_PIPE,
+                access: vk::AccessFlags::empty(),
+                written: false,
+            },
+        })
+        .collect();
+    let mut used = vec![false; resources.len()];
+    let mut pass_barriers = Vec::with_capacity(order.len());
+    for pass in order {
+        let mut barriers = Vec::new();
+        for (id, pass_use) in combined_uses(&passes[*pass]) {
+            if !used[id.0] {
+                used[id.0] = true;
+                // The memory was last used by another image, its contents are discarded
+                if let Some(previous) = aliases[id.0] {
+                    let previous = states[previous.0];
+                    states[id.0] = ResourceState {
+                        layout: vk::ImageLayout::UNDEFINED,
+                        ..previous
+                    };
+                }
+            }
+            let is_image = matches!(resources[id.0], GraphResource::Image(_));
+            let state = &mut states[id.0];
+            let layout_change = is_image && state.layout != pass_use.layout;
+            let hazard = state.written || (pass_use.write && !state.access.is_empty());
+            if layout_change || hazard {
+                barriers.push(GraphBarrier {
+                    resource: id,
+                    src_stage: state.stages,
+                    src_access: write_access(state.access),
+                    dst_stage: pass_use.stages,
+                    dst_access: pass_use.access,
+                    old_layout: state.layout,
+                    new_layout: pass_use.layout,
+                });
+                *state = ResourceState {
+                    layout: pass_use.layout,
+                    stages: pass_use.stages,
+                    access: pass_use.access,
+                    written: pass_use.write,
+                };
+            } else {
+                // Reads that need no barrier are combined so that a later write waits for all of them
+                state.stages |= pass_use.stages;
+                state.access |= pass_use.access;
+                state.written |= pass_use.write;
+            }
+        }
+        pass_barriers.push(barriers);
+    }
+    let mut final_barriers = Vec::new();
+    for (index, resource) in resources.iter().enumerate() {
+        let final_layout = match resource {
+            GraphResource::Image(image) => {
+                image.imported.and_then(|imported| imported.final_layout)
+            }
+            GraphResource::Buffer(..) => None,
+        };
+        let state = states[index];
+        if let Some(final_layout) = final_layout {
+            if state.layout != final_layout || state.written {
+                let (dst_access, dst_stage) = access_for_layout(final_layout);
+                final_barriers.push(GraphBarrier {
+                    resource: ResourceId(index),
+                    src_stage: state.stages,
+                    src_access: write_access(state.access),
+                    dst_stage,
+                    dst_access,
+                    old_layout: state.layout,
+                    new_layout: final_layout,
+                });
+            }
+        }
+    }
+    (pass_barriers, final_barriers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{GraphImage, ImportedImage};
+    use super::*;
+
+    fn transient() -> GraphResource {
+        GraphResource::Image(GraphImage {
+            format: vk::Format::R8G8B8A8_UNORM,
+            extent: vk::Extent2D {
+                width: 256,
+                height: 256,
+            },
+            samples: vk::SampleCountFlagBits::_1,
+            imported: None,
+        })
+    }
+
+    fn swapchain() -> GraphResource {
+        GraphResource::Image(GraphImage {
+            imported: Some(ImportedImage {
+                image: vk::Image::null(),
+                view: vk::ImageView::null(),
+                subresource_range: vk::ImageSubresourceRange::default(),
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: Some(vk::ImageLayout::PRESENT_SRC_KHR),
+            }),
+            ..match transient() {
+                GraphResource::Image(image) => image,
+                GraphResource::Buffer(..) => unreachable!(),
+            }
+        })
+    }
+
+    #[test]
+    fn test_schedule_orders_and_culls() {
+        // The overlay is added before the board it samples, the unused pass writes an image nothing reads
+        let resources = [swapchain(), transient(), transient()];
+        let (target, board, unused) = (ResourceId(0), ResourceId(1), ResourceId(2));
+        let passes = vec![
+            vec![
+                (board, ResourceUsage::Sampled),
+                (target, ResourceUsage::ColorAttachment),
+            ],
+            vec![(board, ResourceUsage::ColorAttachment)],
+            vec![(unused, ResourceUsage::ColorAttachment)],
+        ];
+        let order = schedule(&passes, &resources).unwrap();
+        assert_eq!(order, vec![1, 0]);
+
+        let unwritten = vec![vec![
+            (board, ResourceUsage::Sampled),
+            (target, ResourceUsage::ColorAttachment),
+        ]];
+        assert!(schedule(&unwritten, &resources).is_err());
+
+        // Each pass reads what the other writes
+        let cycle = vec![
+            vec![
+                (board, ResourceUsage::Sampled),
+                (unused, ResourceUsage::ColorAttachment),
+                (target, ResourceUsage::ColorAttachment),
+            ],
+            vec![
+                (unused, ResourceUsage::Sampled),
+                (board, ResourceUsage::ColorAttachment),
+            ],
+        ];
+        assert!(schedule(&cycle, &resources).is_err());
+    }
+
+    #[test]
+    fn test_barriers_between_passes() {
+        let resources = [swapchain(), transient()];
+        let (target, board) = (ResourceId(0), ResourceId(1));
+        let passes = vec![
+            vec![(board, ResourceUsage::ColorAttachment)],
+            vec![
+                (board, ResourceUsage::Sampled),
+                (target, ResourceUsage::ColorAttachment),
+            ],
+        ];
+        let (pass_barriers, final_barriers) = barriers(&[0, 1], &passes, &resources, &[None, None]);
+        // The board is transitioned from undefined before it is drawn
+        assert_eq!(pass_barriers[0].len(), 1);
+        assert_eq!(pass_barriers[0][0].old_layout, vk::ImageLayout::UNDEFINED);
+        // The board waits for its colour writes before it is sampled
+        let sampled = pass_barriers[1]
+            .iter()
+            .find(|barrier| barrier.resource == board)
+            .unwrap();
+        assert_eq!(sampled.src_access, vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
+        assert_eq!(
+            sampled.new_layout,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+        // The swapchain image is made presentable after the last pass
+        assert_eq!(final_barriers.len(), 1);
+        assert_eq!(
+            final_barriers[0].new_layout,
+            vk::ImageLayout::PRESENT_SRC_KHR
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use super::ResourceUsage;
+use erupt::vk1_0 as vk;
+
+impl ResourceUsage {
+    /// The pipeline stages that use the resource
+    pub fn stages(self) -> vk::PipelineStageFlags {
+        match self {
+            ResourceUsage::ColorAttachment => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ResourceUsage::DepthStencilAttachment | ResourceUsage::DepthStencilReadOnly => {
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS
+            }
+            ResourceUsage::InputAttachment => vk::PipelineStageFlags::FRAGMENT_SHADER,
+            ResourceUsage::Sampled | ResourceUsage::StorageRead | ResourceUsage::StorageWrite => {
+                vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER
+            }
+            ResourceUsage::TransferSrc | ResourceUsage::TransferDst => {
+                vk::PipelineStageFlags::TRANSFER
+            }
+            ResourceUsage::VertexBuffer | ResourceUsage::IndexBuffer => {
+                vk::PipelineStageFlags::VERTEX_INPUT
+            }
+            ResourceUsage::UniformBuffer => {
+                vk::PipelineStageFlags::VERTEX_SHADER
+                    | vk::PipelineStageFlags::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags::COMPUTE_SHADER
+            }
+        }
+    }
+
+    /// The memory accesses of the stages
+    pub fn access(self) -> vk::AccessFlags {
+        match self {
+            ResourceUsage::ColorAttachment => {
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            }
+            ResourceUsage::DepthStencilAttachment => {
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+            }
+            ResourceUsage::DepthStencilReadOnly => {
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::SHADER_READ
+            }
+            ResourceUsage::InputAttachment => vk::AccessFlags::INPUT_ATTACHMENT_READ,
+            ResourceUsage::Sampled | ResourceUsage::StorageRead => vk::AccessFlags::SHADER_READ,
+            ResourceUsage::StorageWrite => {
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE
+            }
+            ResourceUsage::TransferSrc => vk::AccessFlags::TRANSFER_READ,
+            ResourceUsage::TransferDst => vk::AccessFlags::TRANSFER_WRITE,
+            ResourceUsage::VertexBuffer => vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            ResourceUsage::IndexBuffer => vk::AccessFlags::INDEX_READ,
+            ResourceUsage::UniformBuffer => vk::AccessFlags::UNIFORM_READ,
+        }
+    }
+
+    /// The layout an image must be in, buffers have no layout
+    pub fn layout(self) -> vk::ImageLayout {
+        match self {
+            ResourceUsage::ColorAttachment => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ResourceUsage::DepthStencilAttachment => {
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+            }
+            ResourceUsage::DepthStencilReadOnly => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            ResourceUsage::InputAttachment | ResourceUsage::Sampled => {
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            }
+            ResourceUsage::StorageRead | ResourceUsage::StorageWrite => vk::ImageLayout::GENERAL,
+            ResourceUsage::TransferSrc => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ResourceUsage::TransferDst => vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ResourceUsage::VertexBuffer
+            | ResourceUsage::IndexBuffer
+            | ResourceUsage::UniformBuffer => vk::ImageLayout::UNDEFINED,
+        }
+    }
+
+    pub fn is_write(self) -> bool {
+        matches!(
+            self,
+            ResourceUsage::ColorAttachment
+                | ResourceUsage::DepthStencilAttachment
+                | ResourceUsage::StorageWrite
+                | ResourceUsage::TransferDst
+        )
+    }
+
+    /// True if the usage can be used with an image rather than a buffer
+    pub fn is_image_usage(self) -> bool {
+        !matches!(
+            self,
+            ResourceUsage::VertexBuffer | ResourceUsage::IndexBuffer | ResourceUsage::UniformBuffer
+        )
+    }
+
+    /// The usage a transient image must be created with
+    pub(super) fn image_usage(self) -> vk::ImageUsageFlags {
+        match self {
+            ResourceUsage::ColorAttachment => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            ResourceUsage::DepthStencilAttachment | ResourceUsage::DepthStencilReadOnly => {
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+            }
+            ResourceUsage::InputAttachment => vk::ImageUsageFlags::INPUT_ATTACHMENT,
+            ResourceUsage::Sampled => vk::ImageUsageFlags::SAMPLED,
+            ResourceUsage::StorageRead | ResourceUsage::StorageWrite => {
+                vk::ImageUsageFlags::STORAGE
+            }
+            ResourceUsage::TransferSrc => vk::ImageUsageFlags::TRANSFER_SRC,
+            ResourceUsage::TransferDst => vk::ImageUsageFlags::TRANSFER_DST,
+            ResourceUsage::VertexBuffer
+            | ResourceUsage::IndexBuffer
+            | ResourceUsage::UniformBuffer => vk::ImageUsageFlags::empty(),
+        }
+    }
+}
+
+/// The accesses that write memory, only these need to be made available to later accesses
+pub(super) fn write_access(access: vk::AccessFlags) -> vk::AccessFlags {
+    access
+        & (vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+            | vk::AccessFlags::SHADER_WRITE
+            | vk::AccessFlags::TRANSFER_WRITE
+            | vk::AccessFlags::HOST_WRITE
+            | vk::AccessFlags::MEMORY_WRITE)
+}
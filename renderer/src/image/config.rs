@@ -0,0 +1,186 @@
+use super::{ConfigureImage, Image, ImageKind, ImageLayouts};
+use crate::error::{Error, ErrorKind};
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+impl<'a> ConfigureImage<'a> {
+    pub fn new(device: &'a VulkanDevice) -> ConfigureImage<'a> {
+        ConfigureImage {
+            device,
+            kind: ImageKind::Texture2D,
+            extent: None,
+            format: None,
+            mip_levels: 1,
+            samples: vk::SampleCountFlagBits::_1,
+            tiling: vk::ImageTiling::OPTIMAL,
+            usage: vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+            memory_properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        }
+    }
+
+    /// The width and height of the first mip level
+    pub fn extent(mut self, width: u32, height: u32) -> Self {
+        self.extent = Some(vk::Extent2D { width, height });
+        self
+    }
+
+    pub fn format(mut self, format: vk::Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Selects between a single image, an array of images or a cube, the default is a single 2D image
+    pub fn kind(mut self, kind: ImageKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn mip_levels(mut self, mip_levels: u32) -> Self {
+        self.mip_levels = mip_levels;
+        self
+    }
+
+    pub fn samples(mut self, samples: vk::SampleCountFlagBits) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn tiling(mut self, tiling: vk::ImageTiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    /// How the image will be used, by default an image can be sampled and copied into
+    pub fn usage(mut self, usage: vk::ImageUsageFlags) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// The properties of the memory the image is bound to, by default this is device local memory
+    pub fn memory_properties(mut self, properties: vk::MemoryPropertyFlags) -> Self {
+        self.memory_properties = properties;
+        self
+    }
+
+    // Checks the configuration against the device limits
+    fn validate(&self) -> Result<(vk::Extent2D, vk::Format, u32), Error> {
+        let extent = self.extent.ok_or_else(|| {
+            Error::new(ErrorKind::InvalidImageConfig, None).with_context(&"The extent was not set")
+        })?;
+        let format = self.format.ok_or_else(|| {
+            Error::new(ErrorKind::InvalidImageConfig, None).with_context(&"The format was not set")
+        })?;
+        if extent.width == 0 || extent.height == 0 {
+            return Err(Error::new(ErrorKind::InvalidImageConfig, None)
+                .with_context(&"The width and height of an image must be greater than zero"));
+        }
+        let limits = self.device.limits();
+        let (max_dimension, array_layers) = match self.kind {
+            ImageKind::Texture2D => (limits.max_image_dimension2_d, 1),
+            ImageKind::Array(layers) => (limits.max_image_dimension2_d, layers),
+            ImageKind::Cube => {
+                if extent.width != extent.height {
+                    return Err(Error::new(ErrorKind::InvalidImageConfig, None)
+                        .with_context(&"The faces of a cube image must be square"));
+                }
+                (limits.max_image_dimension_cube, 6)
+            }
+        };
+        if extent.width > max_dimension || extent.height > max_dimension {
+            return Err(
+                Error::new(ErrorKind::InvalidImageConfig, None).with_context(
+                    &"The extent of the image exceeds the maximum dimension of the device",
+                ),
+            );
+        }
+        if array_layers == 0 || array_layers > limits.max_image_array_layers {
+            return Err(Error::new(ErrorKind::InvalidImageConfig, None)
+                .with_context(&"The number of array layers is zero or exceeds the device limit"));
+        }
+        if self.samples != vk::SampleCountFlagBits::_1
+            && (self.kind != ImageKind::Texture2D || self.mip_levels != 1)
+        {
+            return Err(
+                Error::new(ErrorKind::InvalidImageConfig, None).with_context(
+                    &"A multisampled image must be a single 2D image with one mip level",
+                ),
+            );
+        }
+        if self.mip_levels == 0 || self.mip_levels > mip_levels_for_extent(extent) {
+            return Err(
+                Error::new(ErrorKind::InvalidImageConfig, None).with_context(
+                    &"The number of mip levels is zero or exceeds the size of the image",
+                ),
+            );
+        }
+        Ok((extent, format, array_layers))
+    }
+
+    /// Create the image and bind it to memory, every subresource starts in the UNDEFINED layout
+    pub fn build(self) -> Result<Image, Error> {
+        let device = self.device;
+        let memory_properties = self.memory_properties;
+        let (mut image, requirements) = self.build_unbound()?;
+        let memory = match device.allocate_memory(&requirements, memory_properties) {
+            Ok(memory) => memory,
+            Err(error) => {
+                image.destroy(device);
+                return Err(error);
+            }
+        };
+        if let Err(error) = image.bind_alias(device, memory, 0) {
+            image.destroy(device);
+            unsafe { device.handle().free_memory(Some(memory), None) };
+            return Err(error);
+        }
+        image.memory = memory;
+        Ok(image)
+    }
+
+    // Creates the image without memory so that it can be bound to memory shared with other images
+    pub(crate) fn build_unbound(self) -> Result<(Image, vk::MemoryRequirements), Error> {
+        let (extent, format, array_layers) = self.validate()?;
+        let flags = match self.kind {
+            ImageKind::Cube => vk::ImageCreateFlags::CUBE_COMPATIBLE,
+            _ => vk::ImageCreateFlags::empty(),
+        };
+        let create_info = vk::ImageCreateInfoBuilder::new()
+            .flags(flags)
+            .image_type(vk::ImageType::_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(self.mip_levels)
+            .array_layers(array_layers)
+            .samples(self.samples)
+            .tiling(self.tiling)
+            .usage(self.usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let device = self.device.handle();
+        let handle = unsafe { device.create_image(&create_info, None, None) }.result()?;
+        let requirements = unsafe { device.get_image_memory_requirements(handle, None) };
+        let image = Image {
+            handle,
+            memory: vk::DeviceMemory::null(),
+            kind: self.kind,
+            extent,
+            format,
+            mip_levels: self.mip_levels,
+            array_layers,
+            samples: self.samples,
+            usage: self.usage,
+            layouts: ImageLayouts::new(self.mip_levels, array_layers),
+        };
+        Ok((image, requirements))
+    }
+}
+
+/// The number of mip levels in a complete mip chain for an image of the given extent
+pub fn mip_levels_for_extent(extent: vk::Extent2D) -> u32 {
+    let largest = extent.width.max(extent.height).max(1);
+    32 - largest.leading_zeros()
+}
@@ -0,0 +1,167 @@
+use super::{access_for_layout, aspect_for_format, Image, ImageKind};
+use crate::error::Error;
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+impl Image {
+    pub fn handle(&self) -> vk::Image {
+        self.handle
+    }
+
+    pub fn kind(&self) -> ImageKind {
+        self.kind
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    pub fn array_layers(&self) -> u32 {
+        self.array_layers
+    }
+
+    pub fn samples(&self) -> vk::SampleCountFlagBits {
+        self.samples
+    }
+
+    pub fn usage(&self) -> vk::ImageUsageFlags {
+        self.usage
+    }
+
+    /// The aspects of the image, ie colour or depth and stencil
+    pub fn aspect(&self) -> vk::ImageAspectFlags {
+        aspect_for_format(self.format)
+    }
+
+    /// The layout a subresource was left in by the last transition
+    pub fn layout(&self, mip_level: u32, array_layer: u32) -> vk::ImageLayout {
+        self.layouts.get(mip_level, array_layer)
+    }
+
+    /// Records a transition of the entire image into the new layout
+    pub fn transition_layout(
+        &mut self,
+        device: &VulkanDevice,
+        command_buffer: vk::CommandBuffer,
+        new_layout: vk::ImageLayout,
+    ) {
+        self.transition_subresources(
+            device,
+            command_buffer,
+            new_layout,
+            0..self.mip_levels,
+            0..self.array_layers,
+        );
+    }
+
+    /// Records a transition of a range of mip levels and array layers into the new layout, subresources
+    /// that are already in the new layout get an execution and memory barrier without a layout change
+    pub fn transition_subresources(
+        &mut self,
+        device: &VulkanDevice,
+        command_buffer: vk::CommandBuffer,
+        new_layout: vk::ImageLayout,
+        mip_levels: std::ops::Range<u32>,
+        array_layers: std::ops::Range<u32>,
+    ) {
+        let runs = self
+            .layouts
+            .transition(new_layout, mip_levels, array_layers);
+        if runs.is_empty() {
+            return;
+        }
+        let (dst_access, dst_stage) = access_for_layout(new_layout);
+        let mut src_stage = vk::PipelineStageFlags::empty();
+        let aspect_mask = self.aspect();
+        let barriers: Vec<vk::ImageMemoryBarrierBuilder> = runs
+            .iter()
+            .map(|run| {
+                let (src_access, stage) = access_for_layout(run.old_layout);
+                src_stage |= stage;
+                vk::ImageMemoryBarrierBuilder::new()
+                    .src_access_mask(src_access)
+                    .dst_access_mask(dst_access)
+                    .old_layout(run.old_layout)
+                    .new_layout(new_layout)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(self.handle)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask,
+                        base_mip_level: run.base_mip_level,
+                        level_count: run.level_count,
+                        base_array_layer: run.array_layer,
+                        layer_count: 1,
+                    })
+            })
+            .collect();
+        unsafe {
+            device.handle().cmd_pipeline_barrier(
+                command_buffer,
+                src_stage,
+                dst_stage,
+                None,
+                &[],
+                &[],
+                barriers.as_slice(),
+            )
+        };
+    }
+
+    /// Records that the layout of a range of subresources was changed outside of the image,
+    /// for instance by the final layout of a renderpass attachment
+    pub fn assume_layout(
+        &mut self,
+        layout: vk::ImageLayout,
+        mip_levels: std::ops::Range<u32>,
+        array_layers: std::ops::Range<u32>,
+    ) {
+        self.layouts.transition(layout, mip_levels, array_layers);
+    }
+
+    // Binds the image to memory it doesn't own, the memory is freed by whatever allocated it
+    pub(crate) fn bind_alias(
+        &self,
+        device: &VulkanDevice,
+        memory: vk::DeviceMemory,
+        offset: vk::DeviceSize,
+    ) -> Result<(), Error> {
+        unsafe {
+            device
+                .handle()
+                .bind_image_memory(self.handle, memory, offset)
+        }
+        .result()?;
+        Ok(())
+    }
+
+    /// Destroys the image and frees its memory, the image must no longer be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe {
+            device.handle().destroy_image(Some(self.handle), None);
+            if !self.memory.is_null() {
+                device.handle().free_memory(Some(self.memory), None);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("kind", &self.kind)
+            .field("extent", &self.extent)
+            .field("format", &self.format)
+            .field("mip_levels", &self.mip_levels)
+            .field("array_layers", &self.array_layers)
+            .finish()
+    }
+}
@@ -0,0 +1,166 @@
+use super::{ImageLayouts, LayoutRun};
+use erupt::vk1_0 as vk;
+
+impl ImageLayouts {
+    pub fn new(mip_levels: u32, array_layers: u32) -> ImageLayouts {
+        ImageLayouts {
+            mip_levels,
+            array_layers,
+            layouts: vec![vk::ImageLayout::UNDEFINED; (mip_levels * array_layers) as usize],
+        }
+    }
+
+    pub fn get(&self, mip_level: u32, array_layer: u32) -> vk::ImageLayout {
+        self.layouts[(array_layer * self.mip_levels + mip_level) as usize]
+    }
+
+    /// Moves the subresources in range to the new layout, returning the runs of subresources that
+    /// need a barrier, runs are split whenever the old layout changes between mip levels. Subresources
+    /// already in the new layout still get a run so the barrier orders the previous use before the next
+    pub fn transition(
+        &mut self,
+        new_layout: vk::ImageLayout,
+        mip_levels: std::ops::Range<u32>,
+        array_layers: std::ops::Range<u32>,
+    ) -> Vec<LayoutRun> {
+        let mip_levels = mip_levels.start..mip_levels.end.min(self.mip_levels);
+        let array_layers = array_layers.start..array_layers.end.min(self.array_layers);
+        let mut runs: Vec<LayoutRun> = Vec::new();
+        for layer in array_layers {
+            let mut current: Option<LayoutRun> = None;
+            for mip in mip_levels.clone() {
+                let index = (layer * self.mip_levels + mip) as usize;
+                let old_layout = self.layouts[index];
+                self.layouts[index] = new_layout;
+                match current.as_mut() {
+                    Some(run) if run.old_layout == old_layout => run.level_count += 1,
+                    _ => {
+                        runs.extend(current.take());
+                        current = Some(LayoutRun {
+                            old_layout,
+                            base_mip_level: mip,
+                            level_count: 1,
+                            array_layer: layer,
+                        });
+                    }
+                }
+            }
+            runs.extend(current);
+        }
+        runs
+    }
+}
+
+/// The accesses and pipeline stages that use an image in the given layout, used as the source
+/// scope when leaving a layout and the destination scope when entering it
+pub fn access_for_layout(layout: vk::ImageLayout) -> (vk::AccessFlags, vk::PipelineStageFlags) {
+    match layout {
+        vk::ImageLayout::UNDEFINED | vk::ImageLayout::PREINITIALIZED => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+        ),
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::FRAGMENT_SHADER,
+        ),
+        vk::ImageLayout::PRESENT_SRC_KHR => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        ),
+        // GENERAL and anything we don't know about gets the most conservative scope
+        _ => (
+            vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+    }
+}
+
+/// The aspects contained in an image of the given format
+pub fn aspect_for_format(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+            vk::ImageAspectFlags::DEPTH
+        }
+        vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_whole_image() {
+        let mut layouts = ImageLayouts::new(4, 2);
+        let runs = layouts.transition(vk::ImageLayout::TRANSFER_DST_OPTIMAL, 0..4, 0..2);
+        // One run per layer covering every mip level
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].level_count, 4);
+        assert_eq!(runs[1].array_layer, 1);
+        assert_eq!(runs[0].old_layout, vk::ImageLayout::UNDEFINED);
+        assert_eq!(layouts.get(3, 1), vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+    }
+
+    #[test]
+    fn test_transition_splits_runs() {
+        let mut layouts = ImageLayouts::new(4, 1);
+        layouts.transition(vk::ImageLayout::TRANSFER_DST_OPTIMAL, 0..4, 0..1);
+        layouts.transition(vk::ImageLayout::TRANSFER_SRC_OPTIMAL, 0..1, 0..1);
+        let runs = layouts.transition(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, 0..4, 0..1);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].old_layout, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+        assert_eq!(runs[1].base_mip_level, 1);
+        assert_eq!(runs[1].level_count, 3);
+    }
+
+    #[test]
+    fn test_transition_to_same_layout() {
+        let mut layouts = ImageLayouts::new(2, 1);
+        layouts.transition(vk::ImageLayout::TRANSFER_DST_OPTIMAL, 0..2, 0..1);
+        // A second copy into the image must still wait for the first
+        let runs = layouts.transition(vk::ImageLayout::TRANSFER_DST_OPTIMAL, 0..2, 0..1);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].old_layout, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        assert_eq!(runs[0].level_count, 2);
+    }
+
+    #[test]
+    fn test_aspect_for_format() {
+        assert_eq!(
+            aspect_for_format(vk::Format::R8G8B8A8_SRGB),
+            vk::ImageAspectFlags::COLOR
+        );
+        assert_eq!(
+            aspect_for_format(vk::Format::D24_UNORM_S8_UINT),
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        );
+    }
+}
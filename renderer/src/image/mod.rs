@@ -0,0 +1,103 @@
+mod config;
+mod image;
+mod layout;
+mod sampler;
+mod view;
+
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+pub use config::mip_levels_for_extent;
+pub use layout::{access_for_layout, aspect_for_format};
+
+/// The shape of an image, determines the number of array layers and the default view type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageKind {
+    /// A single 2D image
+    Texture2D,
+    /// An array of 2D images, each layer having the same extent and format
+    Array(u32),
+    /// Six square layers that are viewed as the faces of a cube
+    Cube,
+}
+
+/// Collects the settings of an image before it is created and bound to memory
+pub struct ConfigureImage<'a> {
+    device: &'a VulkanDevice,
+    kind: ImageKind,
+    extent: Option<vk::Extent2D>,
+    format: Option<vk::Format>,
+    mip_levels: u32,
+    samples: vk::SampleCountFlagBits,
+    tiling: vk::ImageTiling,
+    usage: vk::ImageUsageFlags,
+    memory_properties: vk::MemoryPropertyFlags,
+}
+
+/// An image along with the memory backing it, the layout of each mip level and array layer is
+/// tracked so that transitions only need to name the layout that is wanted
+pub struct Image {
+    handle: vk::Image,
+    // Null when the image is bound to memory that is shared with other images
+    memory: vk::DeviceMemory,
+    kind: ImageKind,
+    extent: vk::Extent2D,
+    format: vk::Format,
+    mip_levels: u32,
+    array_layers: u32,
+    samples: vk::SampleCountFlagBits,
+    usage: vk::ImageUsageFlags,
+    layouts: ImageLayouts,
+}
+
+/// Tracks the current layout of every subresource in an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageLayouts {
+    mip_levels: u32,
+    array_layers: u32,
+    // Indexed by layer * mip_levels + mip
+    layouts: Vec<vk::ImageLayout>,
+}
+
+/// A contiguous run of subresources that share the same layout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutRun {
+    pub old_layout: vk::ImageLayout,
+    pub base_mip_level: u32,
+    pub level_count: u32,
+    pub array_layer: u32,
+}
+
+pub struct ImageViewSettings<'a> {
+    image: &'a Image,
+    view_type: vk::ImageViewType,
+    format: vk::Format,
+    components: vk::ComponentMapping,
+    subresource_range: vk::ImageSubresourceRange,
+}
+
+pub struct ImageView {
+    handle: vk::ImageView,
+    image: vk::Image,
+    format: vk::Format,
+    subresource_range: vk::ImageSubresourceRange,
+    // The extent of the first mip level in the view
+    extent: vk::Extent2D,
+}
+
+pub struct SamplerSettings {
+    mag_filter: vk::Filter,
+    min_filter: vk::Filter,
+    mipmap_mode: vk::SamplerMipmapMode,
+    address_mode: [vk::SamplerAddressMode; 3],
+    max_anisotropy: Option<f32>,
+    mip_lod_bias: f32,
+    min_lod: f32,
+    max_lod: f32,
+    border_colour: vk::BorderColor,
+    compare_op: Option<vk::CompareOp>,
+}
+
+pub struct Sampler {
+    handle: vk::Sampler,
+}
@@ -0,0 +1,120 @@
+use super::{Sampler, SamplerSettings};
+use crate::error::{Error, ErrorKind};
+use crate::{Features, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl SamplerSettings {
+    pub fn new() -> SamplerSettings {
+        SamplerSettings {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode: [vk::SamplerAddressMode::CLAMP_TO_EDGE; 3],
+            max_anisotropy: None,
+            mip_lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: vk::LOD_CLAMP_NONE,
+            border_colour: vk::BorderColor::FLOAT_TRANSPARENT_BLACK,
+            compare_op: None,
+        }
+    }
+
+    /// The filters used when the image is magnified and minified
+    pub fn filter(&mut self, mag_filter: vk::Filter, min_filter: vk::Filter) {
+        self.mag_filter = mag_filter;
+        self.min_filter = min_filter;
+    }
+
+    /// How samples are taken between mip levels
+    pub fn mipmap_mode(&mut self, mipmap_mode: vk::SamplerMipmapMode) {
+        self.mipmap_mode = mipmap_mode;
+    }
+
+    /// What happens when the texture is sampled outside of the range 0 - 1 on each axis
+    pub fn address_mode(
+        &mut self,
+        u: vk::SamplerAddressMode,
+        v: vk::SamplerAddressMode,
+        w: vk::SamplerAddressMode,
+    ) {
+        self.address_mode = [u, v, w];
+    }
+
+    /// Enables anisotropic filtering, the value is clamped to the maximum the device supports
+    pub fn anisotropy(&mut self, max_anisotropy: f32) {
+        self.max_anisotropy = Some(max_anisotropy);
+    }
+
+    pub fn lod_bias(&mut self, mip_lod_bias: f32) {
+        self.mip_lod_bias = mip_lod_bias;
+    }
+
+    /// Clamps the mip levels that can be sampled
+    pub fn lod_range(&mut self, min_lod: f32, max_lod: f32) {
+        self.min_lod = min_lod;
+        self.max_lod = max_lod;
+    }
+
+    pub fn border_colour(&mut self, border_colour: vk::BorderColor) {
+        self.border_colour = border_colour;
+    }
+
+    /// Makes this a comparison sampler, used for reading from depth images
+    pub fn compare(&mut self, compare_op: vk::CompareOp) {
+        self.compare_op = Some(compare_op);
+    }
+
+    pub fn create_sampler(self, device: &VulkanDevice) -> Result<Sampler, Error> {
+        let max_anisotropy = match self.max_anisotropy {
+            Some(anisotropy) => {
+                if !device.feature_enabled(&Features::SamplerAnisotropy) {
+                    return Err(Error::new(
+                        ErrorKind::MissingFeature(Features::SamplerAnisotropy),
+                        None,
+                    ));
+                }
+                Some(
+                    anisotropy
+                        .max(1.0)
+                        .min(device.limits().max_sampler_anisotropy),
+                )
+            }
+            None => None,
+        };
+        let create_info = vk::SamplerCreateInfoBuilder::new()
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_mode(self.mipmap_mode)
+            .address_mode_u(self.address_mode[0])
+            .address_mode_v(self.address_mode[1])
+            .address_mode_w(self.address_mode[2])
+            .mip_lod_bias(self.mip_lod_bias)
+            .anisotropy_enable(max_anisotropy.is_some())
+            .max_anisotropy(max_anisotropy.unwrap_or(1.0))
+            .compare_enable(self.compare_op.is_some())
+            .compare_op(self.compare_op.unwrap_or(vk::CompareOp::ALWAYS))
+            .min_lod(self.min_lod)
+            .max_lod(self.max_lod)
+            .border_color(self.border_colour)
+            .unnormalized_coordinates(false);
+        let handle =
+            unsafe { device.handle().create_sampler(&create_info, None, None) }.result()?;
+        Ok(Sampler { handle })
+    }
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        SamplerSettings::new()
+    }
+}
+
+impl Sampler {
+    pub fn handle(&self) -> vk::Sampler {
+        self.handle
+    }
+
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_sampler(Some(self.handle), None) };
+    }
+}
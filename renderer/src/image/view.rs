@@ -0,0 +1,149 @@
+use super::{Image, ImageKind, ImageView, ImageViewSettings};
+use crate::error::Error;
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+impl<'a> ImageViewSettings<'a> {
+    pub fn new(image: &'a Image) -> ImageViewSettings<'a> {
+        let view_type = match image.kind() {
+            ImageKind::Texture2D => vk::ImageViewType::_2D,
+            ImageKind::Array(_) => vk::ImageViewType::_2D_ARRAY,
+            ImageKind::Cube => vk::ImageViewType::CUBE,
+        };
+        ImageViewSettings {
+            image,
+            view_type,
+            format: image.format(),
+            components: vk::ComponentMapping::default(),
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: image.aspect(),
+                base_mip_level: 0,
+                level_count: image.mip_levels(),
+                base_array_layer: 0,
+                layer_count: image.array_layers(),
+            },
+        }
+    }
+
+    /// By default the view type matches the kind of image, a single layer of an array can be
+    /// viewed as a 2D image by selecting a 2D view type and a single array layer
+    pub fn view_type(&mut self, view_type: vk::ImageViewType) {
+        self.view_type = view_type;
+    }
+
+    /// Reinterpret the image using a compatible format, ie viewing an SRGB image as UNORM
+    pub fn format(&mut self, format: vk::Format) {
+        self.format = format;
+    }
+
+    /// Remap the colour channels of the image, ie to present a single channel image as grayscale
+    pub fn swizzle(
+        &mut self,
+        r: vk::ComponentSwizzle,
+        g: vk::ComponentSwizzle,
+        b: vk::ComponentSwizzle,
+        a: vk::ComponentSwizzle,
+    ) {
+        self.components = vk::ComponentMapping { r, g, b, a };
+    }
+
+    /// Restrict the view to a range of mip levels
+    pub fn mip_levels(&mut self, base_mip_level: u32, level_count: u32) {
+        self.subresource_range.base_mip_level = base_mip_level;
+        self.subresource_range.level_count = level_count;
+    }
+
+    /// Restrict the view to a range of array layers
+    pub fn array_layers(&mut self, base_array_layer: u32, layer_count: u32) {
+        self.subresource_range.base_array_layer = base_array_layer;
+        self.subresource_range.layer_count = layer_count;
+    }
+
+    /// Select the aspects of the image to view, ie only the depth of a depth stencil image
+    pub fn aspect(&mut self, aspect_mask: vk::ImageAspectFlags) {
+        self.subresource_range.aspect_mask = aspect_mask;
+    }
+
+    pub fn create_view(self, device: &VulkanDevice) -> Result<ImageView, Error> {
+        let create_info = vk::ImageViewCreateInfoBuilder::new()
+            .image(self.image.handle())
+            .view_type(self.view_type)
+            .format(self.format)
+            .components(self.components)
+            .subresource_range(self.subresource_range);
+        let handle =
+            unsafe { device.handle().create_image_view(&create_info, None, None) }.result()?;
+        let image_extent = self.image.extent();
+        let mip_level = self.subresource_range.base_mip_level;
+        Ok(ImageView {
+            handle,
+            image: self.image.handle(),
+            format: self.format,
+            subresource_range: self.subresource_range,
+            extent: vk::Extent2D {
+                width: (image_extent.width >> mip_level).max(1),
+                height: (image_extent.height >> mip_level).max(1),
+            },
+        })
+    }
+}
+
+impl ImageView {
+    // Swapchain images are owned by the swapchain rather than being an Image, they are always a single 2D colour layer
+    pub(crate) fn for_swapchain_image(
+        device: &VulkanDevice,
+        image: vk::Image,
+        format: vk::Format,
+        extent: vk::Extent2D,
+        components: vk::ComponentMapping,
+    ) -> Result<ImageView, Error> {
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let create_info = vk::ImageViewCreateInfoBuilder::new()
+            .image(image)
+            .view_type(vk::ImageViewType::_2D)
+            .format(format)
+            .components(components)
+            .subresource_range(subresource_range);
+        let handle =
+            unsafe { device.handle().create_image_view(&create_info, None, None) }.result()?;
+        Ok(ImageView {
+            handle,
+            image,
+            format,
+            subresource_range,
+            extent,
+        })
+    }
+
+    pub fn handle(&self) -> vk::ImageView {
+        self.handle
+    }
+
+    /// The image that is being viewed
+    pub fn image(&self) -> vk::Image {
+        self.image
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    pub fn subresource_range(&self) -> vk::ImageSubresourceRange {
+        self.subresource_range
+    }
+
+    /// The width and height of the first mip level in the view
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_image_view(Some(self.handle), None) };
+    }
+}
@@ -293,6 +293,10 @@ impl VulkanApi {
         }
     }
 
+    pub(crate) fn instance(&self) -> &erupt::InstanceLoader {
+        &self.instance
+    }
+
     pub fn extension_loaded(&self, extension: crate::InstanceExtensions) -> bool {
         self.extensions_loaded.contains(extension.get_name())
     }
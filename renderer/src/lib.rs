@@ -1,28 +1,58 @@
+// Lets code generated by #[derive(Vertex)] name this crate from inside it
+extern crate self as renderer;
+
+mod buffer;
+mod commands;
 mod config;
+mod descriptor;
 mod device;
 mod error;
 mod extensions;
 mod features;
 mod gpu;
+mod graph;
+mod image;
 mod instance;
 mod pick;
 mod pipeline;
 mod presenter;
 mod queues;
+mod rendering;
 mod renderpass;
 mod select;
+mod texture;
 mod vendor;
 mod version;
 
 pub use crate::error::Error;
+pub use buffer::Buffer;
+pub use commands::{CommandPool, CommandRecorder};
+pub use descriptor::{
+    ConfigureDescriptorSetLayout, DescriptorPool, DescriptorSet, DescriptorSetLayout,
+    DescriptorWrites, PoolLifetime, TextureIndex, TextureTable,
+};
 pub use extensions::ExtensionManager;
+use features::Vulkan12Features;
 pub use features::{Feature, Features};
+pub use graph::{
+    CompiledGraph, GraphResources, PassSettings, RenderGraph, ResourceId, ResourceUsage,
+};
+pub use image::{
+    mip_levels_for_extent, ConfigureImage, Image, ImageKind, ImageView, ImageViewSettings, Sampler,
+    SamplerSettings,
+};
 pub use instance::InstanceExtensions;
 pub use instance::Layers;
 pub use instance::VulkanApi;
 pub use instance::VulkanConfig;
 pub use pick::PickManager;
-pub use pipeline::ConfigurePipeline;
+pub use pipeline::{
+    BlendMode, ComputePipeline, ConfigureComputePipeline, ConfigurePipeline, GraphicsPipeline,
+    PipelineCache, PipelineDescription, PipelineLayout, PipelineStore, PushConstants,
+    ShaderDescriptorBinding, ShaderInput, ShaderReflection, SpecializationValue, Vertex,
+    VertexAttribute, VertexAttributeType,
+};
+pub use renderer_derive::Vertex;
 pub use version::Version;
 
 pub use config::{ConfigureDevice, DeviceExtensions};
@@ -33,8 +63,17 @@ pub use presenter::{
     SwapchainImageCount,
 };
 pub use queues::{DeviceQueue, OperationQueue, QueueFamily, RendererQueues, RendererQueuesBuilder};
-pub use renderpass::Renderpass;
+pub use rendering::{RenderingAttachment, RenderingFormats};
+pub use renderpass::{
+    Attachment, ConfigureRenderpass, Framebuffer, RenderTarget, Renderpass, SubpassSettings,
+    SwapchainFramebuffers,
+};
 pub use select::{DeviceFilter, DeviceSelector, FiltersDevices, SelectedDevice};
+pub use texture::{
+    decode_file, decode_memory, DecodedImage, ImageRegion, Mipmaps, PendingImage, Texture,
+    TextureFiltering, TextureLoader, TextureSettings, TileCache, TileDraw, TileKey, TiledImage,
+    TILE_SIZE,
+};
 pub use vendor::PciVendor;
 
 use erupt::extensions::khr_surface;
@@ -53,12 +92,12 @@ pub struct Gpu {
     device_type: vk::PhysicalDeviceType,
     available_extensions: Vec<vk::ExtensionProperties>,
     device_features: vk::PhysicalDeviceFeatures,
+    vulkan12_features: Vulkan12Features,
+    limits: vk::PhysicalDeviceLimits,
     surface_capabilities: khr_surface::SurfaceCapabilitiesKHR,
     surface_formats: Vec<khr_surface::SurfaceFormatKHR>,
     present_modes: Vec<khr_surface::PresentModeKHR>,
     presentable: bool,
-    // pipelinecacheID,
-    // limits,
     // sparse_properties,
 }
 
@@ -0,0 +1,231 @@
+use super::{PipelineCache, PipelineCacheKey};
+use crate::error::{Error, ErrorKind};
+use erupt::vk1_0 as vk;
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+
+// The header every implementation writes at the start of its cache data
+const HEADER_SIZE: usize = 16 + vk::UUID_SIZE as usize;
+
+fn write_failed(error: std::io::Error) -> Error {
+    Error::new(ErrorKind::PipelineCacheWriteFailed(error.to_string()), None)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+impl PipelineCacheKey {
+    pub(crate) fn new(properties: &vk::PhysicalDeviceProperties) -> PipelineCacheKey {
+        PipelineCacheKey {
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            driver_version: properties.driver_version,
+            uuid: properties.pipeline_cache_uuid,
+        }
+    }
+
+    // Every part of the key is in the file name so caches from other drivers are never read
+    fn file_name(&self) -> String {
+        let uuid: String = self
+            .uuid
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!(
+            "pipeline-cache-{:04x}-{:04x}-{:08x}-{}.bin",
+            self.vendor_id, self.device_id, self.driver_version, uuid
+        )
+    }
+
+    /// True if the data starts with a header written by the device and driver this key describes
+    fn is_valid_header(&self, data: &[u8]) -> bool {
+        if data.len() < HEADER_SIZE {
+            return false;
+        }
+        // Header fields are written in the byte order of the host
+        let header_length = read_u32(data, 0) as usize;
+        let header_version = read_u32(data, 4);
+        header_length >= HEADER_SIZE
+            && header_length <= data.len()
+            && header_version == vk::PipelineCacheHeaderVersion::ONE.0 as u32
+            && read_u32(data, 8) == self.vendor_id
+            && read_u32(data, 12) == self.device_id
+            && data[16..HEADER_SIZE] == self.uuid
+    }
+}
+
+impl PipelineCache {
+    /// Creates a cache seeded from the file in the directory for this device and driver,
+    /// a missing, stale or corrupt file is ignored and the cache starts empty
+    pub(crate) fn new(
+        device: &erupt::DeviceLoader,
+        key: PipelineCacheKey,
+        directory: Option<&Path>,
+    ) -> Result<PipelineCache, Error> {
+        let path = directory.map(|directory| directory.join(key.file_name()));
+        let initial_data = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .filter(|data| key.is_valid_header(data));
+        let handle = match initial_data {
+            // Drivers may still reject data that passes the header check
+            Some(data) => {
+                Self::create(device, data.as_slice()).or_else(|_| Self::create(device, &[]))?
+            }
+            None => Self::create(device, &[])?,
+        };
+        Ok(PipelineCache {
+            handle,
+            key,
+            path,
+            fallback_reason: None,
+        })
+    }
+
+    // A cache with no handle, pipelines are still created but nothing is reused between them
+    pub(crate) fn disabled(key: PipelineCacheKey) -> PipelineCache {
+        PipelineCache {
+            handle: vk::PipelineCache::null(),
+            key,
+            path: None,
+            fallback_reason: None,
+        }
+    }
+
+    // Records why this cache was used instead of the one that was configured
+    pub(crate) fn fallback_from(mut self, reason: Error) -> PipelineCache {
+        self.fallback_reason = Some(reason);
+        self
+    }
+
+    fn create(device: &erupt::DeviceLoader, data: &[u8]) -> Result<vk::PipelineCache, Error> {
+        let mut create_info = vk::PipelineCacheCreateInfoBuilder::new();
+        if !data.is_empty() {
+            create_info = create_info
+                .initial_data_size(data.len())
+                .initial_data(data.as_ptr() as *const std::ffi::c_void);
+        }
+        let handle = unsafe { device.create_pipeline_cache(&create_info, None, None) }.result()?;
+        Ok(handle)
+    }
+
+    pub fn handle(&self) -> vk::PipelineCache {
+        self.handle
+    }
+
+    /// The file the cache is read from and written to, none if the cache is not persisted
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Why the cache isn't persisted or is disabled when creating the configured cache failed, none if it didn't
+    pub fn fallback_reason(&self) -> Option<&Error> {
+        self.fallback_reason.as_ref()
+    }
+
+    /// Writes the contents of the cache to its file, does nothing if the cache is not persisted
+    pub fn save(&self, device: &erupt::DeviceLoader) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let data = self.data(device)?;
+        // Data from another driver would be discarded the next time it's read anyway
+        if !self.key.is_valid_header(&data) {
+            return Ok(());
+        }
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory).map_err(write_failed)?;
+        }
+        // Writing to a temporary file first means a crash never leaves a partially written cache
+        let mut temporary = PathBuf::from(path);
+        temporary.set_extension("tmp");
+        std::fs::write(&temporary, data).map_err(write_failed)?;
+        std::fs::rename(&temporary, path).map_err(write_failed)?;
+        Ok(())
+    }
+
+    fn data(&self, device: &erupt::DeviceLoader) -> Result<Vec<u8>, Error> {
+        loop {
+            let mut size = 0;
+            unsafe { device.get_pipeline_cache_data(self.handle, &mut size, std::ptr::null_mut()) }
+                .result()?;
+            let mut data = vec![0u8; size];
+            let result = unsafe {
+                device.get_pipeline_cache_data(
+                    self.handle,
+                    &mut size,
+                    data.as_mut_ptr() as *mut std::ffi::c_void,
+                )
+            };
+            // The cache can grow between the two calls if pipelines are being created on another thread
+            if result.raw == vk::Result::INCOMPLETE {
+                continue;
+            }
+            result.result()?;
+            data.truncate(size);
+            return Ok(data);
+        }
+    }
+
+    /// Destroys the cache without writing it to its file
+    pub fn destroy(self, device: &erupt::DeviceLoader) {
+        unsafe { device.destroy_pipeline_cache(Some(self.handle), None) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> PipelineCacheKey {
+        PipelineCacheKey {
+            vendor_id: 0x10DE,
+            device_id: 0x1C03,
+            driver_version: 7,
+            uuid: [3; vk::UUID_SIZE as usize],
+        }
+    }
+
+    fn header(key: &PipelineCacheKey) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(HEADER_SIZE as u32).to_ne_bytes());
+        data.extend_from_slice(&(vk::PipelineCacheHeaderVersion::ONE.0 as u32).to_ne_bytes());
+        data.extend_from_slice(&key.vendor_id.to_ne_bytes());
+        data.extend_from_slice(&key.device_id.to_ne_bytes());
+        data.extend_from_slice(&key.uuid);
+        data.extend_from_slice(&[0xAB; 64]);
+        data
+    }
+
+    #[test]
+    fn test_cache_header_validation() {
+        let key = key();
+        assert!(key.is_valid_header(&header(&key)));
+        // Truncated or empty files
+        assert!(!key.is_valid_header(&[]));
+        assert!(!key.is_valid_header(&header(&key)[..HEADER_SIZE - 1]));
+        // Written by another device or driver
+        let other_device = PipelineCacheKey {
+            device_id: 0x1B80,
+            ..key
+        };
+        assert!(!key.is_valid_header(&header(&other_device)));
+        let other_uuid = PipelineCacheKey {
+            uuid: [4; vk::UUID_SIZE as usize],
+            ..key
+        };
+        assert!(!key.is_valid_header(&header(&other_uuid)));
+        // A header length longer than the file
+        let mut corrupt = header(&key);
+        corrupt[0..4].copy_from_slice(&1024u32.to_ne_bytes());
+        assert!(!key.is_valid_header(&corrupt));
+        // The driver version is only part of the file name
+        let other_driver = PipelineCacheKey {
+            driver_version: 8,
+            ..key
+        };
+        assert_ne!(key.file_name(), other_driver.file_name());
+    }
+}
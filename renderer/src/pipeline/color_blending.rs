@@ -1,4 +1,7 @@
-use super::{ColorBlendingSettings, ColorBlendingType, traits::{ConfigureColorBlending, ConfigureDynamicState}};
+use super::{
+    traits::{ConfigureColorBlending, ConfigureDynamicState},
+    BlendMode, ColorBlendingSettings, ColorBlendingType,
+};
 use crate::ConfigurePipeline;
 use erupt::vk1_0 as vk;
 impl<'a> ConfigureColorBlending for ConfigurePipeline<'a> {
@@ -7,7 +10,10 @@ impl<'a> ConfigureColorBlending for ConfigurePipeline<'a> {
         configure_blending: &mut dyn FnMut(&mut ColorBlendingSettings),
     ) -> &mut dyn ConfigureDynamicState {
         let mut pipeline_blend_settings = vk::PipelineColorBlendStateCreateInfoBuilder::new();
-        let mut blending_settings = ColorBlendingSettings::new(&mut pipeline_blend_settings);
+        let mut blending_settings = ColorBlendingSettings::new(
+            &mut pipeline_blend_settings,
+            self.enabled_features.independent_blend != 0,
+        );
 
         configure_blending(&mut blending_settings);
 
@@ -15,7 +21,9 @@ impl<'a> ConfigureColorBlending for ConfigurePipeline<'a> {
         if blending_settings.pipeline_settings.logic_op_enable == vk::TRUE {
             // We are using logic op based blending
             // Using an enum here doesn't save much
-            let blend = ColorBlendingType::BitwiseBlending(pipeline_blend_settings);
+            // Attachments are still needed for their color write masks
+            let ColorBlendingSettings { attachments, .. } = blending_settings;
+            let blend = ColorBlendingType::BitwiseBlending(pipeline_blend_settings, attachments);
             self.color_blending = Some(blend);
         } else {
             // we are using attachment based blending
@@ -42,22 +50,29 @@ impl<'a> ConfigureColorBlending for ConfigurePipeline<'a> {
 impl<'a, 'b: 'a> ColorBlendingSettings<'a, 'b> {
     pub fn new(
         blend_settings: &'a mut vk::PipelineColorBlendStateCreateInfoBuilder<'b>,
+        independent_blend: bool,
     ) -> ColorBlendingSettings<'a, 'b> {
         ColorBlendingSettings {
             pipeline_settings: blend_settings,
             attachments: Vec::new(),
             constants: [0.0; 4],
+            independent_blend,
         }
     }
 
+    /// True if Features::IndependentBlend is enabled, without it every attachment must blend the same way
+    pub fn independent_blending(&self) -> bool {
+        self.independent_blend
+    }
+
     /// Use bitwise operations to blend the two colours together, enabling this will disable blending by framebuffer attachments
     pub fn enable_bitwise_blending(&mut self, enable_blend: bool) {
         // The second method of blending colors
-        self.pipeline_settings.logic_op_enable(enable_blend);
+        *self.pipeline_settings = self.pipeline_settings.logic_op_enable(enable_blend);
     }
 
     pub fn logic_blending_op(&mut self, operation: vk::LogicOp) {
-        self.pipeline_settings.logic_op(operation);
+        *self.pipeline_settings = self.pipeline_settings.logic_op(operation);
     }
 
     pub fn set_blend_constants(&mut self, r: f32, g: f32, b: f32, a: f32) {
@@ -67,15 +82,71 @@ impl<'a, 'b: 'a> ColorBlendingSettings<'a, 'b> {
         self.constants[3] = a;
     }
 
+    /// Adds how the next colour attachment is blended, attachments are numbered in the order they are added.
+    /// Blending attachments differently fails when the pipeline is created unless Features::IndependentBlend
+    /// is enabled
     pub fn add_blend_attachment(
         &mut self,
         attachment: vk::PipelineColorBlendAttachmentStateBuilder<'b>,
     ) {
-        // add the factor and add the attachment
-        // TODO: All attachments must be the same unless independant blending is active
-        // TODO: Easier way to add color blending attachments
         self.attachments.push(attachment);
     }
+
+    /// Adds the next colour attachment blended with a preset, writing every colour component
+    pub fn add_blend_mode(&mut self, mode: BlendMode) {
+        self.attachments.push(mode.attachment());
+    }
+}
+
+impl BlendMode {
+    /// The blend state of an attachment using the mode
+    pub fn attachment(self) -> vk::PipelineColorBlendAttachmentStateBuilder<'static> {
+        use vk::BlendFactor as Factor;
+        // The colour source and destination factors followed by the alpha factors
+        let (src_color, dst_color, src_alpha, dst_alpha) = match self {
+            BlendMode::Opaque => (Factor::ONE, Factor::ZERO, Factor::ONE, Factor::ZERO),
+            BlendMode::StraightAlpha => (
+                Factor::SRC_ALPHA,
+                Factor::ONE_MINUS_SRC_ALPHA,
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::PremultipliedAlpha => (
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_ALPHA,
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Additive => (Factor::ONE, Factor::ONE, Factor::ONE, Factor::ONE),
+            // src * dst + dst * (1 - src alpha), where the source is transparent the destination is kept
+            BlendMode::Multiply => (
+                Factor::DST_COLOR,
+                Factor::ONE_MINUS_SRC_ALPHA,
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Screen => (
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_COLOR,
+                Factor::ONE,
+                Factor::ONE_MINUS_SRC_ALPHA,
+            ),
+        };
+        vk::PipelineColorBlendAttachmentStateBuilder::new()
+            .blend_enable(self != BlendMode::Opaque)
+            .src_color_blend_factor(src_color)
+            .dst_color_blend_factor(dst_color)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(src_alpha)
+            .dst_alpha_blend_factor(dst_alpha)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .color_write_mask(
+                vk::ColorComponentFlags::R
+                    | vk::ColorComponentFlags::G
+                    | vk::ColorComponentFlags::B
+                    | vk::ColorComponentFlags::A,
+            )
+    }
 }
 
 // pub fn configure_color_blend(&self) -> &Self {
@@ -90,3 +161,47 @@ impl<'a, 'b: 'a> ColorBlendingSettings<'a, 'b> {
 // colorBlendAttachment.alphaBlendOp = VK_BLEND_OP_ADD; // Optional
 //     self
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The colour a blend factor scales by for one channel
+    fn factor(factor: vk::BlendFactor, src: [f32; 2], dst: [f32; 2]) -> f32 {
+        let ([src_color, src_alpha], [dst_color, _]) = (src, dst);
+        match factor {
+            vk::BlendFactor::ZERO => 0.0,
+            vk::BlendFactor::ONE => 1.0,
+            vk::BlendFactor::DST_COLOR => dst_color,
+            vk::BlendFactor::ONE_MINUS_SRC_COLOR => 1.0 - src_color,
+            vk::BlendFactor::SRC_ALPHA => src_alpha,
+            vk::BlendFactor::ONE_MINUS_SRC_ALPHA => 1.0 - src_alpha,
+            _ => unimplemented!(),
+        }
+    }
+
+    // Blends a single colour channel and alpha of the source over the destination
+    fn blend(mode: BlendMode, src: [f32; 2], dst: [f32; 2]) -> f32 {
+        let attachment = mode.attachment();
+        src[0] * factor(attachment.src_color_blend_factor, src, dst)
+            + dst[0] * factor(attachment.dst_color_blend_factor, src, dst)
+    }
+
+    #[test]
+    fn test_transparent_overlay_keeps_destination() {
+        let transparent = [0.0, 0.0];
+        let destination = [0.6, 1.0];
+        for mode in [
+            BlendMode::PremultipliedAlpha,
+            BlendMode::Additive,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+        ]
+        .iter()
+        {
+            assert_eq!(blend(*mode, transparent, destination), 0.6, "{:?}", mode);
+        }
+        // An opaque multiply overlay darkens by its colour
+        assert!((blend(BlendMode::Multiply, [0.5, 1.0], destination) - 0.3).abs() < 1e-6);
+    }
+}
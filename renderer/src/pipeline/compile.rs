@@ -0,0 +1,250 @@
+use crate::error::{Error, ErrorKind};
+use erupt::vk1_0 as vk;
+use naga::back::spv;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::SourceLocation;
+use std::path::Path;
+
+fn compile_failed(name: &str, line: u32, message: String) -> Error {
+    Error::new(
+        ErrorKind::ShaderCompileFailed(String::from(name), line, message),
+        None,
+    )
+}
+
+// Line 0 is used when naga can't place the error in the source
+fn compile_failed_at(name: &str, location: Option<SourceLocation>, message: String) -> Error {
+    let line = location.map_or(0, |location| location.line_number);
+    compile_failed(name, line, message)
+}
+
+fn naga_stage(stage: vk::ShaderStageFlagBits) -> Option<naga::ShaderStage> {
+    match stage {
+        vk::ShaderStageFlagBits::VERTEX => Some(naga::ShaderStage::Vertex),
+        vk::ShaderStageFlagBits::FRAGMENT => Some(naga::ShaderStage::Fragment),
+        vk::ShaderStageFlagBits::COMPUTE => Some(naga::ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+fn supported_stage(name: &str, stage: vk::ShaderStageFlagBits) -> Result<naga::ShaderStage, Error> {
+    naga_stage(stage).ok_or_else(|| {
+        compile_failed(
+            name,
+            0,
+            format!("{:?} shaders can't be compiled at runtime", stage),
+        )
+    })
+}
+
+// Validates a parsed module and writes the entry point as SPIR-V
+fn write_spirv(
+    name: &str,
+    source: &str,
+    module: &naga::Module,
+    entry_name: &str,
+    stage: naga::ShaderStage,
+    flags: spv::WriterFlags,
+) -> Result<Vec<u32>, Error> {
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(module)
+        .map_err(|error| {
+            compile_failed_at(name, error.location(source), error.as_inner().to_string())
+        })?;
+    let options = spv::Options {
+        flags,
+        ..spv::Options::default()
+    };
+    let pipeline_options = spv::PipelineOptions {
+        shader_stage: stage,
+        entry_point: String::from(entry_name),
+    };
+    spv::write_vec(module, &info, &options, Some(&pipeline_options))
+        .map_err(|error| compile_failed(name, 0, error.to_string()))
+}
+
+/// Compiles GLSL to SPIR-V, name is used in errors and the errors include the line they're on
+pub(crate) fn compile_glsl(
+    name: &str,
+    source: &str,
+    entry_name: &str,
+    stage: vk::ShaderStageFlagBits,
+) -> Result<Vec<u32>, Error> {
+    let stage = supported_stage(name, stage)?;
+    let mut module = naga::front::glsl::Frontend::default()
+        .parse(&naga::front::glsl::Options::from(stage), source)
+        .map_err(|errors| match errors.errors.first() {
+            Some(error) => compile_failed_at(name, error.location(source), error.kind.to_string()),
+            None => compile_failed(name, 0, String::from("Unknown error")),
+        })?;
+    // GLSL entry points are always called main
+    for entry_point in module.entry_points.iter_mut() {
+        entry_point.name = String::from(entry_name);
+    }
+    // GLSL for Vulkan already uses Vulkan's coordinate space
+    let flags = spv::Options::default().flags & !spv::WriterFlags::ADJUST_COORDINATE_SPACE;
+    write_spirv(name, source, &module, entry_name, stage, flags)
+}
+
+/// Compiles WGSL to SPIR-V, name is used in errors and the errors include the line they're on
+pub(crate) fn compile_wgsl(
+    name: &str,
+    source: &str,
+    entry_name: &str,
+    stage: vk::ShaderStageFlagBits,
+) -> Result<Vec<u32>, Error> {
+    let stage = supported_stage(name, stage)?;
+    let module = naga::front::wgsl::parse_str(source).map_err(|error| {
+        compile_failed_at(name, error.location(source), String::from(error.message()))
+    })?;
+    // WGSL positions have y pointing up, the writer flips them into Vulkan's coordinate space
+    write_spirv(
+        name,
+        source,
+        &module,
+        entry_name,
+        stage,
+        spv::Options::default().flags,
+    )
+}
+
+/// Reads a shader from a file, .spv files are used as they are, .wgsl files are compiled as WGSL and
+/// .glsl, .vert, .frag and .comp files are compiled as GLSL
+pub(crate) fn load_shader_file(
+    path: &Path,
+    entry_name: &str,
+    stage: vk::ShaderStageFlagBits,
+) -> Result<Vec<u32>, Error> {
+    let name = path.display().to_string();
+    let read_failed = |error: std::io::Error| compile_failed(&name, 0, error.to_string());
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    match extension {
+        Some("spv") => {
+            let bytes = std::fs::read(path).map_err(read_failed)?;
+            erupt::utils::decode_spv(&bytes).map_err(read_failed)
+        }
+        Some("wgsl") => {
+            let source = std::fs::read_to_string(path).map_err(read_failed)?;
+            compile_wgsl(&name, &source, entry_name, stage)
+        }
+        Some("glsl") | Some("vert") | Some("frag") | Some("comp") => {
+            let source = std::fs::read_to_string(path).map_err(read_failed)?;
+            compile_glsl(&name, &source, entry_name, stage)
+        }
+        _ => Err(compile_failed(
+            &name,
+            0,
+            String::from("Expected a .spv, .wgsl, .glsl, .vert, .frag or .comp file"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::ShaderReflection;
+
+    const GLSL_FRAGMENT: &str = "#version 450\n\
+                                 layout(location = 0) in vec3 colour;\n\
+                                 layout(location = 0) out vec4 target;\n\
+                                 void main() {\n    target = vec4(colour, 1.0);\n}\n";
+
+    const WGSL_VERTEX: &str = "@vertex\n\
+                               fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {\n    \
+                               return vec4<f32>(position, 0.0, 1.0);\n}\n";
+
+    #[test]
+    fn test_compile_glsl() {
+        let code = compile_glsl(
+            "colour.frag",
+            GLSL_FRAGMENT,
+            "shade",
+            vk::ShaderStageFlagBits::FRAGMENT,
+        )
+        .unwrap();
+        // The entry point is renamed from main
+        let reflection =
+            ShaderReflection::new(&code, vk::ShaderStageFlagBits::FRAGMENT, "shade").unwrap();
+        assert_eq!(reflection.inputs()[0].format, vk::Format::R32G32B32_SFLOAT);
+
+        let broken = GLSL_FRAGMENT.replace("vec4(colour", "vec4(color");
+        let error = compile_glsl(
+            "colour.frag",
+            &broken,
+            "main",
+            vk::ShaderStageFlagBits::FRAGMENT,
+        )
+        .unwrap_err();
+        match error.kind() {
+            ErrorKind::ShaderCompileFailed(name, line, _) => {
+                assert_eq!((name.as_str(), *line), ("colour.frag", 5))
+            }
+            kind => panic!("Unexpected error {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_compile_wgsl() {
+        let code = compile_wgsl(
+            "quad.wgsl",
+            WGSL_VERTEX,
+            "vs_main",
+            vk::ShaderStageFlagBits::VERTEX,
+        )
+        .unwrap();
+        let reflection =
+            ShaderReflection::new(&code, vk::ShaderStageFlagBits::VERTEX, "vs_main").unwrap();
+        assert_eq!(reflection.inputs()[0].format, vk::Format::R32G32_SFLOAT);
+
+        let broken = WGSL_VERTEX.replace("position, 0.0", "position; 0.0");
+        let error = compile_wgsl(
+            "quad.wgsl",
+            &broken,
+            "vs_main",
+            vk::ShaderStageFlagBits::VERTEX,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::ShaderCompileFailed(_, 3, _)
+        ));
+        // naga doesn't support every stage
+        assert!(compile_wgsl(
+            "quad.wgsl",
+            WGSL_VERTEX,
+            "vs_main",
+            vk::ShaderStageFlagBits::GEOMETRY
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_load_shader_file_by_extension() {
+        let vertex = load_shader_file(
+            Path::new("src/shaders/shader.vert"),
+            "main",
+            vk::ShaderStageFlagBits::VERTEX,
+        )
+        .unwrap();
+        let compiled = load_shader_file(
+            Path::new("src/shaders/vert.spv"),
+            "main",
+            vk::ShaderStageFlagBits::VERTEX,
+        )
+        .unwrap();
+        assert_eq!(vertex[0], compiled[0]);
+        let fragment = load_shader_file(
+            Path::new("src/shaders/shader.frag"),
+            "main",
+            vk::ShaderStageFlagBits::FRAGMENT,
+        )
+        .unwrap();
+        ShaderReflection::new(&fragment, vk::ShaderStageFlagBits::FRAGMENT, "main").unwrap();
+        assert!(load_shader_file(
+            Path::new("src/shaders/shader.hlsl"),
+            "main",
+            vk::ShaderStageFlagBits::FRAGMENT
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use super::interface::{check_descriptor_bindings, check_push_constants, reflected_push_constants};
+use super::specialization::specialization_data;
+use super::{
+    ComputePipeline, ConfigureComputePipeline, ConfigureShaders, LayoutSettings, PipelineLayout,
+    SpecializationValue,
+};
+use crate::error::{Error, ErrorKind};
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+fn invalid_config(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidPipelineConfig, None).with_context(context)
+}
+
+impl<'a> ConfigureComputePipeline<'a> {
+    pub fn new(
+        device: &'a erupt::DeviceLoader,
+        pipeline_cache: vk::PipelineCache,
+        max_push_constants_size: u32,
+    ) -> ConfigureComputePipeline<'a> {
+        ConfigureComputePipeline {
+            device,
+            pipeline_cache,
+            configured_shaders: None,
+            descriptor_set_layouts: Vec::new(),
+            descriptor_set_bindings: Vec::new(),
+            push_constant_ranges: Vec::new(),
+            max_push_constants_size,
+        }
+    }
+
+    /// Configure the compute shader, exactly one compute shader must be created
+    pub fn configure_shaders(
+        &mut self,
+        define_shaders: &mut dyn FnMut(&mut ConfigureShaders) -> Result<(), Error>,
+    ) -> Result<&mut Self, Error> {
+        let mut configure_shaders = ConfigureShaders::new(self.device);
+        if let Err(error) = define_shaders(&mut configure_shaders) {
+            configure_shaders.destroy();
+            return Err(error);
+        }
+        if let Some(previous) = self.configured_shaders.replace(configure_shaders) {
+            previous.destroy();
+        }
+        Ok(self)
+    }
+
+    /// Sets a specialization constant of the compute shader, pipelines created afterwards use the value
+    /// and share the shader module with pipelines created before
+    pub fn specialize<T: Into<SpecializationValue>>(
+        &mut self,
+        constant_id: u32,
+        value: T,
+    ) -> Result<&mut Self, Error> {
+        self.configured_shaders
+            .as_mut()
+            .ok_or_else(|| invalid_config(&"The compute shader was not configured"))?
+            .specialize(vk::ShaderStageFlagBits::COMPUTE, constant_id, value)?;
+        Ok(self)
+    }
+
+    /// Configure the descriptor set layouts and push constants the shader reads
+    pub fn configure_layout(
+        &mut self,
+        configure_layout: &mut dyn FnMut(&mut LayoutSettings) -> Result<(), Error>,
+    ) -> Result<&mut Self, Error> {
+        let mut layout_settings = LayoutSettings::new(
+            &mut self.descriptor_set_layouts,
+            &mut self.descriptor_set_bindings,
+            &mut self.push_constant_ranges,
+            self.max_push_constants_size,
+        );
+        configure_layout(&mut layout_settings)?;
+        Ok(self)
+    }
+
+    /// Creates a compute pipeline from the configured shader and layout, the configuration is kept so
+    /// more pipelines can be created from it. The layout is checked against the reflected shader and its
+    /// push constants are filled in when none were configured
+    pub fn create_pipeline(&self) -> Result<ComputePipeline, Error> {
+        let shaders = self
+            .configured_shaders
+            .as_ref()
+            .ok_or_else(|| invalid_config(&"The compute shader was not configured"))?;
+        let shader = match shaders.shaders() {
+            [shader] if shader.shader_type == vk::ShaderStageFlagBits::COMPUTE => shader,
+            _ => {
+                return Err(invalid_config(
+                    &"A compute pipeline needs exactly one compute shader and no other stages",
+                ))
+            }
+        };
+        check_descriptor_bindings(shader, self.descriptor_set_bindings.as_slice())?;
+        let push_constant_ranges = if self.push_constant_ranges.is_empty() {
+            reflected_push_constants(shaders.shaders(), self.max_push_constants_size)?
+        } else {
+            check_push_constants(shader, self.push_constant_ranges.as_slice())?;
+            self.push_constant_ranges.clone()
+        };
+        let layout = PipelineLayout::new(
+            self.device,
+            self.descriptor_set_layouts.as_slice(),
+            push_constant_ranges.as_slice(),
+        )?;
+        let (entries, data) = specialization_data(shader.specialization.as_slice());
+        let specialization_info = vk::SpecializationInfoBuilder::new()
+            .map_entries(entries.as_slice())
+            .data_size(data.len())
+            .data(data.as_ptr() as *const std::ffi::c_void);
+        let mut stage = vk::PipelineShaderStageCreateInfoBuilder::new()
+            .stage(shader.shader_type)
+            .module(shader.shader_module)
+            .name(shader.entry_name.as_c_str());
+        if !entries.is_empty() {
+            stage = stage.specialization_info(&specialization_info);
+        }
+        let create_info = vk::ComputePipelineCreateInfoBuilder::new()
+            .stage(*stage)
+            .layout(layout.handle());
+        let result = unsafe {
+            self.device
+                .create_compute_pipelines(Some(self.pipeline_cache), &[create_info], None)
+        }
+        .result();
+        match result {
+            Ok(pipelines) => Ok(ComputePipeline {
+                handle: pipelines[0],
+                layout,
+            }),
+            Err(error) => {
+                unsafe {
+                    self.device
+                        .destroy_pipeline_layout(Some(layout.handle()), None)
+                };
+                Err(Error::from(error))
+            }
+        }
+    }
+
+    /// Destroys the shader module, pipelines that were already created remain valid
+    pub fn destroy(self) {
+        if let Some(shaders) = self.configured_shaders {
+            shaders.destroy();
+        }
+    }
+}
+
+impl ComputePipeline {
+    pub fn handle(&self) -> vk::Pipeline {
+        self.handle
+    }
+
+    pub fn layout(&self) -> &PipelineLayout {
+        &self.layout
+    }
+
+    /// Destroys the pipeline and its layout, the pipeline must not be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_pipeline(Some(self.handle), None) };
+        self.layout.destroy(device);
+    }
+}
@@ -10,9 +10,12 @@ impl<'a> ConfigureDepthStencil for ConfigurePipeline<'a> {
         &mut self,
         configure_depthstencil: &mut dyn FnMut(&mut DepthStencilSettings),
     ) -> &mut dyn super::traits::ConfigureColorBlending {
-        let mut pipeline_state = vk::PipelineDepthStencilStateCreateInfoBuilder::new();
+        let mut pipeline_state = vk::PipelineDepthStencilStateCreateInfoBuilder::new()
+            .depth_compare_op(vk::CompareOp::LESS)
+            .max_depth_bounds(1.0);
         let mut settings = DepthStencilSettings::new(&mut pipeline_state);
         configure_depthstencil(&mut settings);
+        self.depth_stencil = Some(pipeline_state);
         self
     }
 }
@@ -25,34 +28,34 @@ impl<'a, 'b: 'a> DepthStencilSettings<'a, 'b> {
     }
 
     pub fn enable_stencil_test(&mut self, enable_test: bool) {
-        self.settings.stencil_test_enable(enable_test);
+        *self.settings = self.settings.stencil_test_enable(enable_test);
     }
 
     pub fn min_depth_bounds(&mut self, min_bounds: f32) {
-        self.settings.min_depth_bounds(min_bounds);
+        *self.settings = self.settings.min_depth_bounds(min_bounds);
     }
 
     pub fn max_depth_bounds(&mut self, max_bounds: f32) {
-        self.settings.max_depth_bounds(max_bounds);
+        *self.settings = self.settings.max_depth_bounds(max_bounds);
     }
 
     pub fn front(&mut self, stencil_state: vk::StencilOpState) {
-        self.settings.front(stencil_state);
+        *self.settings = self.settings.front(stencil_state);
     }
 
     pub fn depth_writable(&mut self, is_writable: bool) {
-        self.settings.depth_write_enable(is_writable);
+        *self.settings = self.settings.depth_write_enable(is_writable);
     }
 
     pub fn depth_compare(&mut self, compare_type: vk::CompareOp) {
-        self.settings.depth_compare_op(compare_type);
+        *self.settings = self.settings.depth_compare_op(compare_type);
     }
 
     pub fn enable_depth_test(&mut self, is_enabled: bool) {
-        self.settings.depth_test_enable(is_enabled);
+        *self.settings = self.settings.depth_test_enable(is_enabled);
     }
 
     pub fn enable_depth_bounds(&mut self, enable: bool) {
-        self.settings.depth_bounds_test_enable(enable);
+        *self.settings = self.settings.depth_bounds_test_enable(enable);
     }
 }
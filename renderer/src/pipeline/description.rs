@@ -0,0 +1,593 @@
+use super::interface::{
+    check_descriptor_bindings, check_push_constants, check_vertex_input, reflected_push_constants,
+    reflected_vertex_input,
+};
+use super::specialization::{set_constant, specialization_data};
+use super::{
+    BlendAttachmentDescription, ColorBlendDescription, ColorBlendingType, ConfigurePipeline,
+    DepthStencilDescription, FloatBits, GraphicsPipeline, MultisampleDescription,
+    PipelineDescription, PipelineLayout, PushConstantDescription, RasterizerDescription,
+    ScissorDescription, ShaderStageDescription, SpecializationValue, StencilDescription,
+    VertexAttributeDescription, VertexBindingDescription, ViewportDescription,
+};
+use crate::error::{Error, ErrorKind};
+use crate::{RenderingFormats, Renderpass, VulkanDevice};
+use erupt::vk1_0 as vk;
+use std::hash::{Hash, Hasher};
+
+fn invalid_config(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidPipelineConfig, None).with_context(context)
+}
+
+impl PartialEq for FloatBits {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for FloatBits {}
+
+impl Hash for FloatBits {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl StencilDescription {
+    fn new(state: &vk::StencilOpState) -> StencilDescription {
+        StencilDescription {
+            fail_op: state.fail_op,
+            pass_op: state.pass_op,
+            depth_fail_op: state.depth_fail_op,
+            compare_op: state.compare_op,
+            compare_mask: state.compare_mask,
+            write_mask: state.write_mask,
+            reference: state.reference,
+        }
+    }
+
+    fn to_vulkan(self) -> vk::StencilOpState {
+        *vk::StencilOpStateBuilder::new()
+            .fail_op(self.fail_op)
+            .pass_op(self.pass_op)
+            .depth_fail_op(self.depth_fail_op)
+            .compare_op(self.compare_op)
+            .compare_mask(self.compare_mask)
+            .write_mask(self.write_mask)
+            .reference(self.reference)
+    }
+}
+
+impl BlendAttachmentDescription {
+    pub(super) fn new(state: &vk::PipelineColorBlendAttachmentState) -> BlendAttachmentDescription {
+        BlendAttachmentDescription {
+            blend_enable: state.blend_enable != 0,
+            src_color_blend_factor: state.src_color_blend_factor,
+            dst_color_blend_factor: state.dst_color_blend_factor,
+            color_blend_op: state.color_blend_op,
+            src_alpha_blend_factor: state.src_alpha_blend_factor,
+            dst_alpha_blend_factor: state.dst_alpha_blend_factor,
+            alpha_blend_op: state.alpha_blend_op,
+            color_write_mask: state.color_write_mask,
+        }
+    }
+
+    fn to_vulkan(self) -> vk::PipelineColorBlendAttachmentStateBuilder<'static> {
+        vk::PipelineColorBlendAttachmentStateBuilder::new()
+            .blend_enable(self.blend_enable)
+            .src_color_blend_factor(self.src_color_blend_factor)
+            .dst_color_blend_factor(self.dst_color_blend_factor)
+            .color_blend_op(self.color_blend_op)
+            .src_alpha_blend_factor(self.src_alpha_blend_factor)
+            .dst_alpha_blend_factor(self.dst_alpha_blend_factor)
+            .alpha_blend_op(self.alpha_blend_op)
+            .color_write_mask(self.color_write_mask)
+    }
+}
+
+impl<'a> ConfigurePipeline<'a> {
+    /// Copies the configured stages into a description that no longer borrows the configuration,
+    /// fails if a required stage was not configured or the shaders read something the pipeline doesn't provide.
+    /// Vertex input and push constants that were not configured are filled in from the shaders
+    pub fn description(&self) -> Result<PipelineDescription, Error> {
+        let shaders = self
+            .configured_shaders
+            .as_ref()
+            .ok_or_else(|| invalid_config(&"The shader stage was not configured"))?;
+        if !shaders
+            .shaders()
+            .iter()
+            .any(|shader| shader.shader_type == vk::ShaderStageFlagBits::VERTEX)
+        {
+            return Err(invalid_config(
+                &"The shader stage is missing a vertex shader",
+            ));
+        }
+        let input_assembly = self
+            .pipeline_input
+            .ok_or_else(|| invalid_config(&"The input assembly stage was not configured"))?;
+        let rasterizer = self
+            .rasterizer_configuration
+            .ok_or_else(|| invalid_config(&"The rasterizer stage was not configured"))?;
+        let multisample = self
+            .multisample_config
+            .ok_or_else(|| invalid_config(&"The multisampling stage was not configured"))?;
+        let viewports = self
+            .viewports_to_create
+            .as_ref()
+            .ok_or_else(|| invalid_config(&"The viewport stage was not configured"))?;
+        let mut dynamic_states = self.dynamic_states.clone();
+        if self.dynamic_viewports {
+            for state in [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR].iter() {
+                if !dynamic_states.contains(state) {
+                    dynamic_states.push(*state);
+                }
+            }
+        }
+        // Dynamic viewports and scissors only need a count when the pipeline is created
+        if viewports.is_empty() && !dynamic_states.contains(&vk::DynamicState::VIEWPORT) {
+            return Err(invalid_config(
+                &"The viewport stage did not create a viewport",
+            ));
+        }
+        if self.scissors_to_create.is_empty()
+            && !dynamic_states.contains(&vk::DynamicState::SCISSOR)
+        {
+            return Err(invalid_config(
+                &"The viewport stage did not create a scissor",
+            ));
+        }
+        if !self.dynamic_states.contains(&vk::DynamicState::LINE_WIDTH)
+            && rasterizer.line_width <= 0.0
+        {
+            return Err(invalid_config(
+                &"The rasterizer stage has a line width that is not positive",
+            ));
+        }
+        if !self.sample_masks.is_empty()
+            && (self.sample_masks.len() as u32) * 32 < multisample.rasterization_samples.0
+        {
+            return Err(invalid_config(
+                &"The multisampling stage needs a sample mask bit for every sample",
+            ));
+        }
+
+        // Shaders were reflected when they were created, the stages they read from are checked against them
+        let vertex_shader = shaders
+            .shaders()
+            .iter()
+            .find(|shader| shader.shader_type == vk::ShaderStageFlagBits::VERTEX)
+            .expect("The vertex shader was checked above");
+        let (vertex_bindings, vertex_attributes) = if self.vertex_binding_descriptions.is_empty()
+            && self.vertex_attribute_descriptions.is_empty()
+        {
+            reflected_vertex_input(vertex_shader.reflection())
+        } else {
+            check_vertex_input(
+                vertex_shader.reflection(),
+                self.vertex_binding_descriptions.as_slice(),
+                self.vertex_attribute_descriptions.as_slice(),
+            )?;
+            (
+                self.vertex_binding_descriptions.clone(),
+                self.vertex_attribute_descriptions.clone(),
+            )
+        };
+        for shader in shaders.shaders().iter() {
+            check_descriptor_bindings(shader, self.descriptor_set_bindings.as_slice())?;
+        }
+        let push_constant_ranges = if self.push_constant_ranges.is_empty() {
+            reflected_push_constants(shaders.shaders(), self.max_push_constants_size)?
+        } else {
+            for shader in shaders.shaders().iter() {
+                check_push_constants(shader, self.push_constant_ranges.as_slice())?;
+            }
+            self.push_constant_ranges.clone()
+        };
+
+        let color_blend = match &self.color_blending {
+            Some(ColorBlendingType::BlendWithAttachments(_, attachments, constants)) => {
+                ColorBlendDescription {
+                    logic_op: None,
+                    attachments: attachments
+                        .iter()
+                        .map(|attachment| BlendAttachmentDescription::new(attachment))
+                        .collect(),
+                    constants: [
+                        FloatBits(constants[0]),
+                        FloatBits(constants[1]),
+                        FloatBits(constants[2]),
+                        FloatBits(constants[3]),
+                    ],
+                }
+            }
+            Some(ColorBlendingType::BitwiseBlending(settings, attachments)) => {
+                ColorBlendDescription {
+                    logic_op: Some(settings.logic_op),
+                    attachments: attachments
+                        .iter()
+                        .map(|attachment| BlendAttachmentDescription::new(attachment))
+                        .collect(),
+                    constants: [FloatBits::default(); 4],
+                }
+            }
+            None => ColorBlendDescription {
+                logic_op: None,
+                attachments: Vec::new(),
+                constants: [FloatBits::default(); 4],
+            },
+        };
+        let description = PipelineDescription {
+            shaders: shaders
+                .shaders()
+                .iter()
+                .map(|shader| ShaderStageDescription {
+                    stage: shader.shader_type,
+                    module: shader.shader_module,
+                    entry_name: shader.entry_name.clone(),
+                    specialization: shader.specialization.clone(),
+                    path: shader.path.clone(),
+                })
+                .collect(),
+            vertex_bindings: vertex_bindings
+                .iter()
+                .map(|binding| VertexBindingDescription {
+                    binding: binding.binding,
+                    stride: binding.stride,
+                    input_rate: binding.input_rate,
+                })
+                .collect(),
+            vertex_attributes: vertex_attributes
+                .iter()
+                .map(|attribute| VertexAttributeDescription {
+                    location: attribute.location,
+                    binding: attribute.binding,
+                    format: attribute.format,
+                    offset: attribute.offset,
+                })
+                .collect(),
+            topology: input_assembly.topology,
+            primitive_restart: input_assembly.primitive_restart_enable != 0,
+            viewports: viewports
+                .iter()
+                .map(|viewport| ViewportDescription {
+                    x: FloatBits(viewport.x),
+                    y: FloatBits(viewport.y),
+                    width: FloatBits(viewport.width),
+                    height: FloatBits(viewport.height),
+                    min_depth: FloatBits(viewport.min_depth),
+                    max_depth: FloatBits(viewport.max_depth),
+                })
+                .collect(),
+            scissors: self
+                .scissors_to_create
+                .iter()
+                .map(|scissor| ScissorDescription {
+                    offset: (scissor.offset.x, scissor.offset.y),
+                    extent: (scissor.extent.width, scissor.extent.height),
+                })
+                .collect(),
+            rasterizer: RasterizerDescription {
+                depth_clamp: rasterizer.depth_clamp_enable != 0,
+                rasterizer_discard: rasterizer.rasterizer_discard_enable != 0,
+                polygon_mode: rasterizer.polygon_mode,
+                cull_mode: rasterizer.cull_mode,
+                front_face: rasterizer.front_face,
+                depth_bias: rasterizer.depth_bias_enable != 0,
+                depth_bias_constant_factor: FloatBits(rasterizer.depth_bias_constant_factor),
+                depth_bias_clamp: FloatBits(rasterizer.depth_bias_clamp),
+                depth_bias_slope_factor: FloatBits(rasterizer.depth_bias_slope_factor),
+                line_width: FloatBits(rasterizer.line_width),
+            },
+            multisample: MultisampleDescription {
+                samples: multisample.rasterization_samples,
+                sample_shading: multisample.sample_shading_enable != 0,
+                min_sample_shading: FloatBits(multisample.min_sample_shading),
+                sample_masks: self.sample_masks.clone(),
+                alpha_to_coverage: multisample.alpha_to_coverage_enable != 0,
+                alpha_to_one: multisample.alpha_to_one_enable != 0,
+            },
+            depth_stencil: self.depth_stencil.as_ref().map(|depth_stencil| {
+                DepthStencilDescription {
+                    depth_test: depth_stencil.depth_test_enable != 0,
+                    depth_write: depth_stencil.depth_write_enable != 0,
+                    depth_compare_op: depth_stencil.depth_compare_op,
+                    depth_bounds_test: depth_stencil.depth_bounds_test_enable != 0,
+                    stencil_test: depth_stencil.stencil_test_enable != 0,
+                    front: StencilDescription::new(&depth_stencil.front),
+                    back: StencilDescription::new(&depth_stencil.back),
+                    min_depth_bounds: FloatBits(depth_stencil.min_depth_bounds),
+                    max_depth_bounds: FloatBits(depth_stencil.max_depth_bounds),
+                }
+            }),
+            color_blend,
+            dynamic_states,
+            set_layouts: self.descriptor_set_layouts.clone(),
+            push_constant_ranges: push_constant_ranges
+                .iter()
+                .map(|range| PushConstantDescription {
+                    stages: range.stage_flags,
+                    offset: range.offset,
+                    size: range.size,
+                })
+                .collect(),
+        };
+        description.validate(&self.enabled_features, &self.limits)?;
+        Ok(description)
+    }
+}
+
+impl PipelineDescription {
+    /// A copy of the description with a different value for a specialization constant of a stage, the copy
+    /// uses the same shader modules so a variant of a pipeline doesn't need its shaders to be created again
+    pub fn specialize<T: Into<SpecializationValue>>(
+        &self,
+        stage: vk::ShaderStageFlagBits,
+        constant_id: u32,
+        value: T,
+    ) -> Result<PipelineDescription, Error> {
+        let mut description = self.clone();
+        let shader = description
+            .shaders
+            .iter_mut()
+            .find(|shader| shader.stage == stage)
+            .ok_or_else(|| invalid_config(&"The description has no shader for the stage"))?;
+        set_constant(&mut shader.specialization, stage, constant_id, value.into())?;
+        Ok(description)
+    }
+
+    /// Creates a pipeline for a subpass of the renderpass, can be called again for a renderpass
+    /// that was recreated with a different swapchain format
+    pub fn create(
+        &self,
+        device: &VulkanDevice,
+        renderpass: &Renderpass,
+        subpass: u32,
+    ) -> Result<GraphicsPipeline, Error> {
+        let render_pass = renderpass.subpass_handle(subpass)?;
+        self.validate(&device.enabled_features, &device.limits)?;
+        self.create_with(
+            device.handle(),
+            device.pipeline_cache().handle(),
+            render_pass,
+            subpass,
+            None,
+        )
+    }
+
+    /// Creates a pipeline that renders to attachments of the formats between begin_rendering and end_rendering
+    /// instead of in a renderpass, the device must support dynamic rendering
+    pub fn create_for_rendering(
+        &self,
+        device: &VulkanDevice,
+        formats: &RenderingFormats,
+    ) -> Result<GraphicsPipeline, Error> {
+        if !device.supports_dynamic_rendering() {
+            return Err(invalid_config(
+                &"Dynamic rendering was not enabled on the device",
+            ));
+        }
+        self.validate(&device.enabled_features, &device.limits)?;
+        self.validate_rendering_formats(formats, &device.limits)?;
+        self.create_with(
+            device.handle(),
+            device.pipeline_cache().handle(),
+            vk::RenderPass::null(),
+            0,
+            Some(formats),
+        )
+    }
+
+    // Without a renderpass the pipeline is created for dynamic rendering to attachments of the formats
+    pub(super) fn create_with(
+        &self,
+        device: &erupt::DeviceLoader,
+        pipeline_cache: vk::PipelineCache,
+        render_pass: vk::RenderPass,
+        subpass: u32,
+        formats: Option<&RenderingFormats>,
+    ) -> Result<GraphicsPipeline, Error> {
+        let push_constant_ranges: Vec<vk::PushConstantRange> = self
+            .push_constant_ranges
+            .iter()
+            .map(|range| vk::PushConstantRange {
+                stage_flags: range.stages,
+                offset: range.offset,
+                size: range.size,
+            })
+            .collect();
+        let layout = PipelineLayout::new(
+            device,
+            self.set_layouts.as_slice(),
+            push_constant_ranges.as_slice(),
+        )?;
+
+        let specialization_data: Vec<_> = self
+            .shaders
+            .iter()
+            .map(|shader| specialization_data(shader.specialization.as_slice()))
+            .collect();
+        let specialization_infos: Vec<vk::SpecializationInfo> = specialization_data
+            .iter()
+            .map(|(entries, data)| {
+                *vk::SpecializationInfoBuilder::new()
+                    .map_entries(entries.as_slice())
+                    .data_size(data.len())
+                    .data(data.as_ptr() as *const std::ffi::c_void)
+            })
+            .collect();
+        let stages: Vec<vk::PipelineShaderStageCreateInfoBuilder> = self
+            .shaders
+            .iter()
+            .zip(specialization_infos.iter())
+            .map(|(shader, specialization_info)| {
+                let stage = vk::PipelineShaderStageCreateInfoBuilder::new()
+                    .stage(shader.stage)
+                    .module(shader.module)
+                    .name(shader.entry_name.as_c_str());
+                if shader.specialization.is_empty() {
+                    stage
+                } else {
+                    stage.specialization_info(specialization_info)
+                }
+            })
+            .collect();
+        let bindings: Vec<vk::VertexInputBindingDescriptionBuilder> = self
+            .vertex_bindings
+            .iter()
+            .map(|binding| {
+                vk::VertexInputBindingDescriptionBuilder::new()
+                    .binding(binding.binding)
+                    .stride(binding.stride)
+                    .input_rate(binding.input_rate)
+            })
+            .collect();
+        let attributes: Vec<vk::VertexInputAttributeDescriptionBuilder> = self
+            .vertex_attributes
+            .iter()
+            .map(|attribute| {
+                vk::VertexInputAttributeDescriptionBuilder::new()
+                    .location(attribute.location)
+                    .binding(attribute.binding)
+                    .format(attribute.format)
+                    .offset(attribute.offset)
+            })
+            .collect();
+        let vertex_input = vk::PipelineVertexInputStateCreateInfoBuilder::new()
+            .vertex_binding_descriptions(bindings.as_slice())
+            .vertex_attribute_descriptions(attributes.as_slice());
+        let input_assembly = vk::PipelineInputAssemblyStateCreateInfoBuilder::new()
+            .topology(self.topology)
+            .primitive_restart_enable(self.primitive_restart);
+        let viewports: Vec<vk::ViewportBuilder> = self
+            .viewports
+            .iter()
+            .map(|viewport| {
+                vk::ViewportBuilder::new()
+                    .x(viewport.x.0)
+                    .y(viewport.y.0)
+                    .width(viewport.width.0)
+                    .height(viewport.height.0)
+                    .min_depth(viewport.min_depth.0)
+                    .max_depth(viewport.max_depth.0)
+            })
+            .collect();
+        let scissors: Vec<vk::Rect2DBuilder> = self
+            .scissors
+            .iter()
+            .map(|scissor| {
+                vk::Rect2DBuilder::new()
+                    .offset(vk::Offset2D {
+                        x: scissor.offset.0,
+                        y: scissor.offset.1,
+                    })
+                    .extent(vk::Extent2D {
+                        width: scissor.extent.0,
+                        height: scissor.extent.1,
+                    })
+            })
+            .collect();
+        let mut viewport_state = vk::PipelineViewportStateCreateInfoBuilder::new()
+            .viewports(viewports.as_slice())
+            .scissors(scissors.as_slice());
+        // A dynamic list that was left empty takes its count from the other list
+        let count = self.viewports.len().max(self.scissors.len()).max(1) as u32;
+        if self.viewports.is_empty() {
+            viewport_state.viewport_count = count;
+        }
+        if self.scissors.is_empty() {
+            viewport_state.scissor_count = count;
+        }
+        let rasterizer = vk::PipelineRasterizationStateCreateInfoBuilder::new()
+            .depth_clamp_enable(self.rasterizer.depth_clamp)
+            .rasterizer_discard_enable(self.rasterizer.rasterizer_discard)
+            .polygon_mode(self.rasterizer.polygon_mode)
+            .cull_mode(self.rasterizer.cull_mode)
+            .front_face(self.rasterizer.front_face)
+            .depth_bias_enable(self.rasterizer.depth_bias)
+            .depth_bias_constant_factor(self.rasterizer.depth_bias_constant_factor.0)
+            .depth_bias_clamp(self.rasterizer.depth_bias_clamp.0)
+            .depth_bias_slope_factor(self.rasterizer.depth_bias_slope_factor.0)
+            .line_width(self.rasterizer.line_width.0);
+        let mut multisample = vk::PipelineMultisampleStateCreateInfoBuilder::new()
+            .rasterization_samples(self.multisample.samples)
+            .sample_shading_enable(self.multisample.sample_shading)
+            .min_sample_shading(self.multisample.min_sample_shading.0)
+            .alpha_to_coverage_enable(self.multisample.alpha_to_coverage)
+            .alpha_to_one_enable(self.multisample.alpha_to_one);
+        if !self.multisample.sample_masks.is_empty() {
+            multisample = multisample.sample_mask(self.multisample.sample_masks.as_slice());
+        }
+        let depth_stencil = self.depth_stencil.map(|depth_stencil| {
+            vk::PipelineDepthStencilStateCreateInfoBuilder::new()
+                .depth_test_enable(depth_stencil.depth_test)
+                .depth_write_enable(depth_stencil.depth_write)
+                .depth_compare_op(depth_stencil.depth_compare_op)
+                .depth_bounds_test_enable(depth_stencil.depth_bounds_test)
+                .stencil_test_enable(depth_stencil.stencil_test)
+                .front(depth_stencil.front.to_vulkan())
+                .back(depth_stencil.back.to_vulkan())
+                .min_depth_bounds(depth_stencil.min_depth_bounds.0)
+                .max_depth_bounds(depth_stencil.max_depth_bounds.0)
+        });
+        let blend_attachments: Vec<vk::PipelineColorBlendAttachmentStateBuilder> = self
+            .color_blend
+            .attachments
+            .iter()
+            .map(|attachment| attachment.to_vulkan())
+            .collect();
+        let mut color_blending = vk::PipelineColorBlendStateCreateInfoBuilder::new()
+            .attachments(blend_attachments.as_slice());
+        match self.color_blend.logic_op {
+            Some(logic_op) => {
+                color_blending = color_blending.logic_op_enable(true).logic_op(logic_op);
+            }
+            None => {
+                let constants = &self.color_blend.constants;
+                color_blending = color_blending.blend_constants([
+                    constants[0].0,
+                    constants[1].0,
+                    constants[2].0,
+                    constants[3].0,
+                ]);
+            }
+        }
+        let dynamic_state = vk::PipelineDynamicStateCreateInfoBuilder::new()
+            .dynamic_states(self.dynamic_states.as_slice());
+
+        let mut create_info = vk::GraphicsPipelineCreateInfoBuilder::new()
+            .stages(stages.as_slice())
+            .vertex_input_state(&vertex_input)
+            .input_assembly_state(&input_assembly)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterizer)
+            .multisample_state(&multisample)
+            .color_blend_state(&color_blending)
+            .dynamic_state(&dynamic_state)
+            .layout(layout.handle())
+            .render_pass(render_pass)
+            .subpass(subpass);
+        if let Some(depth_stencil) = &depth_stencil {
+            create_info = create_info.depth_stencil_state(depth_stencil);
+        }
+        let rendering_info = formats.map(|formats| formats.to_vulkan());
+        if let Some(rendering_info) = &rendering_info {
+            create_info.p_next = rendering_info as *const _ as *const std::ffi::c_void;
+        }
+        let result =
+            unsafe { device.create_graphics_pipelines(Some(pipeline_cache), &[create_info], None) }
+                .result();
+        match result {
+            Ok(pipelines) => Ok(GraphicsPipeline {
+                handle: pipelines[0],
+                layout,
+                dynamic_states: self.dynamic_states.clone(),
+                viewport_count: count,
+            }),
+            Err(error) => {
+                unsafe { device.destroy_pipeline_layout(Some(layout.handle()), None) };
+                Err(Error::from(error))
+            }
+        }
+    }
+}
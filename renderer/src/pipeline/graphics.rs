@@ -0,0 +1,94 @@
+use super::{ConfigurePipeline, GraphicsPipeline, PipelineLayout, SpecializationValue};
+use crate::error::{Error, ErrorKind};
+use crate::{RenderingFormats, Renderpass, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl<'a> ConfigurePipeline<'a> {
+    /// Creates a pipeline for a subpass of the renderpass from the configured stages, the configuration
+    /// is kept so more pipelines can be created from it
+    pub fn create_pipeline(
+        &self,
+        renderpass: &Renderpass,
+        subpass: u32,
+    ) -> Result<GraphicsPipeline, Error> {
+        let description = self.description()?;
+        let render_pass = renderpass.subpass_handle(subpass)?;
+        description.create_with(self.device, self.pipeline_cache, render_pass, subpass, None)
+    }
+
+    /// Creates a pipeline for dynamic rendering to attachments of the formats instead of for a renderpass,
+    /// fails if the device doesn't support dynamic rendering
+    pub fn create_rendering_pipeline(
+        &self,
+        formats: &RenderingFormats,
+    ) -> Result<GraphicsPipeline, Error> {
+        if !self.dynamic_rendering {
+            return Err(Error::new(ErrorKind::InvalidPipelineConfig, None)
+                .with_context(&"Dynamic rendering was not enabled on the device"));
+        }
+        let description = self.description()?;
+        description.validate_rendering_formats(formats, &self.limits)?;
+        description.create_with(
+            self.device,
+            self.pipeline_cache,
+            vk::RenderPass::null(),
+            0,
+            Some(formats),
+        )
+    }
+
+    /// Sets a specialization constant of the shader for a stage, pipelines created afterwards use the value
+    /// and share the shader modules with pipelines created before
+    pub fn specialize<T: Into<SpecializationValue>>(
+        &mut self,
+        stage: vk::ShaderStageFlagBits,
+        constant_id: u32,
+        value: T,
+    ) -> Result<&mut Self, Error> {
+        self.configured_shaders
+            .as_mut()
+            .ok_or_else(|| {
+                Error::new(ErrorKind::InvalidPipelineConfig, None)
+                    .with_context(&"The shaders have not been configured")
+            })?
+            .specialize(stage, constant_id, value)?;
+        Ok(self)
+    }
+
+    /// Destroys the shader modules, pipelines that were already created remain valid
+    pub fn destroy(self) {
+        if let Some(shaders) = self.configured_shaders {
+            shaders.destroy();
+        }
+    }
+}
+
+impl GraphicsPipeline {
+    pub fn handle(&self) -> vk::Pipeline {
+        self.handle
+    }
+
+    /// The states that are set while recording rather than when the pipeline was created
+    pub fn dynamic_states(&self) -> &[vk::DynamicState] {
+        self.dynamic_states.as_slice()
+    }
+
+    pub fn is_dynamic(&self, state: vk::DynamicState) -> bool {
+        self.dynamic_states.contains(&state)
+    }
+
+    /// The number of viewports and scissors the pipeline uses
+    pub fn viewport_count(&self) -> u32 {
+        self.viewport_count
+    }
+
+    pub fn layout(&self) -> &PipelineLayout {
+        &self.layout
+    }
+
+    /// Destroys the pipeline and its layout, the pipeline must not be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_pipeline(Some(self.handle), None) };
+        self.layout.destroy(device);
+    }
+}
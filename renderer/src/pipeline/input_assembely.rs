@@ -11,7 +11,8 @@ impl<'a> ConfigureInputAssembely for ConfigurePipeline<'a> {
         // VkPipelineInputAssemblyStateCreateInfo inputAssembly{};
         // inputAssembly.topology = VK_PRIMITIVE_TOPOLOGY_TRIANGLE_LIST;
         // inputAssembly.primitiveRestartEnable = VK_FALSE;
-        let mut pipeline_input = vk::PipelineInputAssemblyStateCreateInfoBuilder::new();
+        let mut pipeline_input = vk::PipelineInputAssemblyStateCreateInfoBuilder::new()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
         let mut input_settings = InputAssembelySettings::new(&mut pipeline_input);
         configure_assembely(&mut input_settings);
         self.pipeline_input = Some(pipeline_input);
@@ -29,11 +30,12 @@ impl<'a, 'b: 'a> InputAssembelySettings<'a, 'b> {
     }
 
     pub fn set_topology(&mut self, topology_to_use: vk::PrimitiveTopology) {
-        self.pipeline_assembely.topology(topology_to_use);
+        *self.pipeline_assembely = self.pipeline_assembely.topology(topology_to_use);
     }
 
     pub fn set_restart(&mut self, can_restart: bool) {
-        self.pipeline_assembely
+        *self.pipeline_assembely = self
+            .pipeline_assembely
             .primitive_restart_enable(can_restart);
     }
 }
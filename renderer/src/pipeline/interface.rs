@@ -0,0 +1,351 @@
+use super::{ConfigurePipeline, ShaderData, ShaderDescriptorBinding, ShaderReflection};
+use crate::error::{Error, ErrorKind};
+use crate::{DescriptorSetLayout, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+fn mismatch(message: String) -> Error {
+    Error::new(ErrorKind::ShaderInterfaceMismatch(message), None)
+}
+
+// The type a shader sees when it reads an attribute of a given format
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumericType {
+    Float,
+    Double,
+    SignedInt,
+    UnsignedInt,
+}
+
+fn numeric_type(format: vk::Format) -> Option<NumericType> {
+    use vk::Format as F;
+    match format {
+        F::R64_SFLOAT | F::R64G64_SFLOAT | F::R64G64B64_SFLOAT | F::R64G64B64A64_SFLOAT => {
+            Some(NumericType::Double)
+        }
+        F::R8_SINT
+        | F::R8G8_SINT
+        | F::R8G8B8_SINT
+        | F::B8G8R8_SINT
+        | F::R8G8B8A8_SINT
+        | F::B8G8R8A8_SINT
+        | F::A8B8G8R8_SINT_PACK32
+        | F::A2R10G10B10_SINT_PACK32
+        | F::A2B10G10R10_SINT_PACK32
+        | F::R16_SINT
+        | F::R16G16_SINT
+        | F::R16G16B16_SINT
+        | F::R16G16B16A16_SINT
+        | F::R32_SINT
+        | F::R32G32_SINT
+        | F::R32G32B32_SINT
+        | F::R32G32B32A32_SINT => Some(NumericType::SignedInt),
+        F::R8_UINT
+        | F::R8G8_UINT
+        | F::R8G8B8_UINT
+        | F::B8G8R8_UINT
+        | F::R8G8B8A8_UINT
+        | F::B8G8R8A8_UINT
+        | F::A8B8G8R8_UINT_PACK32
+        | F::A2R10G10B10_UINT_PACK32
+        | F::A2B10G10R10_UINT_PACK32
+        | F::R16_UINT
+        | F::R16G16_UINT
+        | F::R16G16B16_UINT
+        | F::R16G16B16A16_UINT
+        | F::R32_UINT
+        | F::R32G32_UINT
+        | F::R32G32B32_UINT
+        | F::R32G32B32A32_UINT => Some(NumericType::UnsignedInt),
+        // 64 bit integers are not something reflection produces so they are never checked
+        F::R64_UINT
+        | F::R64G64_UINT
+        | F::R64G64B64_UINT
+        | F::R64G64B64A64_UINT
+        | F::R64_SINT
+        | F::R64G64_SINT
+        | F::R64G64B64_SINT
+        | F::R64G64B64A64_SINT => None,
+        // Normalized, scaled and floating point formats are all read as floats
+        _ => Some(NumericType::Float),
+    }
+}
+
+// The size of the formats reflection produces, used to pack attributes when filling in vertex input
+fn format_size(format: vk::Format) -> u32 {
+    use vk::Format as F;
+    match format {
+        F::R16_SFLOAT | F::R16_SINT | F::R16_UINT => 2,
+        F::R16G16_SFLOAT | F::R16G16_SINT | F::R16G16_UINT => 4,
+        F::R16G16B16_SFLOAT | F::R16G16B16_SINT | F::R16G16B16_UINT => 6,
+        F::R16G16B16A16_SFLOAT | F::R16G16B16A16_SINT | F::R16G16B16A16_UINT => 8,
+        F::R32_SFLOAT | F::R32_SINT | F::R32_UINT => 4,
+        F::R32G32_SFLOAT | F::R32G32_SINT | F::R32G32_UINT => 8,
+        F::R32G32B32_SFLOAT | F::R32G32B32_SINT | F::R32G32B32_UINT => 12,
+        F::R32G32B32A32_SFLOAT | F::R32G32B32A32_SINT | F::R32G32B32A32_UINT => 16,
+        F::R64_SFLOAT => 8,
+        F::R64G64_SFLOAT => 16,
+        F::R64G64B64_SFLOAT => 24,
+        F::R64G64B64A64_SFLOAT => 32,
+        _ => 0,
+    }
+}
+
+/// Describes one per vertex binding with the inputs of the vertex shader tightly packed in location order
+pub(super) fn reflected_vertex_input(
+    reflection: &ShaderReflection,
+) -> (
+    Vec<vk::VertexInputBindingDescription>,
+    Vec<vk::VertexInputAttributeDescription>,
+) {
+    if reflection.inputs().is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let mut offset = 0;
+    let attributes = reflection
+        .inputs()
+        .iter()
+        .map(|input| {
+            let attribute = vk::VertexInputAttributeDescriptionBuilder::new()
+                .location(input.location)
+                .binding(0)
+                .format(input.format)
+                .offset(offset);
+            offset += format_size(input.format);
+            *attribute
+        })
+        .collect();
+    let binding = vk::VertexInputBindingDescriptionBuilder::new()
+        .binding(0)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .stride(offset);
+    (vec![*binding], attributes)
+}
+
+/// Checks that every location the vertex shader reads has an attribute the shader can read it from
+pub(super) fn check_vertex_input(
+    reflection: &ShaderReflection,
+    bindings: &[vk::VertexInputBindingDescription],
+    attributes: &[vk::VertexInputAttributeDescription],
+) -> Result<(), Error> {
+    for attribute in attributes.iter() {
+        if !bindings
+            .iter()
+            .any(|binding| binding.binding == attribute.binding)
+        {
+            return Err(mismatch(format!(
+                "The attribute at location {} uses binding {} which was never added",
+                attribute.location, attribute.binding
+            )));
+        }
+    }
+    for input in reflection.inputs().iter() {
+        let attribute = attributes
+            .iter()
+            .find(|attribute| attribute.location == input.location)
+            .ok_or_else(|| {
+                mismatch(format!(
+                    "The vertex shader reads location {} as {:?} but no attribute provides it",
+                    input.location, input.format
+                ))
+            })?;
+        if let Some(numeric) = numeric_type(attribute.format) {
+            if Some(numeric) != numeric_type(input.format) {
+                return Err(mismatch(format!(
+                    "The vertex shader reads location {} as {:?} but the attribute is {:?}",
+                    input.location, input.format, attribute.format
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every descriptor the shader reads is in the layout with the same type and is visible to the stage
+pub(super) fn check_descriptor_bindings(
+    shader: &ShaderData,
+    set_bindings: &[Vec<vk::DescriptorSetLayoutBinding>],
+) -> Result<(), Error> {
+    let stage = vk::ShaderStageFlags::from_bits_truncate(shader.shader_type.0);
+    for reflected in shader.reflection.descriptor_bindings().iter() {
+        let bindings = set_bindings.get(reflected.set as usize).ok_or_else(|| {
+            mismatch(format!(
+                "The {:?} shader reads binding {} of set {} but the layout has {} descriptor sets",
+                shader.shader_type,
+                reflected.binding,
+                reflected.set,
+                set_bindings.len()
+            ))
+        })?;
+        let binding = bindings
+            .iter()
+            .find(|binding| binding.binding == reflected.binding)
+            .ok_or_else(|| {
+                mismatch(format!(
+                    "The {:?} shader reads binding {} of set {} but the set layout has no such binding",
+                    shader.shader_type, reflected.binding, reflected.set
+                ))
+            })?;
+        if binding.descriptor_type != reflected.descriptor_type {
+            return Err(mismatch(format!(
+                "The {:?} shader reads binding {} of set {} as {:?} but the set layout declares {:?}",
+                shader.shader_type,
+                reflected.binding,
+                reflected.set,
+                reflected.descriptor_type,
+                binding.descriptor_type
+            )));
+        }
+        if binding.descriptor_count < reflected.count {
+            return Err(mismatch(format!(
+                "The {:?} shader reads {} descriptors at binding {} of set {} but the set layout has {}",
+                shader.shader_type,
+                reflected.count,
+                reflected.binding,
+                reflected.set,
+                binding.descriptor_count
+            )));
+        }
+        if !binding.stage_flags.contains(stage) {
+            return Err(mismatch(format!(
+                "Binding {} of set {} is not visible to the {:?} shader that reads it",
+                reflected.binding, reflected.set, shader.shader_type
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// One push constant range per stage that reads a block
+pub(super) fn reflected_push_constants(
+    shaders: &[ShaderData],
+    max_push_constants_size: u32,
+) -> Result<Vec<vk::PushConstantRange>, Error> {
+    let mut ranges = Vec::new();
+    for shader in shaders.iter() {
+        if let Some((offset, size)) = shader.reflection.push_constants() {
+            if offset + size > max_push_constants_size {
+                return Err(mismatch(format!(
+                    "The {:?} shader reads {} bytes of push constants but the device only has {}",
+                    shader.shader_type,
+                    offset + size,
+                    max_push_constants_size
+                )));
+            }
+            let range = vk::PushConstantRangeBuilder::new()
+                .stage_flags(vk::ShaderStageFlags::from_bits_truncate(
+                    shader.shader_type.0,
+                ))
+                .offset(offset)
+                .size(size);
+            ranges.push(*range);
+        }
+    }
+    Ok(ranges)
+}
+
+/// Checks that the push constants the shader reads are covered by a range for its stage
+pub(super) fn check_push_constants(
+    shader: &ShaderData,
+    ranges: &[vk::PushConstantRange],
+) -> Result<(), Error> {
+    let stage = vk::ShaderStageFlags::from_bits_truncate(shader.shader_type.0);
+    if let Some((offset, size)) = shader.reflection.push_constants() {
+        let covered = ranges.iter().any(|range| {
+            range.stage_flags.contains(stage)
+                && range.offset <= offset
+                && offset + size <= range.offset + range.size
+        });
+        if !covered {
+            return Err(mismatch(format!(
+                "The {:?} shader reads push constants from byte {} to {} but no range for the stage covers them",
+                shader.shader_type,
+                offset,
+                offset + size
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Merges the bindings every stage reads, the same binding must have the same type in every stage
+fn merged_bindings(
+    shaders: &[ShaderData],
+) -> Result<Vec<(ShaderDescriptorBinding, vk::ShaderStageFlags)>, Error> {
+    let mut merged: Vec<(ShaderDescriptorBinding, vk::ShaderStageFlags)> = Vec::new();
+    for shader in shaders.iter() {
+        let stage = vk::ShaderStageFlags::from_bits_truncate(shader.shader_type.0);
+        for reflected in shader.reflection.descriptor_bindings().iter() {
+            match merged.iter_mut().find(|(binding, _)| {
+                binding.set == reflected.set && binding.binding == reflected.binding
+            }) {
+                Some((binding, _)) if binding.descriptor_type != reflected.descriptor_type => {
+                    return Err(mismatch(format!(
+                        "Binding {} of set {} is read as {:?} and {:?} by different stages",
+                        reflected.binding,
+                        reflected.set,
+                        binding.descriptor_type,
+                        reflected.descriptor_type
+                    )))
+                }
+                Some((binding, stages)) => {
+                    binding.count = binding.count.max(reflected.count);
+                    *stages |= stage;
+                }
+                None => merged.push((*reflected, stage)),
+            }
+        }
+    }
+    Ok(merged)
+}
+
+impl<'a> ConfigurePipeline<'a> {
+    /// Creates a layout for each descriptor set the configured shaders read, numbered from set zero,
+    /// the layouts are owned by the caller and are added to the pipeline with configure_layout
+    pub fn create_reflected_set_layouts(
+        &self,
+        device: &VulkanDevice,
+    ) -> Result<Vec<DescriptorSetLayout>, Error> {
+        let shaders = match &self.configured_shaders {
+            Some(shaders) => shaders.shaders(),
+            None => return Ok(Vec::new()),
+        };
+        let merged = merged_bindings(shaders)?;
+        let set_count = merged
+            .iter()
+            .map(|(binding, _)| binding.set + 1)
+            .max()
+            .unwrap_or(0);
+        let mut layouts: Vec<DescriptorSetLayout> = Vec::new();
+        for set in 0..set_count {
+            let mut configure = device.create_descriptor_set_layout();
+            for (binding, stages) in merged.iter().filter(|(binding, _)| binding.set == set) {
+                // Runtime sized arrays need binding flags only the caller can choose
+                if binding.count == 0 {
+                    for layout in layouts {
+                        layout.destroy(device);
+                    }
+                    return Err(mismatch(format!(
+                        "Binding {} of set {} is a runtime sized array, its layout must be created by hand",
+                        binding.binding, binding.set
+                    )));
+                }
+                configure = configure.binding(
+                    binding.binding,
+                    binding.descriptor_type,
+                    *stages,
+                    binding.count,
+                );
+            }
+            match configure.build() {
+                Ok(layout) => layouts.push(layout),
+                Err(error) => {
+                    for layout in layouts {
+                        layout.destroy(device);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(layouts)
+    }
+}
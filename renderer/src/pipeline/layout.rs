@@ -1,6 +1,8 @@
 use crate::ConfigurePipeline;
 
-use super::{LayoutSettings, traits::ConfigureLayout};
+use super::{traits::ConfigureLayout, LayoutSettings, PipelineLayout, PushConstants};
+use crate::error::{Error, ErrorKind};
+use crate::{DescriptorSetLayout, VulkanDevice};
 use erupt::vk1_0 as vk;
 // pub fn configure_layout(mut self) -> Self {
 // TODO: Create the uniform variables - ie globals that are passed to the shaders
@@ -19,32 +21,165 @@ use erupt::vk1_0 as vk;
 // }
 
 impl<'a> ConfigureLayout for ConfigurePipeline<'a> {
-    fn configure_layout(&mut self, configure_layout: &mut dyn FnMut(&mut super::LayoutSettings)) {
-        let mut pipeline_layout_settings = vk::PipelineLayoutCreateInfoBuilder::new();
-        let mut push_constants: Vec<vk::PushConstantRangeBuilder> = Vec::new();
-        let mut layout_settings = LayoutSettings::new(&mut pipeline_layout_settings, &mut push_constants);
+    fn configure_layout(
+        &mut self,
+        configure_layout: &mut dyn FnMut(&mut super::LayoutSettings) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut layout_settings = LayoutSettings::new(
+            &mut self.descriptor_set_layouts,
+            &mut self.descriptor_set_bindings,
+            &mut self.push_constant_ranges,
+            self.max_push_constants_size,
+        );
+        configure_layout(&mut layout_settings)
         // TODO: Should return a ConfiguredPipeline, ie configured but not created
         // TODO: Creating a pipeline should not free a configured pipeline
     }
 }
 
-impl<'a, 'b: 'a> LayoutSettings<'a, 'b> {
-    pub fn new(pipeline_settings: &'a mut vk::PipelineLayoutCreateInfoBuilder<'b>, push_constants: &'a mut Vec<vk::PushConstantRangeBuilder<'b>>) -> LayoutSettings<'a, 'b> {
+impl PipelineLayout {
+    // Each pipeline gets its own layout so that destroying one pipeline never invalidates another
+    pub(super) fn new(
+        device: &erupt::DeviceLoader,
+        set_layouts: &[vk::DescriptorSetLayout],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Result<PipelineLayout, Error> {
+        let range_builders: Vec<vk::PushConstantRangeBuilder> = push_constant_ranges
+            .iter()
+            .map(|range| range.into_builder())
+            .collect();
+        let create_info = vk::PipelineLayoutCreateInfoBuilder::new()
+            .set_layouts(set_layouts)
+            .push_constant_ranges(range_builders.as_slice());
+        let handle = unsafe { device.create_pipeline_layout(&create_info, None, None) }.result()?;
+        Ok(PipelineLayout {
+            handle,
+            set_layouts: set_layouts.to_vec(),
+            push_constant_ranges: push_constant_ranges.to_vec(),
+        })
+    }
+
+    pub fn handle(&self) -> vk::PipelineLayout {
+        self.handle
+    }
+
+    pub fn set_layouts(&self) -> &[vk::DescriptorSetLayout] {
+        self.set_layouts.as_slice()
+    }
+
+    pub fn push_constant_ranges(&self) -> &[vk::PushConstantRange] {
+        self.push_constant_ranges.as_slice()
+    }
+
+    /// True if the layout has a push constant range covering the block of T for all of its stages
+    pub fn has_push_constants<T: PushConstants>(&self) -> bool {
+        self.push_constant_ranges.iter().any(|range| {
+            range.stage_flags.contains(T::STAGES)
+                && range.offset <= T::OFFSET
+                && T::OFFSET + T::SIZE <= range.offset + range.size
+        })
+    }
+
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe {
+            device
+                .handle()
+                .destroy_pipeline_layout(Some(self.handle), None)
+        };
+    }
+}
+
+impl<'a> LayoutSettings<'a> {
+    pub fn new(
+        set_layouts: &'a mut Vec<vk::DescriptorSetLayout>,
+        set_bindings: &'a mut Vec<Vec<vk::DescriptorSetLayoutBinding>>,
+        push_constants: &'a mut Vec<vk::PushConstantRange>,
+        max_push_constants_size: u32,
+    ) -> LayoutSettings<'a> {
         LayoutSettings {
-            pipeline_settings,
+            set_layouts,
+            set_bindings,
             push_constants,
+            max_push_constants_size,
         }
     }
 
-    pub fn add_descriptor_set_layout(&mut self) {
-        // Uniform buffers etc - this is tricky because they fall outside the scope of VulkanFlex???
-        // VulkanFlex doesn't need to create the descriptor sets it just needs to access them
-        // self.pipeline_settings.set_layouts(push_constant_ranges)
+    /// Adds the layout of the next descriptor set, sets are numbered in the order they are added
+    pub fn add_descriptor_set_layout(&mut self, layout: &DescriptorSetLayout) {
+        self.set_layouts.push(layout.handle());
+        // The bindings are kept so they can be checked against the shaders
+        self.set_bindings.push(layout.bindings().to_vec());
     }
 
     /// Push constants represent a high speed path to modify constant data in pipelines that is expected to outperform memory-backed resource updates
-    pub fn add_push_constant(&mut self, push_constant: vk::PushConstantRangeBuilder<'b>) {
+    pub fn add_push_constant(&mut self, push_constant: vk::PushConstantRangeBuilder) {
+        self.push_constants.push(*push_constant);
+    }
+
+    /// Adds a push constant block whose size and stages come from a Rust struct, the block must fit in the
+    /// push constant space of the device and no stage can read more than one block
+    pub fn add_push_constants<T: PushConstants>(&mut self) -> Result<(), Error> {
+        if T::OFFSET + T::SIZE > self.max_push_constants_size {
+            return Err(
+                Error::new(ErrorKind::InvalidPipelineConfig, None).with_context(
+                    &"The push constants are larger than the maxPushConstantsSize of the device",
+                ),
+            );
+        }
+        if self
+            .push_constants
+            .iter()
+            .any(|range| range.stage_flags.intersects(T::STAGES))
+        {
+            return Err(Error::new(ErrorKind::InvalidPipelineConfig, None)
+                .with_context(&"A shader stage can only read one push constant block"));
+        }
+        let push_constant = vk::PushConstantRangeBuilder::new()
+            .stage_flags(T::STAGES)
+            .offset(T::OFFSET)
+            .size(T::SIZE);
+        self.push_constants.push(*push_constant);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.push_constants.push(push_constant);
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Transform {
+        offset: [f32; 2],
+        scale: [f32; 2],
+        texture: u32,
+        _padding: [u32; 3],
     }
-}
\ No newline at end of file
+
+    impl PushConstants for Transform {
+        const STAGES: vk::ShaderStageFlags = vk::ShaderStageFlags::VERTEX;
+    }
+
+    #[test]
+    fn test_add_push_constants() {
+        let mut set_layouts = Vec::new();
+        let mut set_bindings = Vec::new();
+        let mut push_constants = Vec::new();
+        let mut settings = LayoutSettings::new(
+            &mut set_layouts,
+            &mut set_bindings,
+            &mut push_constants,
+            128,
+        );
+        assert!(settings.add_push_constants::<Transform>().is_ok());
+        // The vertex stage already reads a block
+        assert!(settings.add_push_constants::<Transform>().is_err());
+        assert_eq!(push_constants[0].size, 32);
+        assert_eq!(push_constants[0].stage_flags, vk::ShaderStageFlags::VERTEX);
+
+        let mut push_constants = Vec::new();
+        let mut settings =
+            LayoutSettings::new(&mut set_layouts, &mut set_bindings, &mut push_constants, 16);
+        assert!(settings.add_push_constants::<Transform>().is_err());
+    }
+}
@@ -1,24 +1,36 @@
+mod cache;
 mod color_blending;
+mod compile;
+mod compute;
 mod config;
 mod depth_stencil;
+mod description;
+mod dynamic_state;
+mod graphics;
 mod input_assembely;
+mod interface;
+mod layout;
 mod multisampling;
 mod rasterizer;
+mod reflect;
 mod shaders;
+mod specialization;
+mod store;
 mod traits;
+mod validate;
 mod vertex_input;
 mod viewport;
-mod layout;
-mod dynamic_state;
 
 use erupt::vk1_0 as vk;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub use std::ffi::CString;
+pub use traits::{PushConstants, Vertex, VertexAttributeType};
 
 /// Configure the rasterization options
-#[derive(Debug, Clone)]
 pub struct RasterizerSettings<'a, 'b: 'a> {
-    settings: &'a vk::PipelineRasterizationStateCreateInfoBuilder<'b>,
+    settings: &'a mut vk::PipelineRasterizationStateCreateInfoBuilder<'b>,
 }
 
 pub struct ConfigureShaders<'a> {
@@ -30,24 +42,70 @@ pub struct VertexInputSettings<'a> {
     vertex_attributes: &'a mut Vec<vk::VertexInputAttributeDescription>,
     vertex_bindings: &'a mut Vec<vk::VertexInputBindingDescription>,
 }
+/// Collects the viewports and scissors of a pipeline, there must be a scissor for every viewport
 pub struct ViewportManager {
-    viewports: Vec<Viewport>,
-}
-pub struct Viewport {
-    viewport: vk::Viewport,
-    scissor: vk::Rect2D,
+    viewports: Vec<vk::Viewport>,
+    scissors: Vec<vk::Rect2D>,
+    max_viewports: u32,
+    dynamic_count: Option<u32>,
 }
 pub struct ShaderData {
     entry_name: CString,
     shader_module: vk::ShaderModule,
     shader_type: vk::ShaderStageFlagBits,
+    reflection: ShaderReflection,
+    // Every specialization constant the shader declares with the value it will be created with
+    specialization: Vec<(u32, SpecializationValue)>,
+    // The file the shader was loaded from, pipelines using it can be rebuilt when it changes
+    path: Option<PathBuf>,
+}
+
+/// The interface of a shader entry point found by reflecting its SPIR-V
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderReflection {
+    entry_points: Vec<(vk::ShaderStageFlagBits, String)>,
+    inputs: Vec<ShaderInput>,
+    descriptor_bindings: Vec<ShaderDescriptorBinding>,
+    push_constants: Option<(u32, u32)>,
+    specialization_constants: Vec<(u32, SpecializationValue)>,
+}
+
+/// The value of a specialization constant, it must have the type the constant is declared with in the shader.
+/// Values are compared by their bits so descriptions that use them can be keys
+#[derive(Debug, Clone, Copy)]
+pub enum SpecializationValue {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Double(f64),
+}
+
+/// A location the shader reads from the previous stage or from a vertex attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShaderInput {
+    pub location: u32,
+    pub format: vk::Format,
+}
+
+/// A descriptor the shader reads, a count of zero is a runtime sized array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShaderDescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32,
 }
 
 pub struct ConfigurePipeline<'a> {
     device: &'a erupt::DeviceLoader,
+    pipeline_cache: vk::PipelineCache,
     shader_config_modules: Vec<vk::ShaderModule>,
     pipeline_input: Option<vk::PipelineInputAssemblyStateCreateInfoBuilder<'a>>,
-    viewports_to_create: Option<Vec<Viewport>>,
+    viewports_to_create: Option<Vec<vk::Viewport>>,
+    scissors_to_create: Vec<vk::Rect2D>,
+    // Set when the viewport stage leaves the viewports and scissors to be set while recording
+    dynamic_viewports: bool,
     vertex_attribute_descriptions: Vec<vk::VertexInputAttributeDescription>,
     vertex_binding_descriptions: Vec<vk::VertexInputBindingDescription>,
     vertex_input_info: Option<vk::PipelineVertexInputStateCreateInfo>,
@@ -56,10 +114,32 @@ pub struct ConfigurePipeline<'a> {
     multisample_config: Option<vk::PipelineMultisampleStateCreateInfoBuilder<'a>>,
     sample_masks: Vec<vk::SampleMask>,
     color_blending: Option<ColorBlendingType<'a>>,
+    depth_stencil: Option<vk::PipelineDepthStencilStateCreateInfoBuilder<'a>>,
+    dynamic_states: Vec<vk::DynamicState>,
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    descriptor_set_bindings: Vec<Vec<vk::DescriptorSetLayoutBinding>>,
+    push_constant_ranges: Vec<vk::PushConstantRange>,
+    max_push_constants_size: u32,
+    // Settings are checked against these when the pipeline is described
+    enabled_features: vk::PhysicalDeviceFeatures,
+    limits: vk::PhysicalDeviceLimits,
+    // Pipelines can be created without a renderpass when the device supports dynamic rendering
+    dynamic_rendering: bool,
+}
+
+/// Configures a compute pipeline from a single compute shader and a layout
+pub struct ConfigureComputePipeline<'a> {
+    device: &'a erupt::DeviceLoader,
+    pipeline_cache: vk::PipelineCache,
+    configured_shaders: Option<ConfigureShaders<'a>>,
+    descriptor_set_layouts: Vec<vk::DescriptorSetLayout>,
+    descriptor_set_bindings: Vec<Vec<vk::DescriptorSetLayoutBinding>>,
+    push_constant_ranges: Vec<vk::PushConstantRange>,
+    max_push_constants_size: u32,
 }
 
 pub struct InputAssembelySettings<'a, 'b: 'a> {
-    pipeline_assembely: &'a vk::PipelineInputAssemblyStateCreateInfoBuilder<'b>,
+    pipeline_assembely: &'a mut vk::PipelineInputAssemblyStateCreateInfoBuilder<'b>,
 }
 
 pub struct VertexBinding<'a> {
@@ -67,36 +147,84 @@ pub struct VertexBinding<'a> {
     attributes: &'a mut Vec<vk::VertexInputAttributeDescription>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AttributeFormat {
     Float,
     Vec2,
     Vec3,
     Vec4,
     Double,
+    UInt,
     UVec2,
     UVec3,
     UVec4,
+    SInt,
     SVec2,
     SVec3,
     SVec4,
     DVec2,
     DVec3,
     DVec4,
+    /// Unsigned bytes read by the shader as floats from 0 to 1
+    UNormByte2,
+    UNormByte4,
+    /// Signed bytes read by the shader as floats from -1 to 1
+    SNormByte2,
+    SNormByte4,
+    /// Unsigned shorts read by the shader as floats from 0 to 1
+    UNormShort2,
+    UNormShort4,
+    /// Signed shorts read by the shader as floats from -1 to 1
+    SNormShort2,
+    SNormShort4,
+    /// A u32 packed as 2 bits of alpha and 10 bits for each of blue, green and red, read as a normalized vec4
+    A2B10G10R10UNormPack32,
+    /// A u32 packed as unsigned 10 bit blue and 11 bit green and red floats, read as a vec3
+    B10G11R11UFloatPack32,
+}
+
+/// An attribute of a Vertex, the format is read from the offset in each vertex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub format: AttributeFormat,
+    pub offset: u32,
 }
 
 pub struct MultiSampleSettings<'a, 'b: 'a> {
-    settings: &'a vk::PipelineMultisampleStateCreateInfoBuilder<'b>,
+    settings: &'a mut vk::PipelineMultisampleStateCreateInfoBuilder<'b>,
     masks: &'a mut Vec<vk::SampleMask>,
 }
 
 pub struct DepthStencilSettings<'a, 'b: 'a> {
-    settings: &'a vk::PipelineDepthStencilStateCreateInfoBuilder<'b>,
+    settings: &'a mut vk::PipelineDepthStencilStateCreateInfoBuilder<'b>,
 }
 
 pub struct ColorBlendingSettings<'a, 'b: 'a> {
-    pipeline_settings: &'a vk::PipelineColorBlendStateCreateInfoBuilder<'b>,
+    pipeline_settings: &'a mut vk::PipelineColorBlendStateCreateInfoBuilder<'b>,
     attachments: Vec<vk::PipelineColorBlendAttachmentStateBuilder<'b>>,
     constants: [f32; 4],
+    independent_blend: bool,
+}
+
+/// Common ways of compositing the colour a fragment shader outputs with the colour already in the attachment,
+/// every mode except StraightAlpha expects the shader to output colour premultiplied by its alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Replaces the destination, blending is disabled
+    Opaque,
+    /// Blends by the source alpha, expects straight colour that isn't multiplied by its alpha
+    StraightAlpha,
+    /// Blends by the source alpha, expects premultiplied colour
+    PremultipliedAlpha,
+    /// Adds the source to the destination, expects premultiplied colour so transparent areas add nothing
+    Additive,
+    /// Multiplies the destination by the source, darkening it. Expects premultiplied colour so transparent areas
+    /// leave the destination as it is
+    Multiply,
+    /// Inverts, multiplies and inverts again, lightening the destination. Expects premultiplied colour so
+    /// transparent areas leave the destination as it is
+    Screen,
 }
 
 pub enum ColorBlendingType<'a> {
@@ -105,7 +233,10 @@ pub enum ColorBlendingType<'a> {
         Vec<vk::PipelineColorBlendAttachmentStateBuilder<'a>>,
         [f32; 4],
     ),
-    BitwiseBlending(vk::PipelineColorBlendStateCreateInfoBuilder<'a>),
+    BitwiseBlending(
+        vk::PipelineColorBlendStateCreateInfoBuilder<'a>,
+        Vec<vk::PipelineColorBlendAttachmentStateBuilder<'a>>,
+    ),
 }
 
 pub struct DynamicStateSettings<'a, 'b: 'a> {
@@ -113,7 +244,208 @@ pub struct DynamicStateSettings<'a, 'b: 'a> {
     dynamic_states: &'a mut Vec<vk::DynamicState>,
 }
 
-pub struct LayoutSettings<'a, 'b: 'a> {
-    pipeline_settings: &'a mut vk::PipelineLayoutCreateInfoBuilder<'b>,
-    push_constants: &'a mut Vec<vk::PushConstantRangeBuilder<'b>>,
-}
\ No newline at end of file
+pub struct LayoutSettings<'a> {
+    set_layouts: &'a mut Vec<vk::DescriptorSetLayout>,
+    set_bindings: &'a mut Vec<Vec<vk::DescriptorSetLayoutBinding>>,
+    push_constants: &'a mut Vec<vk::PushConstantRange>,
+    max_push_constants_size: u32,
+}
+
+/// The descriptor set layouts and push constant ranges a pipeline was created with
+pub struct PipelineLayout {
+    handle: vk::PipelineLayout,
+    set_layouts: Vec<vk::DescriptorSetLayout>,
+    push_constant_ranges: Vec<vk::PushConstantRange>,
+}
+
+/// A graphics pipeline along with the layout it was created with
+pub struct GraphicsPipeline {
+    handle: vk::Pipeline,
+    layout: PipelineLayout,
+    dynamic_states: Vec<vk::DynamicState>,
+    viewport_count: u32,
+}
+
+/// A compute pipeline along with the layout it was created with
+pub struct ComputePipeline {
+    handle: vk::Pipeline,
+    layout: PipelineLayout,
+}
+
+// An f32 that is compared and hashed by its bits so that descriptions can be used as keys
+#[derive(Debug, Clone, Copy, Default)]
+struct FloatBits(f32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ShaderStageDescription {
+    stage: vk::ShaderStageFlagBits,
+    module: vk::ShaderModule,
+    entry_name: CString,
+    specialization: Vec<(u32, SpecializationValue)>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct VertexBindingDescription {
+    binding: u32,
+    stride: u32,
+    input_rate: vk::VertexInputRate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct VertexAttributeDescription {
+    location: u32,
+    binding: u32,
+    format: vk::Format,
+    offset: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ViewportDescription {
+    x: FloatBits,
+    y: FloatBits,
+    width: FloatBits,
+    height: FloatBits,
+    min_depth: FloatBits,
+    max_depth: FloatBits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ScissorDescription {
+    offset: (i32, i32),
+    extent: (u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct RasterizerDescription {
+    depth_clamp: bool,
+    rasterizer_discard: bool,
+    polygon_mode: vk::PolygonMode,
+    cull_mode: vk::CullModeFlags,
+    front_face: vk::FrontFace,
+    depth_bias: bool,
+    depth_bias_constant_factor: FloatBits,
+    depth_bias_clamp: FloatBits,
+    depth_bias_slope_factor: FloatBits,
+    line_width: FloatBits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MultisampleDescription {
+    samples: vk::SampleCountFlagBits,
+    sample_shading: bool,
+    min_sample_shading: FloatBits,
+    sample_masks: Vec<vk::SampleMask>,
+    alpha_to_coverage: bool,
+    alpha_to_one: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct StencilDescription {
+    fail_op: vk::StencilOp,
+    pass_op: vk::StencilOp,
+    depth_fail_op: vk::StencilOp,
+    compare_op: vk::CompareOp,
+    compare_mask: u32,
+    write_mask: u32,
+    reference: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct DepthStencilDescription {
+    depth_test: bool,
+    depth_write: bool,
+    depth_compare_op: vk::CompareOp,
+    depth_bounds_test: bool,
+    stencil_test: bool,
+    front: StencilDescription,
+    back: StencilDescription,
+    min_depth_bounds: FloatBits,
+    max_depth_bounds: FloatBits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BlendAttachmentDescription {
+    blend_enable: bool,
+    src_color_blend_factor: vk::BlendFactor,
+    dst_color_blend_factor: vk::BlendFactor,
+    color_blend_op: vk::BlendOp,
+    src_alpha_blend_factor: vk::BlendFactor,
+    dst_alpha_blend_factor: vk::BlendFactor,
+    alpha_blend_op: vk::BlendOp,
+    color_write_mask: vk::ColorComponentFlags,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ColorBlendDescription {
+    // Bitwise blending replaces attachment blending when set
+    logic_op: Option<vk::LogicOp>,
+    attachments: Vec<BlendAttachmentDescription>,
+    constants: [FloatBits; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PushConstantDescription {
+    stages: vk::ShaderStageFlags,
+    offset: u32,
+    size: u32,
+}
+
+/// An owned copy of everything needed to create a graphics pipeline, it can create the same pipeline for
+/// other renderpasses and identical descriptions compare and hash equal so that they can share a pipeline,
+/// the shader modules it refers to must outlive it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineDescription {
+    shaders: Vec<ShaderStageDescription>,
+    vertex_bindings: Vec<VertexBindingDescription>,
+    vertex_attributes: Vec<VertexAttributeDescription>,
+    topology: vk::PrimitiveTopology,
+    primitive_restart: bool,
+    viewports: Vec<ViewportDescription>,
+    scissors: Vec<ScissorDescription>,
+    rasterizer: RasterizerDescription,
+    multisample: MultisampleDescription,
+    depth_stencil: Option<DepthStencilDescription>,
+    color_blend: ColorBlendDescription,
+    dynamic_states: Vec<vk::DynamicState>,
+    set_layouts: Vec<vk::DescriptorSetLayout>,
+    push_constant_ranges: Vec<PushConstantDescription>,
+}
+
+/// Shares one pipeline between identical descriptions created for the same subpass of a renderpass
+pub struct PipelineStore {
+    // Keyed by the id of the renderpass as its handle can be reused by a renderpass created after it is destroyed
+    pipelines: HashMap<(PipelineDescription, u64, u32), (vk::RenderPass, GraphicsPipeline)>,
+    // The shader files used by pipelines in the store, keyed by the canonical path the watcher reports
+    #[cfg(debug_assertions)]
+    shader_files: HashMap<PathBuf, PathBuf>,
+    // Watches the directories of the shader files, none if the platform couldn't create a watcher
+    #[cfg(debug_assertions)]
+    watcher: Option<notify::RecommendedWatcher>,
+    #[cfg(debug_assertions)]
+    changed_files: std::sync::mpsc::Receiver<PathBuf>,
+    // Why the watcher couldn't be created or a shader file couldn't be watched, returned by the next reload
+    #[cfg(debug_assertions)]
+    watch_error: Option<crate::error::Error>,
+    // Modules recompiled from changed shader files, these replace the module in any description that loaded the file
+    reloaded_shaders: HashMap<(PathBuf, vk::ShaderStageFlagBits, CString), vk::ShaderModule>,
+}
+
+/// Identifies the device and driver that a pipeline cache was created by, the data of one driver can't be used by another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PipelineCacheKey {
+    vendor_id: u32,
+    device_id: u32,
+    driver_version: u32,
+    uuid: [u8; vk::UUID_SIZE as usize],
+}
+
+/// Speeds up pipeline creation by reusing compiled pipelines, when given a directory it is seeded from a file
+/// written by the same device and driver and written back when the device is destroyed
+pub struct PipelineCache {
+    handle: vk::PipelineCache,
+    key: PipelineCacheKey,
+    path: Option<PathBuf>,
+    // Why the cache isn't persisted or is disabled when that wasn't what was configured
+    fallback_reason: Option<crate::error::Error>,
+}
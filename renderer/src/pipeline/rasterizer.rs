@@ -10,7 +10,12 @@ impl<'a> ConfigureRasterizer for ConfigurePipeline<'a> {
         &mut self,
         configure_rasterizer: &mut dyn FnMut(&mut RasterizerSettings),
     ) -> &mut dyn ConfigureMultisampling {
-        let mut builder = vk::PipelineRasterizationStateCreateInfoBuilder::new();
+        // Filled triangles without culling, a line width other than 1.0 needs the wideLines feature
+        let mut builder = vk::PipelineRasterizationStateCreateInfoBuilder::new()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .line_width(1.0);
         // Many of these options require the use of device features
         let mut config = RasterizerSettings::new(&mut builder);
         configure_rasterizer(&mut config);
@@ -23,46 +28,48 @@ impl<'a, 'b: 'a> RasterizerSettings<'a, 'b> {
     // Many of these functions require a GPU feature to have been enabled
     /// Determines how fragments are generated for geometry.
     pub fn polygon_mode(&mut self, polygon_mode: vk::PolygonMode) {
-        self.settings.polygon_mode(polygon_mode);
+        *self.settings = self.settings.polygon_mode(polygon_mode);
     }
     /// If depthClampEnable is set to true, then fragments that are beyond the near and far planes
     /// are clamped to them as opposed to discarding them
     pub fn depth_clamp(&mut self, depth_clamp: bool) {
-        self.settings.depth_clamp_enable(depth_clamp);
+        *self.settings = self.settings.depth_clamp_enable(depth_clamp);
     }
 
     /// Discards the primivites immediately before rasterization, effectively not drawing them
     pub fn rasterizer_discard(&mut self, rasterizer_discard: bool) {
-        self.settings.rasterizer_discard_enable(rasterizer_discard);
+        *self.settings = self.settings.rasterizer_discard_enable(rasterizer_discard);
     }
     /// Describes the thickness of lines in terms of number of fragments, a value above 1.0 requires a GPU feature
     pub fn line_width(&mut self, line_width: f32) {
-        self.settings.line_width(line_width);
+        *self.settings = self.settings.line_width(line_width);
     }
     /// Type of face culling to use
     pub fn cull_mode(&mut self, cull_mode: vk::CullModeFlags) {
-        self.settings.cull_mode(cull_mode);
+        *self.settings = self.settings.cull_mode(cull_mode);
     }
     /// Specifies the vertex order for faces to be considered front-facing and can be clockwise or counterclockwise
     pub fn front_face(&mut self, front_face: vk::FrontFace) {
-        self.settings.front_face(front_face);
+        *self.settings = self.settings.front_face(front_face);
     }
     /// Alter the depth values by adding a constant value or biasing them based on a fragment's slope
     pub fn depth_bias(&mut self, depth_bias: bool) {
-        self.settings.depth_bias_enable(depth_bias);
+        *self.settings = self.settings.depth_bias_enable(depth_bias);
     }
 
     pub fn depth_bias_constant_factor(&mut self, depth_bias_constant_factor: f32) {
-        self.settings
+        *self.settings = self
+            .settings
             .depth_bias_constant_factor(depth_bias_constant_factor);
     }
 
     pub fn depth_bias_clamp(&mut self, depth_bias_clamp: f32) {
-        self.settings.depth_bias_clamp(depth_bias_clamp);
+        *self.settings = self.settings.depth_bias_clamp(depth_bias_clamp);
     }
 
     pub fn depth_bias_slope_factor(&mut self, depth_bias_slope_factor: f32) {
-        self.settings
+        *self.settings = self
+            .settings
             .depth_bias_slope_factor(depth_bias_slope_factor);
     }
 
@@ -0,0 +1,943 @@
+use super::{ShaderDescriptorBinding, ShaderInput, ShaderReflection, SpecializationValue};
+use crate::error::{Error, ErrorKind};
+use erupt::vk1_0 as vk;
+use std::collections::{HashMap, HashSet};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// Opcodes
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_FUNCTION_CALL: u32 = 57;
+const OP_VARIABLE: u32 = 59;
+const OP_IMAGE_TEXEL_POINTER: u32 = 60;
+const OP_LOAD: u32 = 61;
+const OP_STORE: u32 = 62;
+const OP_COPY_MEMORY: u32 = 63;
+const OP_COPY_MEMORY_SIZED: u32 = 64;
+const OP_ACCESS_CHAIN: u32 = 65;
+const OP_IN_BOUNDS_ACCESS_CHAIN: u32 = 66;
+const OP_PTR_ACCESS_CHAIN: u32 = 67;
+const OP_ARRAY_LENGTH: u32 = 68;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_ATOMIC_LOAD: u32 = 227;
+const OP_ATOMIC_STORE: u32 = 228;
+const OP_ATOMIC_XOR: u32 = 242;
+
+// Decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// Image dimensions and sampled values
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+const IMAGE_STORAGE: u32 = 2;
+
+fn reflection_failed(message: String) -> Error {
+    Error::new(ErrorKind::ShaderReflectionFailed(message), None)
+}
+
+#[derive(Debug, Clone)]
+enum Type {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Decorations {
+    spec_id: Option<u32>,
+    location: Option<u32>,
+    binding: Option<u32>,
+    set: Option<u32>,
+    array_stride: Option<u32>,
+    built_in: bool,
+    block: bool,
+    buffer_block: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MemberDecorations {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+}
+
+// The pointers a function accesses and the functions it calls
+#[derive(Debug, Default)]
+struct FunctionUses {
+    pointers: Vec<u32>,
+    calls: Vec<u32>,
+}
+
+// Everything in a module that reflection needs, collected in one pass over the instructions
+#[derive(Default)]
+struct Module {
+    // The execution model, function, name and interface of each entry point
+    entry_points: Vec<(u32, u32, String, Vec<u32>)>,
+    functions: HashMap<u32, FunctionUses>,
+    // The function whose body is being read
+    current_function: Option<u32>,
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    // The id, type and default value of each specialization constant
+    spec_constants: Vec<(u32, u32, Vec<u32>)>,
+    variables: Vec<(u32, u32, u32)>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), MemberDecorations>,
+}
+
+// Literal strings are nul terminated and packed four bytes to a word, returns the string and the words it used
+fn read_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), index + 1);
+            }
+            bytes.push(*byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+fn execution_model(stage: vk::ShaderStageFlagBits) -> Option<u32> {
+    match stage {
+        vk::ShaderStageFlagBits::VERTEX => Some(0),
+        vk::ShaderStageFlagBits::TESSELLATION_CONTROL => Some(1),
+        vk::ShaderStageFlagBits::TESSELLATION_EVALUATION => Some(2),
+        vk::ShaderStageFlagBits::GEOMETRY => Some(3),
+        vk::ShaderStageFlagBits::FRAGMENT => Some(4),
+        vk::ShaderStageFlagBits::COMPUTE => Some(5),
+        _ => None,
+    }
+}
+
+fn stage_for_model(model: u32) -> Option<vk::ShaderStageFlagBits> {
+    match model {
+        0 => Some(vk::ShaderStageFlagBits::VERTEX),
+        1 => Some(vk::ShaderStageFlagBits::TESSELLATION_CONTROL),
+        2 => Some(vk::ShaderStageFlagBits::TESSELLATION_EVALUATION),
+        3 => Some(vk::ShaderStageFlagBits::GEOMETRY),
+        4 => Some(vk::ShaderStageFlagBits::FRAGMENT),
+        5 => Some(vk::ShaderStageFlagBits::COMPUTE),
+        _ => None,
+    }
+}
+
+// The vertex attribute format that matches a scalar or vector of the given component type
+fn component_format(component: &Type, count: u32) -> Option<vk::Format> {
+    use vk::Format as F;
+    let formats = match component {
+        Type::Float { width: 32 } => [
+            F::R32_SFLOAT,
+            F::R32G32_SFLOAT,
+            F::R32G32B32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+        ],
+        Type::Float { width: 64 } => [
+            F::R64_SFLOAT,
+            F::R64G64_SFLOAT,
+            F::R64G64B64_SFLOAT,
+            F::R64G64B64A64_SFLOAT,
+        ],
+        Type::Float { width: 16 } => [
+            F::R16_SFLOAT,
+            F::R16G16_SFLOAT,
+            F::R16G16B16_SFLOAT,
+            F::R16G16B16A16_SFLOAT,
+        ],
+        Type::Int {
+            width: 32,
+            signed: true,
+        } => [
+            F::R32_SINT,
+            F::R32G32_SINT,
+            F::R32G32B32_SINT,
+            F::R32G32B32A32_SINT,
+        ],
+        Type::Int {
+            width: 32,
+            signed: false,
+        } => [
+            F::R32_UINT,
+            F::R32G32_UINT,
+            F::R32G32B32_UINT,
+            F::R32G32B32A32_UINT,
+        ],
+        Type::Int {
+            width: 16,
+            signed: true,
+        } => [
+            F::R16_SINT,
+            F::R16G16_SINT,
+            F::R16G16B16_SINT,
+            F::R16G16B16A16_SINT,
+        ],
+        Type::Int {
+            width: 16,
+            signed: false,
+        } => [
+            F::R16_UINT,
+            F::R16G16_UINT,
+            F::R16G16B16_UINT,
+            F::R16G16B16A16_UINT,
+        ],
+        _ => return None,
+    };
+    formats.get(count.checked_sub(1)? as usize).copied()
+}
+
+// 64 bit vectors of more than two components use two locations
+fn format_locations(format: vk::Format) -> u32 {
+    match format {
+        vk::Format::R64G64B64_SFLOAT | vk::Format::R64G64B64A64_SFLOAT => 2,
+        _ => 1,
+    }
+}
+
+impl Module {
+    fn parse(code: &[u32]) -> Result<Module, Error> {
+        if code.len() < 5 || code[0] != SPIRV_MAGIC {
+            return Err(reflection_failed(String::from(
+                "The code is not SPIR-V, it does not start with the magic number",
+            )));
+        }
+        let mut module = Module::default();
+        let mut offset = 5;
+        while offset < code.len() {
+            let opcode = code[offset] & 0xFFFF;
+            let count = (code[offset] >> 16) as usize;
+            if count == 0 || offset + count > code.len() {
+                return Err(reflection_failed(format!(
+                    "The instruction at word {} has an invalid length",
+                    offset
+                )));
+            }
+            module.read_instruction(opcode, &code[offset + 1..offset + count]);
+            offset += count;
+        }
+        Ok(module)
+    }
+
+    fn read_instruction(&mut self, opcode: u32, operands: &[u32]) {
+        let operand = |index: usize| operands.get(index).copied().unwrap_or(0);
+        if let Some(function) = self.current_function {
+            self.read_function_instruction(function, opcode, operands);
+        }
+        match opcode {
+            OP_ENTRY_POINT if operands.len() >= 3 => {
+                let (name, used) = read_string(&operands[2..]);
+                let interface = operands[(2 + used).min(operands.len())..].to_vec();
+                self.entry_points
+                    .push((operand(0), operand(1), name, interface));
+            }
+            OP_FUNCTION => {
+                self.current_function = Some(operand(1));
+                self.functions.entry(operand(1)).or_default();
+            }
+            OP_FUNCTION_END => self.current_function = None,
+            OP_TYPE_BOOL => {
+                self.types.insert(operand(0), Type::Bool);
+            }
+            OP_TYPE_INT => {
+                let signed = operand(2) == 1;
+                self.types.insert(
+                    operand(0),
+                    Type::Int {
+                        width: operand(1),
+                        signed,
+                    },
+                );
+            }
+            OP_TYPE_FLOAT => {
+                self.types
+                    .insert(operand(0), Type::Float { width: operand(1) });
+            }
+            OP_TYPE_VECTOR => {
+                let vector = Type::Vector {
+                    component: operand(1),
+                    count: operand(2),
+                };
+                self.types.insert(operand(0), vector);
+            }
+            OP_TYPE_MATRIX => {
+                let matrix = Type::Matrix {
+                    column: operand(1),
+                    count: operand(2),
+                };
+                self.types.insert(operand(0), matrix);
+            }
+            OP_TYPE_IMAGE => {
+                let image = Type::Image {
+                    dim: operand(2),
+                    sampled: operand(6),
+                };
+                self.types.insert(operand(0), image);
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operand(0), Type::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operand(0), Type::SampledImage);
+            }
+            OP_TYPE_ARRAY => {
+                // The length is an id, it is resolved once every constant has been read
+                let array = Type::Array {
+                    element: operand(1),
+                    length: operand(2),
+                };
+                self.types.insert(operand(0), array);
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.types.insert(
+                    operand(0),
+                    Type::RuntimeArray {
+                        element: operand(1),
+                    },
+                );
+            }
+            OP_TYPE_STRUCT if !operands.is_empty() => {
+                let members = operands[1..].to_vec();
+                self.types.insert(operand(0), Type::Struct { members });
+            }
+            OP_TYPE_POINTER => {
+                self.types.insert(
+                    operand(0),
+                    Type::Pointer {
+                        pointee: operand(2),
+                    },
+                );
+            }
+            OP_CONSTANT => {
+                self.constants.insert(operand(1), operand(2));
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE => {
+                let value = (opcode == OP_SPEC_CONSTANT_TRUE) as u32;
+                self.spec_constants
+                    .push((operand(1), operand(0), vec![value]));
+            }
+            OP_SPEC_CONSTANT if operands.len() >= 3 => {
+                self.spec_constants
+                    .push((operand(1), operand(0), operands[2..].to_vec()));
+            }
+            OP_VARIABLE => {
+                self.variables.push((operand(1), operand(0), operand(2)));
+            }
+            OP_DECORATE => {
+                let decorations = self.decorations.entry(operand(0)).or_default();
+                match operand(1) {
+                    DECORATION_SPEC_ID => decorations.spec_id = Some(operand(2)),
+                    DECORATION_BLOCK => decorations.block = true,
+                    DECORATION_BUFFER_BLOCK => decorations.buffer_block = true,
+                    DECORATION_ARRAY_STRIDE => decorations.array_stride = Some(operand(2)),
+                    DECORATION_BUILT_IN => decorations.built_in = true,
+                    DECORATION_LOCATION => decorations.location = Some(operand(2)),
+                    DECORATION_BINDING => decorations.binding = Some(operand(2)),
+                    DECORATION_DESCRIPTOR_SET => decorations.set = Some(operand(2)),
+                    _ => {}
+                }
+            }
+            OP_MEMBER_DECORATE => {
+                let decorations = self
+                    .member_decorations
+                    .entry((operand(0), operand(1)))
+                    .or_default();
+                match operand(2) {
+                    DECORATION_OFFSET => decorations.offset = Some(operand(3)),
+                    DECORATION_MATRIX_STRIDE => decorations.matrix_stride = Some(operand(3)),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Records the pointers an instruction in a function body reads or writes through
+    fn read_function_instruction(&mut self, function: u32, opcode: u32, operands: &[u32]) {
+        let uses = self.functions.entry(function).or_default();
+        let operand = |index: usize| operands.get(index).copied().unwrap_or(0);
+        match opcode {
+            OP_FUNCTION_CALL => {
+                uses.calls.push(operand(2));
+                // Pointers to resources can be passed as arguments
+                uses.pointers
+                    .extend_from_slice(operands.get(3..).unwrap_or(&[]));
+            }
+            OP_STORE | OP_ATOMIC_STORE => uses.pointers.push(operand(0)),
+            OP_COPY_MEMORY | OP_COPY_MEMORY_SIZED => {
+                uses.pointers.push(operand(0));
+                uses.pointers.push(operand(1));
+            }
+            OP_IMAGE_TEXEL_POINTER
+            | OP_LOAD
+            | OP_ACCESS_CHAIN
+            | OP_IN_BOUNDS_ACCESS_CHAIN
+            | OP_PTR_ACCESS_CHAIN
+            | OP_ARRAY_LENGTH
+            | OP_ATOMIC_LOAD..=OP_ATOMIC_XOR => uses.pointers.push(operand(2)),
+            _ => {}
+        }
+    }
+
+    // Every id accessed by the function and the functions it calls along with the entry point interface,
+    // before SPIR-V 1.4 the interface only lists inputs and outputs
+    fn used_by_entry_point(&self, function: u32, interface: &[u32]) -> HashSet<u32> {
+        let mut used: HashSet<u32> = interface.iter().copied().collect();
+        let mut visited = HashSet::new();
+        let mut pending = vec![function];
+        while let Some(function) = pending.pop() {
+            if !visited.insert(function) {
+                continue;
+            }
+            if let Some(uses) = self.functions.get(&function) {
+                used.extend(uses.pointers.iter().copied());
+                pending.extend(uses.calls.iter().copied());
+            }
+        }
+        used
+    }
+
+    // The default value of a specialization constant, none for types that can't be specialized from Rust
+    fn spec_constant_value(&self, ty: u32, words: &[u32]) -> Option<SpecializationValue> {
+        let word = words.first().copied().unwrap_or(0);
+        match self.types.get(&ty)? {
+            Type::Bool => Some(SpecializationValue::Bool(word != 0)),
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => Some(SpecializationValue::Int(word as i32)),
+            Type::Int {
+                width: 32,
+                signed: false,
+            } => Some(SpecializationValue::UInt(word)),
+            Type::Float { width: 32 } => Some(SpecializationValue::Float(f32::from_bits(word))),
+            // Wider literals are stored low word first
+            Type::Float { width: 64 } => {
+                let high = words.get(1).copied().unwrap_or(0) as u64;
+                Some(SpecializationValue::Double(f64::from_bits(
+                    high << 32 | word as u64,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    fn pointee(&self, pointer: u32) -> Option<u32> {
+        match self.types.get(&pointer) {
+            Some(Type::Pointer { pointee }) => Some(*pointee),
+            _ => None,
+        }
+    }
+
+    fn array_length(&self, length: u32) -> u32 {
+        self.constants.get(&length).copied().unwrap_or(1)
+    }
+
+    // Each input location holds at most a four component vector, matrices and arrays use several locations
+    fn input_formats(&self, id: u32) -> Result<Vec<vk::Format>, Error> {
+        match self.types.get(&id) {
+            Some(Type::Array { element, length }) => {
+                let element_formats = self.input_formats(*element)?;
+                let length = self.array_length(*length) as usize;
+                Ok(element_formats.repeat(length))
+            }
+            Some(Type::Matrix { column, count }) => {
+                let column_formats = self.input_formats(*column)?;
+                Ok(column_formats.repeat(*count as usize))
+            }
+            Some(Type::Vector { component, count }) => {
+                let component = self.types.get(component).cloned();
+                let format = component
+                    .as_ref()
+                    .and_then(|component| component_format(component, *count));
+                let format = format.ok_or_else(|| {
+                    reflection_failed(format!(
+                        "Unsupported input type, a vector of {} {:?}",
+                        count, component
+                    ))
+                })?;
+                Ok(vec![format])
+            }
+            Some(scalar) => component_format(scalar, 1)
+                .map(|format| vec![format])
+                .ok_or_else(|| reflection_failed(format!("Unsupported input type {:?}", scalar))),
+            None => Err(reflection_failed(format!("Input type {} is undefined", id))),
+        }
+    }
+
+    // The inputs of a variable starting at its location, each format is placed after the locations of the last
+    fn inputs(&self, location: u32, id: u32) -> Result<Vec<ShaderInput>, Error> {
+        let mut location = location;
+        let inputs = self
+            .input_formats(id)?
+            .into_iter()
+            .map(|format| {
+                let input = ShaderInput { location, format };
+                location += format_locations(format);
+                input
+            })
+            .collect();
+        Ok(inputs)
+    }
+
+    // The descriptor type of a variable and how many descriptors it holds
+    fn descriptor(&self, storage: u32, id: u32) -> Option<(vk::DescriptorType, u32)> {
+        match self.types.get(&id)? {
+            Type::Array { element, length } => {
+                let (descriptor_type, _) = self.descriptor(storage, *element)?;
+                Some((descriptor_type, self.array_length(*length)))
+            }
+            Type::RuntimeArray { element } => {
+                let (descriptor_type, _) = self.descriptor(storage, *element)?;
+                Some((descriptor_type, 0))
+            }
+            Type::Sampler => Some((vk::DescriptorType::SAMPLER, 1)),
+            Type::SampledImage => Some((vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 1)),
+            Type::Image { dim, sampled } => {
+                let descriptor_type = match (*dim, *sampled) {
+                    (DIM_SUBPASS_DATA, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                    (DIM_BUFFER, IMAGE_STORAGE) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                    (DIM_BUFFER, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    (_, IMAGE_STORAGE) => vk::DescriptorType::STORAGE_IMAGE,
+                    _ => vk::DescriptorType::SAMPLED_IMAGE,
+                };
+                Some((descriptor_type, 1))
+            }
+            Type::Struct { .. } => {
+                let decorations = self.decorations.get(&id).copied().unwrap_or_default();
+                match storage {
+                    STORAGE_STORAGE_BUFFER => Some((vk::DescriptorType::STORAGE_BUFFER, 1)),
+                    STORAGE_UNIFORM if decorations.buffer_block => {
+                        Some((vk::DescriptorType::STORAGE_BUFFER, 1))
+                    }
+                    STORAGE_UNIFORM if decorations.block => {
+                        Some((vk::DescriptorType::UNIFORM_BUFFER, 1))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // The number of bytes a member of a block uses, following the explicit layout decorations
+    fn size_of(&self, id: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&id) {
+            Some(Type::Bool) => 4,
+            Some(Type::Int { width, .. }) | Some(Type::Float { width }) => width / 8,
+            Some(Type::Vector { component, count }) => self.size_of(*component, None) * count,
+            Some(Type::Matrix { column, count }) => match matrix_stride {
+                Some(stride) => stride * count,
+                None => self.size_of(*column, None) * count,
+            },
+            Some(Type::Array { element, length }) => {
+                let stride = self
+                    .decorations
+                    .get(&id)
+                    .and_then(|decorations| decorations.array_stride)
+                    .unwrap_or_else(|| self.size_of(*element, matrix_stride));
+                stride * self.array_length(*length)
+            }
+            Some(Type::Struct { members }) => self.block_range(id, members).1,
+            _ => 0,
+        }
+    }
+
+    // The first and one past the last byte the members of a block use
+    fn block_range(&self, id: u32, members: &[u32]) -> (u32, u32) {
+        let mut start = u32::MAX;
+        let mut end = 0;
+        for (index, member) in members.iter().enumerate() {
+            let decorations = self
+                .member_decorations
+                .get(&(id, index as u32))
+                .copied()
+                .unwrap_or_default();
+            let offset = decorations.offset.unwrap_or(0);
+            start = start.min(offset);
+            end = end.max(offset + self.size_of(*member, decorations.matrix_stride));
+        }
+        (start.min(end), end)
+    }
+}
+
+impl ShaderReflection {
+    /// Reflects the entry point of the SPIR-V that runs in the given stage
+    pub fn new(
+        code: &[u32],
+        stage: vk::ShaderStageFlagBits,
+        entry_name: &str,
+    ) -> Result<ShaderReflection, Error> {
+        let module = Module::parse(code)?;
+        let model = execution_model(stage)
+            .ok_or_else(|| reflection_failed(format!("{:?} shaders are not supported", stage)))?;
+        let (function, interface) = module
+            .entry_points
+            .iter()
+            .find(|(entry_model, _, name, _)| *entry_model == model && name == entry_name)
+            .map(|(_, function, _, interface)| (*function, interface))
+            .ok_or_else(|| {
+                let available: Vec<&str> = module
+                    .entry_points
+                    .iter()
+                    .map(|(_, _, name, _)| name.as_str())
+                    .collect();
+                reflection_failed(format!(
+                    "There is no {:?} entry point named {}, the entry points are {:?}",
+                    stage, entry_name, available
+                ))
+            })?;
+
+        // Other entry points in the module can use resources this one never touches
+        let used = module.used_by_entry_point(function, interface);
+        let mut inputs = Vec::new();
+        let mut descriptor_bindings = Vec::new();
+        let mut push_constants = None;
+        for (id, pointer, storage) in module.variables.iter() {
+            let decorations = module.decorations.get(id).copied().unwrap_or_default();
+            let pointee = match module.pointee(*pointer) {
+                Some(pointee) => pointee,
+                None => continue,
+            };
+            match *storage {
+                // Only inputs of this entry point that are not built in variables come from the previous stage
+                STORAGE_INPUT if interface.contains(id) && !decorations.built_in => {
+                    if let Some(location) = decorations.location {
+                        inputs.extend(module.inputs(location, pointee)?);
+                    }
+                }
+                _ if !used.contains(id) => {}
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    if let Some((descriptor_type, count)) = module.descriptor(*storage, pointee) {
+                        descriptor_bindings.push(ShaderDescriptorBinding {
+                            set: decorations.set.unwrap_or(0),
+                            binding: decorations.binding.unwrap_or(0),
+                            descriptor_type,
+                            count,
+                        });
+                    }
+                }
+                STORAGE_PUSH_CONSTANT => {
+                    if let Some(Type::Struct { members }) = module.types.get(&pointee) {
+                        let (start, end) = module.block_range(pointee, members);
+                        push_constants = Some((start, end - start));
+                    }
+                }
+                _ => {}
+            }
+        }
+        inputs.sort_by_key(|input| input.location);
+        descriptor_bindings.sort_by_key(|binding| (binding.set, binding.binding));
+        // Constants without a SpecId are only specialized by OpSpecConstantOp and can't be set
+        let mut specialization_constants: Vec<(u32, SpecializationValue)> = module
+            .spec_constants
+            .iter()
+            .filter_map(|(id, ty, words)| {
+                let constant_id = module.decorations.get(id)?.spec_id?;
+                Some((constant_id, module.spec_constant_value(*ty, words)?))
+            })
+            .collect();
+        specialization_constants.sort_by_key(|(constant_id, _)| *constant_id);
+        let entry_points = module
+            .entry_points
+            .iter()
+            .filter_map(|(model, _, name, _)| {
+                stage_for_model(*model).map(|stage| (stage, name.clone()))
+            })
+            .collect();
+        Ok(ShaderReflection {
+            entry_points,
+            inputs,
+            descriptor_bindings,
+            push_constants,
+            specialization_constants,
+        })
+    }
+
+    /// Every entry point in the module along with the stage it runs in
+    pub fn entry_points(&self) -> &[(vk::ShaderStageFlagBits, String)] {
+        self.entry_points.as_slice()
+    }
+
+    /// The locations the entry point reads, ordered by location, 64 bit vectors of more than two components
+    /// use two locations
+    pub fn inputs(&self) -> &[ShaderInput] {
+        self.inputs.as_slice()
+    }
+
+    /// The descriptors the entry point reads, ordered by set then binding
+    pub fn descriptor_bindings(&self) -> &[ShaderDescriptorBinding] {
+        self.descriptor_bindings.as_slice()
+    }
+
+    /// The offset and size of the push constant block the entry point reads
+    pub fn push_constants(&self) -> Option<(u32, u32)> {
+        self.push_constants
+    }
+
+    /// The specialization constants the module declares with their default values, ordered by constant id
+    pub fn specialization_constants(&self) -> &[(u32, SpecializationValue)] {
+        self.specialization_constants.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OP_LABEL: u32 = 248;
+    const OP_RETURN: u32 = 253;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    // A fragment shader reading a vec2 and a uint, a texture array, a storage buffer and a push constant block,
+    // a second entry point reads the storage buffer and calls a function reading the push constants
+    fn fragment_module() -> Vec<u32> {
+        let main = u32::from_le_bytes(*b"main");
+        let blur = u32::from_le_bytes(*b"blur");
+        let mut code = vec![SPIRV_MAGIC, 0x0001_0000, 0, 100, 0];
+        let parts = [
+            instruction(OP_ENTRY_POINT, &[4, 80, main, 0, 20, 21, 22]),
+            instruction(OP_ENTRY_POINT, &[4, 81, blur, 0]),
+            instruction(OP_DECORATE, &[20, DECORATION_LOCATION, 0]),
+            instruction(OP_DECORATE, &[21, DECORATION_LOCATION, 1]),
+            instruction(OP_DECORATE, &[22, DECORATION_BUILT_IN, 15]),
+            instruction(OP_DECORATE, &[30, DECORATION_DESCRIPTOR_SET, 0]),
+            instruction(OP_DECORATE, &[30, DECORATION_BINDING, 2]),
+            instruction(OP_DECORATE, &[31, DECORATION_DESCRIPTOR_SET, 1]),
+            instruction(OP_DECORATE, &[31, DECORATION_BINDING, 0]),
+            instruction(OP_DECORATE, &[50, DECORATION_BLOCK]),
+            instruction(OP_MEMBER_DECORATE, &[50, 0, DECORATION_OFFSET, 0]),
+            instruction(OP_DECORATE, &[60, DECORATION_BLOCK]),
+            instruction(OP_MEMBER_DECORATE, &[60, 0, DECORATION_OFFSET, 16]),
+            instruction(OP_MEMBER_DECORATE, &[60, 1, DECORATION_OFFSET, 32]),
+            instruction(OP_DECORATE, &[70, DECORATION_SPEC_ID, 3]),
+            instruction(OP_DECORATE, &[71, DECORATION_SPEC_ID, 0]),
+            // float, vec2, uint, vec4
+            instruction(OP_TYPE_FLOAT, &[2, 32]),
+            instruction(OP_TYPE_VECTOR, &[3, 2, 2]),
+            instruction(OP_TYPE_INT, &[4, 32, 0]),
+            instruction(OP_TYPE_VECTOR, &[5, 2, 4]),
+            instruction(OP_CONSTANT, &[4, 6, 8]),
+            // layout(constant_id = 3) const float radius = 2.5, layout(constant_id = 0) const bool grayscale = true
+            instruction(OP_TYPE_BOOL, &[11]),
+            instruction(OP_SPEC_CONSTANT, &[2, 70, 2.5f32.to_bits()]),
+            instruction(OP_SPEC_CONSTANT_TRUE, &[11, 71]),
+            // sampler2D textures[8]
+            instruction(OP_TYPE_IMAGE, &[7, 2, 1, 0, 0, 0, 1, 0]),
+            instruction(OP_TYPE_SAMPLED_IMAGE, &[8, 7]),
+            instruction(OP_TYPE_ARRAY, &[9, 8, 6]),
+            // buffer { vec4 values[]; }
+            instruction(OP_TYPE_RUNTIME_ARRAY, &[10, 5]),
+            instruction(OP_TYPE_STRUCT, &[50, 10]),
+            // push_constant { vec4 tint; vec2 offset; }
+            instruction(OP_TYPE_STRUCT, &[60, 5, 3]),
+            instruction(OP_TYPE_POINTER, &[40, STORAGE_INPUT, 3]),
+            instruction(OP_TYPE_POINTER, &[41, STORAGE_INPUT, 4]),
+            instruction(OP_TYPE_POINTER, &[42, STORAGE_INPUT, 5]),
+            instruction(OP_TYPE_POINTER, &[43, STORAGE_UNIFORM_CONSTANT, 9]),
+            instruction(OP_TYPE_POINTER, &[44, STORAGE_STORAGE_BUFFER, 50]),
+            instruction(OP_TYPE_POINTER, &[45, STORAGE_PUSH_CONSTANT, 60]),
+            instruction(OP_VARIABLE, &[40, 20, STORAGE_INPUT]),
+            instruction(OP_VARIABLE, &[41, 21, STORAGE_INPUT]),
+            instruction(OP_VARIABLE, &[42, 22, STORAGE_INPUT]),
+            instruction(OP_VARIABLE, &[43, 30, STORAGE_UNIFORM_CONSTANT]),
+            instruction(OP_VARIABLE, &[44, 31, STORAGE_STORAGE_BUFFER]),
+            instruction(OP_VARIABLE, &[45, 32, STORAGE_PUSH_CONSTANT]),
+            // main reads every resource
+            instruction(OP_FUNCTION, &[1, 80, 0, 12]),
+            instruction(OP_LABEL, &[90]),
+            instruction(OP_ACCESS_CHAIN, &[46, 91, 30, 6]),
+            instruction(OP_LOAD, &[8, 92, 91]),
+            instruction(OP_ARRAY_LENGTH, &[4, 93, 31, 0]),
+            instruction(OP_ACCESS_CHAIN, &[47, 94, 32, 6]),
+            instruction(OP_RETURN, &[]),
+            instruction(OP_FUNCTION_END, &[]),
+            // blur stores to the storage buffer then calls tint
+            instruction(OP_FUNCTION, &[1, 81, 0, 12]),
+            instruction(OP_LABEL, &[95]),
+            instruction(OP_STORE, &[31, 96]),
+            instruction(OP_FUNCTION_CALL, &[1, 97, 82]),
+            instruction(OP_RETURN, &[]),
+            instruction(OP_FUNCTION_END, &[]),
+            instruction(OP_FUNCTION, &[1, 82, 0, 12]),
+            instruction(OP_LABEL, &[98]),
+            instruction(OP_LOAD, &[60, 99, 32]),
+            instruction(OP_RETURN, &[]),
+            instruction(OP_FUNCTION_END, &[]),
+        ];
+        for part in parts.iter() {
+            code.extend_from_slice(part);
+        }
+        code
+    }
+
+    #[test]
+    fn test_reflect_fragment_shader() {
+        let reflection = ShaderReflection::new(
+            &fragment_module(),
+            vk::ShaderStageFlagBits::FRAGMENT,
+            "main",
+        )
+        .unwrap();
+        assert_eq!(
+            reflection.inputs(),
+            &[
+                ShaderInput {
+                    location: 0,
+                    format: vk::Format::R32G32_SFLOAT
+                },
+                ShaderInput {
+                    location: 1,
+                    format: vk::Format::R32_UINT
+                },
+            ]
+        );
+        assert_eq!(
+            reflection.descriptor_bindings(),
+            &[
+                ShaderDescriptorBinding {
+                    set: 0,
+                    binding: 2,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    count: 8,
+                },
+                ShaderDescriptorBinding {
+                    set: 1,
+                    binding: 0,
+                    descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                    count: 1,
+                },
+            ]
+        );
+        assert_eq!(reflection.push_constants(), Some((16, 24)));
+        assert_eq!(
+            reflection.specialization_constants(),
+            &[
+                (0, SpecializationValue::Bool(true)),
+                (3, SpecializationValue::Float(2.5)),
+            ]
+        );
+        // The entry point must exist for the stage
+        assert!(
+            ShaderReflection::new(&fragment_module(), vk::ShaderStageFlagBits::VERTEX, "main")
+                .is_err()
+        );
+        assert!(
+            ShaderReflection::new(&[0, 1, 2], vk::ShaderStageFlagBits::FRAGMENT, "main").is_err()
+        );
+    }
+
+    #[test]
+    fn test_reflect_entry_point_resources() {
+        let reflection = ShaderReflection::new(
+            &fragment_module(),
+            vk::ShaderStageFlagBits::FRAGMENT,
+            "blur",
+        )
+        .unwrap();
+        assert!(reflection.inputs().is_empty());
+        // The texture array is only read by main
+        assert_eq!(
+            reflection.descriptor_bindings(),
+            &[ShaderDescriptorBinding {
+                set: 1,
+                binding: 0,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                count: 1,
+            }]
+        );
+        // Push constants read by a called function belong to the entry point
+        assert_eq!(reflection.push_constants(), Some((16, 24)));
+    }
+
+    #[test]
+    fn test_input_locations() {
+        let mut module = Module::default();
+        module.read_instruction(OP_TYPE_FLOAT, &[1, 64]);
+        module.read_instruction(OP_TYPE_VECTOR, &[2, 1, 4]);
+        module.read_instruction(OP_TYPE_MATRIX, &[3, 2, 2]);
+        module.read_instruction(OP_TYPE_VECTOR, &[4, 1, 2]);
+        module.read_instruction(OP_TYPE_VECTOR, &[5, 1, 0]);
+        // Each column of a dmat2x4 uses two locations, like AttributeFormat::DVec4
+        let dmat = module.inputs(3, 3).unwrap();
+        assert_eq!(
+            dmat.iter().map(|input| input.location).collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+        assert_eq!(dmat[0].format, vk::Format::R64G64B64A64_SFLOAT);
+        assert_eq!(
+            module.inputs(0, 4).unwrap()[0].format,
+            vk::Format::R64G64_SFLOAT
+        );
+        // A malformed vector without components is an error rather than a panic
+        assert!(module.inputs(0, 5).is_err());
+    }
+
+    #[test]
+    fn test_reflect_compiled_shaders() {
+        let vertex = erupt::utils::decode_spv(include_bytes!("../shaders/vert.spv")).unwrap();
+        let reflection =
+            ShaderReflection::new(&vertex, vk::ShaderStageFlagBits::VERTEX, "main").unwrap();
+        // Positions come from gl_VertexIndex so nothing is read from vertex buffers
+        assert!(reflection.inputs().is_empty());
+        assert!(reflection.descriptor_bindings().is_empty());
+
+        let fragment = erupt::utils::decode_spv(include_bytes!("../shaders/frag.spv")).unwrap();
+        let reflection =
+            ShaderReflection::new(&fragment, vk::ShaderStageFlagBits::FRAGMENT, "main").unwrap();
+        assert_eq!(
+            reflection.inputs(),
+            &[ShaderInput {
+                location: 0,
+                format: vk::Format::R32G32B32_SFLOAT
+            }]
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use super::{ConfigureShaders, ShaderData};
+use super::compile::{compile_glsl, compile_wgsl, load_shader_file};
+use super::specialization::set_constant;
+use super::{ConfigureShaders, ShaderData, ShaderReflection, SpecializationValue};
 use erupt::vk1_0 as vk;
 use std::ffi::CString;
+use std::path::{Path, PathBuf};
 
 use crate::error::{Error, ErrorKind};
 
@@ -29,7 +32,8 @@ impl<'a> ConfigureShaders<'a> {
     }
 
     // Creates the data associated with a shader so that it can be easily included when the pipeline is ready to be created.
-    // The shader module is the only data structure that is created before the pipeline is created.
+    // The shader module is the only data structure that is created before the pipeline is created,
+    // the code is reflected first so a missing entry point is reported before any module is created
     pub fn prepare_shader(
         &self,
         entry_name: &str,
@@ -42,14 +46,14 @@ impl<'a> ConfigureShaders<'a> {
                 None,
             )
         })?;
-        let shader_module_builder = vk::ShaderModuleCreateInfoBuilder::new();
-        shader_module_builder.code(code);
+        let reflection = ShaderReflection::new(code, shader_type, entry_name)?;
+        let shader_module_builder = vk::ShaderModuleCreateInfoBuilder::new().code(code);
         let shader_module = unsafe {
             self.device
                 .create_shader_module(&shader_module_builder, None, None)
         }
-        .expect("Failed to create vertex shader module");
-        let shader_data = ShaderData::new(owned_name, shader_module, shader_type);
+        .result()?;
+        let shader_data = ShaderData::new(owned_name, shader_module, shader_type, reflection);
         return Ok(shader_data);
     }
     // Create a vertex shader with a given entry name and code
@@ -67,6 +71,106 @@ impl<'a> ConfigureShaders<'a> {
         self.configured_shaders.push(shader_data);
         Ok(self)
     }
+
+    // Create a compute shader with a given entry name and code
+    pub fn create_compute_shader(
+        &mut self,
+        entry_name: &str,
+        code: &[u32],
+    ) -> Result<&mut Self, Error> {
+        let shader_data =
+            self.prepare_shader(entry_name, code, vk::ShaderStageFlagBits::COMPUTE)?;
+        self.configured_shaders.push(shader_data);
+        Ok(self)
+    }
+
+    /// Compiles GLSL source for a vertex, fragment or compute stage, name is used when reporting errors
+    pub fn create_shader_from_glsl(
+        &mut self,
+        stage: vk::ShaderStageFlagBits,
+        entry_name: &str,
+        name: &str,
+        source: &str,
+    ) -> Result<&mut Self, Error> {
+        let code = compile_glsl(name, source, entry_name, stage)?;
+        let shader_data = self.prepare_shader(entry_name, &code, stage)?;
+        self.configured_shaders.push(shader_data);
+        Ok(self)
+    }
+
+    /// Compiles WGSL source for a vertex, fragment or compute stage, name is used when reporting errors
+    pub fn create_shader_from_wgsl(
+        &mut self,
+        stage: vk::ShaderStageFlagBits,
+        entry_name: &str,
+        name: &str,
+        source: &str,
+    ) -> Result<&mut Self, Error> {
+        let code = compile_wgsl(name, source, entry_name, stage)?;
+        let shader_data = self.prepare_shader(entry_name, &code, stage)?;
+        self.configured_shaders.push(shader_data);
+        Ok(self)
+    }
+
+    /// Loads a shader for a stage from a .spv file or compiles it from a WGSL or GLSL file, in debug builds
+    /// a PipelineStore watches the file and rebuilds the pipelines using it when it changes
+    pub fn create_shader_from_file<P: AsRef<Path>>(
+        &mut self,
+        stage: vk::ShaderStageFlagBits,
+        entry_name: &str,
+        path: P,
+    ) -> Result<&mut Self, Error> {
+        let path = path.as_ref();
+        let code = load_shader_file(path, entry_name, stage)?;
+        let mut shader_data = self.prepare_shader(entry_name, &code, stage)?;
+        shader_data.path = Some(PathBuf::from(path));
+        self.configured_shaders.push(shader_data);
+        Ok(self)
+    }
+}
+
+impl<'a> ConfigureShaders<'a> {
+    /// Sets a specialization constant of the shader created for the stage, the value must have the type the
+    /// constant is declared with. Pipelines created afterwards use the value
+    pub fn specialize<T: Into<SpecializationValue>>(
+        &mut self,
+        stage: vk::ShaderStageFlagBits,
+        constant_id: u32,
+        value: T,
+    ) -> Result<&mut Self, Error> {
+        let shader = self
+            .configured_shaders
+            .iter_mut()
+            .find(|shader| shader.shader_type == stage)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::InvalidPipelineConfig, None)
+                    .with_context(&"No shader was created for the stage")
+            })?;
+        set_constant(&mut shader.specialization, stage, constant_id, value.into())?;
+        Ok(self)
+    }
+
+    /// The reflected interface of the shader created for the stage
+    pub fn reflection(&self, stage: vk::ShaderStageFlagBits) -> Option<&ShaderReflection> {
+        self.configured_shaders
+            .iter()
+            .find(|shader| shader.shader_type == stage)
+            .map(|shader| &shader.reflection)
+    }
+
+    pub(super) fn shaders(&self) -> &[ShaderData] {
+        self.configured_shaders.as_slice()
+    }
+
+    // Shader modules are only needed until the pipelines using them have been created
+    pub(super) fn destroy(self) {
+        for shader in self.configured_shaders {
+            unsafe {
+                self.device
+                    .destroy_shader_module(Some(shader.shader_module), None)
+            };
+        }
+    }
 }
 
 impl ShaderData {
@@ -74,11 +178,19 @@ impl ShaderData {
         entry_name: CString,
         module: vk::ShaderModule,
         shader_type: vk::ShaderStageFlagBits,
+        reflection: ShaderReflection,
     ) -> ShaderData {
         ShaderData {
             entry_name,
             shader_type,
             shader_module: module,
+            specialization: reflection.specialization_constants().to_vec(),
+            reflection,
+            path: None,
         }
     }
+
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
 }
@@ -0,0 +1,163 @@
+use super::SpecializationValue;
+use crate::error::{Error, ErrorKind};
+use erupt::vk1_0 as vk;
+use std::hash::{Hash, Hasher};
+
+impl SpecializationValue {
+    fn type_name(self) -> &'static str {
+        match self {
+            SpecializationValue::Bool(_) => "bool",
+            SpecializationValue::Int(_) => "int",
+            SpecializationValue::UInt(_) => "uint",
+            SpecializationValue::Float(_) => "float",
+            SpecializationValue::Double(_) => "double",
+        }
+    }
+
+    // The bits of the value as the shader reads them, booleans are 32 bit
+    fn bits(self) -> u64 {
+        match self {
+            SpecializationValue::Bool(value) => value as u64,
+            SpecializationValue::Int(value) => value as u32 as u64,
+            SpecializationValue::UInt(value) => value as u64,
+            SpecializationValue::Float(value) => value.to_bits() as u64,
+            SpecializationValue::Double(value) => value.to_bits(),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            SpecializationValue::Double(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+impl PartialEq for SpecializationValue {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.bits() == other.bits()
+    }
+}
+
+impl Eq for SpecializationValue {}
+
+impl Hash for SpecializationValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        self.bits().hash(state);
+    }
+}
+
+impl From<bool> for SpecializationValue {
+    fn from(value: bool) -> Self {
+        SpecializationValue::Bool(value)
+    }
+}
+
+impl From<i32> for SpecializationValue {
+    fn from(value: i32) -> Self {
+        SpecializationValue::Int(value)
+    }
+}
+
+impl From<u32> for SpecializationValue {
+    fn from(value: u32) -> Self {
+        SpecializationValue::UInt(value)
+    }
+}
+
+impl From<f32> for SpecializationValue {
+    fn from(value: f32) -> Self {
+        SpecializationValue::Float(value)
+    }
+}
+
+impl From<f64> for SpecializationValue {
+    fn from(value: f64) -> Self {
+        SpecializationValue::Double(value)
+    }
+}
+
+/// Replaces the value of a constant the shader declares, the value must have the type of the constant
+pub(super) fn set_constant(
+    constants: &mut [(u32, SpecializationValue)],
+    stage: vk::ShaderStageFlagBits,
+    constant_id: u32,
+    value: SpecializationValue,
+) -> Result<(), Error> {
+    let constant = constants
+        .iter_mut()
+        .find(|(id, _)| *id == constant_id)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::ShaderInterfaceMismatch(format!(
+                    "The {:?} shader has no specialization constant with id {}",
+                    stage, constant_id
+                )),
+                None,
+            )
+        })?;
+    if std::mem::discriminant(&constant.1) != std::mem::discriminant(&value) {
+        return Err(Error::new(
+            ErrorKind::ShaderInterfaceMismatch(format!(
+                "Specialization constant {} of the {:?} shader is a {} not a {}",
+                constant_id,
+                stage,
+                constant.1.type_name(),
+                value.type_name()
+            )),
+            None,
+        ));
+    }
+    constant.1 = value;
+    Ok(())
+}
+
+/// The map entries and data of a stage, these must outlive the specialization info that points to them
+pub(super) fn specialization_data(
+    constants: &[(u32, SpecializationValue)],
+) -> (Vec<vk::SpecializationMapEntryBuilder<'static>>, Vec<u8>) {
+    let mut entries = Vec::with_capacity(constants.len());
+    let mut data = Vec::new();
+    for (constant_id, value) in constants {
+        let entry = vk::SpecializationMapEntryBuilder::new()
+            .constant_id(*constant_id)
+            .offset(data.len() as u32)
+            .size(value.size());
+        match value.size() {
+            8 => data.extend_from_slice(&value.bits().to_ne_bytes()),
+            _ => data.extend_from_slice(&(value.bits() as u32).to_ne_bytes()),
+        }
+        entries.push(entry);
+    }
+    (entries, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_specialization_constant() {
+        let mut constants = vec![
+            (0, SpecializationValue::UInt(3)),
+            (4, SpecializationValue::Bool(false)),
+            (7, SpecializationValue::Double(0.5)),
+        ];
+        let stage = vk::ShaderStageFlagBits::FRAGMENT;
+        set_constant(&mut constants, stage, 0, 9u32.into()).unwrap();
+        set_constant(&mut constants, stage, 4, true.into()).unwrap();
+        assert_eq!(constants[0].1, SpecializationValue::UInt(9));
+        assert_eq!(constants[1].1, SpecializationValue::Bool(true));
+        // Wrong types and unknown ids leave the constants unchanged
+        assert!(set_constant(&mut constants, stage, 0, 9i32.into()).is_err());
+        assert!(set_constant(&mut constants, stage, 2, 1u32.into()).is_err());
+        assert_eq!(constants[0].1, SpecializationValue::UInt(9));
+
+        let (entries, data) = specialization_data(&constants);
+        assert_eq!(data.len(), 16);
+        assert_eq!((entries[1].offset, entries[1].size), (4, 4));
+        assert_eq!((entries[2].offset, entries[2].size), (8, 8));
+        assert_eq!(data[8..16], 0.5f64.to_ne_bytes());
+    }
+}
@@ -0,0 +1,312 @@
+use super::{GraphicsPipeline, PipelineDescription, PipelineStore};
+use crate::error::Error;
+#[cfg(debug_assertions)]
+use crate::error::ErrorKind;
+use crate::{Renderpass, VulkanDevice};
+use erupt::vk1_0 as vk;
+#[cfg(debug_assertions)]
+use notify::Watcher;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::PathBuf;
+#[cfg(debug_assertions)]
+use std::sync::mpsc::{self, Receiver};
+
+type ShaderFile = (PathBuf, vk::ShaderStageFlagBits, CString);
+
+#[cfg(debug_assertions)]
+fn watch_failed(error: notify::Error) -> Error {
+    Error::new(ErrorKind::ShaderWatchFailed(error.to_string()), None)
+}
+
+// A watcher that sends the path of every file created or modified in the directories it watches
+#[cfg(debug_assertions)]
+fn file_watcher() -> (Result<notify::RecommendedWatcher, Error>, Receiver<PathBuf>) {
+    let (sender, receiver) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // Editors often save by replacing the file so creating it counts as a change
+        if let Ok(event) = event {
+            if event.kind.is_modify() || event.kind.is_create() {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        }
+    });
+    (watcher.map_err(watch_failed), receiver)
+}
+
+// Replaces the modules of shaders loaded from files that have been recompiled
+fn with_reloaded_shaders(
+    description: &PipelineDescription,
+    reloaded_shaders: &HashMap<ShaderFile, vk::ShaderModule>,
+) -> PipelineDescription {
+    let mut description = description.clone();
+    for shader in description.shaders.iter_mut() {
+        if let Some(path) = &shader.path {
+            let file = (path.clone(), shader.stage, shader.entry_name.clone());
+            if let Some(module) = reloaded_shaders.get(&file) {
+                shader.module = *module;
+            }
+        }
+    }
+    description
+}
+
+impl PipelineStore {
+    pub fn new() -> PipelineStore {
+        #[cfg(debug_assertions)]
+        let (watcher, changed_files) = file_watcher();
+        #[cfg(debug_assertions)]
+        let (watcher, watch_error) = match watcher {
+            Ok(watcher) => (Some(watcher), None),
+            Err(error) => (None, Some(error)),
+        };
+        PipelineStore {
+            pipelines: HashMap::new(),
+            #[cfg(debug_assertions)]
+            shader_files: HashMap::new(),
+            #[cfg(debug_assertions)]
+            watcher,
+            #[cfg(debug_assertions)]
+            changed_files,
+            #[cfg(debug_assertions)]
+            watch_error,
+            reloaded_shaders: HashMap::new(),
+        }
+    }
+
+    /// Returns the pipeline created for an identical description and subpass, creating it if there is none
+    pub fn get_or_create(
+        &mut self,
+        device: &VulkanDevice,
+        description: &PipelineDescription,
+        renderpass: &Renderpass,
+        subpass: u32,
+    ) -> Result<&GraphicsPipeline, Error> {
+        let render_pass = renderpass.subpass_handle(subpass)?;
+        let description = with_reloaded_shaders(description, &self.reloaded_shaders);
+        let key = (description, renderpass.id(), subpass);
+        if !self.pipelines.contains_key(&key) {
+            key.0.validate(&device.enabled_features, &device.limits)?;
+            let pipeline = key.0.create_with(
+                device.handle(),
+                device.pipeline_cache().handle(),
+                render_pass,
+                subpass,
+                None,
+            )?;
+            #[cfg(debug_assertions)]
+            self.watch_shader_files(&key.0);
+            self.pipelines.insert(key.clone(), (render_pass, pipeline));
+        }
+        Ok(&self.pipelines[&key].1)
+    }
+
+    #[cfg(debug_assertions)]
+    fn watch_shader_files(&mut self, description: &PipelineDescription) {
+        let watcher = match self.watcher.as_mut() {
+            Some(watcher) => watcher,
+            None => return,
+        };
+        for path in description
+            .shaders
+            .iter()
+            .filter_map(|shader| shader.path.as_ref())
+        {
+            let file = match std::fs::canonicalize(path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+            if self.shader_files.contains_key(&file) {
+                continue;
+            }
+            // Watching the directory still sees the file after an editor replaces it
+            if let Some(directory) = file.parent() {
+                let watched = self
+                    .shader_files
+                    .keys()
+                    .any(|watched| watched.parent() == Some(directory));
+                if !watched {
+                    if let Err(error) =
+                        watcher.watch(directory, notify::RecursiveMode::NonRecursive)
+                    {
+                        self.watch_error = Some(watch_failed(error));
+                        continue;
+                    }
+                }
+            }
+            self.shader_files.insert(file, path.clone());
+        }
+    }
+
+    /// Recompiles the shader files that changed since the last call and rebuilds the pipelines using them, returns
+    /// the number of pipelines rebuilt. Changes are watched for in the background but Vulkan objects are only
+    /// replaced here so call it once a frame before recording. If a shader fails to compile the error is returned
+    /// once for that change and the existing pipelines keep being used, if shader files can't be watched that
+    /// error is also returned once
+    #[cfg(debug_assertions)]
+    pub fn reload_changed_shaders(&mut self, device: &VulkanDevice) -> Result<usize, Error> {
+        if let Some(error) = self.watch_error.take() {
+            return Err(error);
+        }
+        let mut changed = Vec::new();
+        for file in self.changed_files.try_iter() {
+            if let Some(path) = self.shader_files.get(&file) {
+                if !changed.contains(path) {
+                    changed.push(path.clone());
+                }
+            }
+        }
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        let mut files: Vec<ShaderFile> = Vec::new();
+        for (description, _, _) in self.pipelines.keys() {
+            for shader in description.shaders.iter() {
+                if let Some(path) = shader.path.as_ref().filter(|path| changed.contains(path)) {
+                    let file = (path.clone(), shader.stage, shader.entry_name.clone());
+                    if !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+            }
+        }
+        // Every changed shader is compiled before any pipeline is touched
+        let mut modules = HashMap::new();
+        for file in files {
+            match Self::compile_module(device, &file) {
+                Ok(module) => {
+                    modules.insert(file, module);
+                }
+                Err(error) => {
+                    Self::destroy_modules(device, modules.values());
+                    return Err(error);
+                }
+            }
+        }
+        let mut rebuilt = Vec::new();
+        for ((description, id, subpass), (render_pass, _)) in self.pipelines.iter() {
+            let uses_changed_file = description.shaders.iter().any(|shader| {
+                shader.path.as_ref().is_some_and(|path| {
+                    modules.contains_key(&(path.clone(), shader.stage, shader.entry_name.clone()))
+                })
+            });
+            if !uses_changed_file {
+                continue;
+            }
+            let new_description = with_reloaded_shaders(description, &modules);
+            let created = new_description.create_with(
+                device.handle(),
+                device.pipeline_cache().handle(),
+                *render_pass,
+                *subpass,
+                None,
+            );
+            match created {
+                Ok(pipeline) => rebuilt.push((
+                    (description.clone(), *id, *subpass),
+                    (new_description, *id, *subpass),
+                    (*render_pass, pipeline),
+                )),
+                Err(error) => {
+                    for (_, _, (_, pipeline)) in rebuilt {
+                        pipeline.destroy(device);
+                    }
+                    Self::destroy_modules(device, modules.values());
+                    return Err(error);
+                }
+            }
+        }
+        // The old pipelines and modules may still be used by commands that are executing
+        unsafe { device.handle().device_wait_idle() }.result()?;
+        let rebuilt_count = rebuilt.len();
+        for (old_key, new_key, pipeline) in rebuilt {
+            if let Some((_, old_pipeline)) = self.pipelines.remove(&old_key) {
+                old_pipeline.destroy(device);
+            }
+            self.pipelines.insert(new_key, pipeline);
+        }
+        for (file, module) in modules {
+            if let Some(old_module) = self.reloaded_shaders.insert(file, module) {
+                unsafe {
+                    device
+                        .handle()
+                        .destroy_shader_module(Some(old_module), None)
+                };
+            }
+        }
+        Ok(rebuilt_count)
+    }
+
+    /// Shader files are only watched in debug builds so release builds never rebuild anything
+    #[cfg(not(debug_assertions))]
+    pub fn reload_changed_shaders(&mut self, _device: &VulkanDevice) -> Result<usize, Error> {
+        Ok(0)
+    }
+
+    #[cfg(debug_assertions)]
+    fn compile_module(device: &VulkanDevice, file: &ShaderFile) -> Result<vk::ShaderModule, Error> {
+        let (path, stage, entry_name) = file;
+        let entry_name = entry_name.to_string_lossy();
+        let code = super::compile::load_shader_file(path, &entry_name, *stage)?;
+        // Reports a missing entry point as an error instead of creating a broken pipeline
+        super::ShaderReflection::new(&code, *stage, &entry_name)?;
+        let create_info = vk::ShaderModuleCreateInfoBuilder::new().code(&code);
+        let module = unsafe {
+            device
+                .handle()
+                .create_shader_module(&create_info, None, None)
+        }
+        .result()?;
+        Ok(module)
+    }
+
+    fn destroy_modules<'m>(
+        device: &VulkanDevice,
+        modules: impl Iterator<Item = &'m vk::ShaderModule>,
+    ) {
+        for module in modules {
+            unsafe { device.handle().destroy_shader_module(Some(*module), None) };
+        }
+    }
+
+    /// The number of distinct pipelines in the store
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Destroys the pipelines created for the renderpass, call before destroying the renderpass when the
+    /// swapchain is recreated then request the pipelines again with the new renderpass
+    pub fn release_renderpass(&mut self, device: &VulkanDevice, renderpass: &Renderpass) {
+        let keys: Vec<_> = self
+            .pipelines
+            .keys()
+            .filter(|(_, id, _)| *id == renderpass.id())
+            .cloned()
+            .collect();
+        for key in keys {
+            if let Some((_, pipeline)) = self.pipelines.remove(&key) {
+                pipeline.destroy(device);
+            }
+        }
+    }
+
+    /// Destroys every pipeline in the store and the shader modules it recompiled, none of them can be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        for (_, (_, pipeline)) in self.pipelines {
+            pipeline.destroy(device);
+        }
+        Self::destroy_modules(device, self.reloaded_shaders.values());
+    }
+}
+
+impl Default for PipelineStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,9 @@
 use super::{
-    ColorBlendingSettings, DepthStencilSettings, DynamicStateSettings, InputAssembelySettings,
-    LayoutSettings, MultiSampleSettings, RasterizerSettings, VertexInputSettings, ViewportManager,
+    AttributeFormat, ColorBlendingSettings, DepthStencilSettings, DynamicStateSettings,
+    InputAssembelySettings, LayoutSettings, MultiSampleSettings, RasterizerSettings,
+    VertexAttribute, VertexInputSettings, ViewportManager,
 };
+use crate::error::Error;
 use erupt::vk1_0 as vk;
 pub trait ConfigureVertexInput {
     fn configure_vertex_input(
@@ -60,5 +62,48 @@ pub trait ConfigureDynamicState {
 }
 
 pub trait ConfigureLayout {
-    fn configure_layout(&mut self, configure_layout: &mut dyn FnMut(&mut LayoutSettings));
+    fn configure_layout(
+        &mut self,
+        configure_layout: &mut dyn FnMut(&mut LayoutSettings) -> Result<(), Error>,
+    ) -> Result<(), Error>;
+}
+
+/// A Rust struct that is stored in vertex buffers, derive it for a #[repr(C)] struct with #[derive(Vertex)]
+pub trait Vertex: Sized {
+    /// The number of bytes from the start of one vertex to the next
+    fn stride() -> u32 {
+        std::mem::size_of::<Self>() as u32
+    }
+
+    /// The attributes read from each vertex in order of their location
+    fn attributes() -> Vec<VertexAttribute>;
+}
+
+/// A Rust type that can be read as a vertex attribute without naming its format
+pub trait VertexAttributeType {
+    const FORMAT: AttributeFormat;
+}
+
+/// A Rust struct that is pushed to shaders as a push constant block, the struct must be #[repr(C)] and
+/// match the layout of the block declared in the shaders
+pub trait PushConstants: Copy {
+    /// The shader stages that read the block
+    const STAGES: vk::ShaderStageFlags;
+    /// Where the block starts in the push constant range of the pipeline layout
+    const OFFSET: u32 = 0;
+    /// The size of the block, checked at compile time to be a non zero multiple of 4 starting at a multiple of 4
+    // is_multiple_of needs a newer compiler than the crate otherwise does
+    #[allow(clippy::manual_is_multiple_of)]
+    const SIZE: u32 = {
+        let size = std::mem::size_of::<Self>();
+        assert!(
+            size > 0 && size % 4 == 0,
+            "Push constants must be a non zero multiple of 4 bytes in size"
+        );
+        assert!(
+            Self::OFFSET % 4 == 0,
+            "Push constants must start at a multiple of 4 bytes"
+        );
+        size as u32
+    };
 }
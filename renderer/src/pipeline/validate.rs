@@ -0,0 +1,470 @@
+use super::{BlendAttachmentDescription, PipelineDescription};
+use crate::error::{Error, ErrorKind};
+use crate::RenderingFormats;
+use erupt::vk1_0 as vk;
+
+fn invalid_config(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidPipelineConfig, None).with_context(context)
+}
+
+// Fails with the context when the check is true
+fn check(failed: bool, context: &'static &'static str) -> Result<(), Error> {
+    if failed {
+        Err(invalid_config(context))
+    } else {
+        Ok(())
+    }
+}
+
+fn outside(value: f32, range: [f32; 2]) -> bool {
+    value < range[0] || value > range[1]
+}
+
+fn uses_second_source(attachment: &BlendAttachmentDescription) -> bool {
+    [
+        attachment.src_color_blend_factor,
+        attachment.dst_color_blend_factor,
+        attachment.src_alpha_blend_factor,
+        attachment.dst_alpha_blend_factor,
+    ]
+    .iter()
+    .any(|factor| {
+        matches!(
+            *factor,
+            vk::BlendFactor::SRC1_COLOR
+                | vk::BlendFactor::ONE_MINUS_SRC1_COLOR
+                | vk::BlendFactor::SRC1_ALPHA
+                | vk::BlendFactor::ONE_MINUS_SRC1_ALPHA
+        )
+    })
+}
+
+impl PipelineDescription {
+    fn is_dynamic(&self, state: vk::DynamicState) -> bool {
+        self.dynamic_states.contains(&state)
+    }
+
+    /// Checks every setting against the features that were enabled on the device and the limits of the device,
+    /// Vulkan leaves the behaviour of a pipeline that breaks either undefined
+    pub(crate) fn validate(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        self.validate_shaders(features)?;
+        self.validate_vertex_input(limits)?;
+        self.validate_viewports(features, limits)?;
+        self.validate_rasterizer(features, limits)?;
+        self.validate_multisampling(features, limits)?;
+        self.validate_depth_stencil(features)?;
+        self.validate_blending(features, limits)
+    }
+
+    /// Checks the formats a pipeline created for dynamic rendering renders to against the description
+    pub(crate) fn validate_rendering_formats(
+        &self,
+        formats: &RenderingFormats,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        let color_formats = formats.color_formats();
+        check(
+            color_formats.len() > limits.max_color_attachments as usize,
+            &"There are more colour formats than the maxColorAttachments limit",
+        )?;
+        check(
+            color_formats.len() != self.color_blend.attachments.len(),
+            &"There must be a blend attachment for each colour format",
+        )?;
+        check(
+            color_formats.contains(&vk::Format::UNDEFINED),
+            &"A colour format is undefined",
+        )
+    }
+
+    fn validate_shaders(&self, features: &vk::PhysicalDeviceFeatures) -> Result<(), Error> {
+        for shader in self.shaders.iter() {
+            match shader.stage {
+                vk::ShaderStageFlagBits::GEOMETRY => check(
+                    features.geometry_shader == 0,
+                    &"Geometry shaders need the geometryShader feature",
+                )?,
+                vk::ShaderStageFlagBits::TESSELLATION_CONTROL
+                | vk::ShaderStageFlagBits::TESSELLATION_EVALUATION => check(
+                    features.tessellation_shader == 0,
+                    &"Tessellation shaders need the tessellationShader feature",
+                )?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_vertex_input(&self, limits: &vk::PhysicalDeviceLimits) -> Result<(), Error> {
+        for binding in self.vertex_bindings.iter() {
+            check(
+                binding.binding >= limits.max_vertex_input_bindings,
+                &"A vertex binding number is not less than the maxVertexInputBindings limit",
+            )?;
+            check(
+                binding.stride > limits.max_vertex_input_binding_stride,
+                &"A vertex binding stride is larger than the maxVertexInputBindingStride limit",
+            )?;
+        }
+        for attribute in self.vertex_attributes.iter() {
+            check(
+                attribute.location >= limits.max_vertex_input_attributes,
+                &"A vertex attribute location is not less than the maxVertexInputAttributes limit",
+            )?;
+            check(
+                attribute.offset > limits.max_vertex_input_attribute_offset,
+                &"A vertex attribute offset is larger than the maxVertexInputAttributeOffset limit",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn validate_viewports(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        check(
+            !self.viewports.is_empty()
+                && !self.scissors.is_empty()
+                && self.viewports.len() != self.scissors.len(),
+            &"There must be a scissor for every viewport",
+        )?;
+        let count = self.viewports.len().max(self.scissors.len());
+        check(
+            count > 1 && features.multi_viewport == 0,
+            &"More than one viewport or scissor needs the multiViewport feature",
+        )?;
+        check(
+            count > limits.max_viewports as usize,
+            &"There are more viewports or scissors than the maxViewports limit",
+        )?;
+        for scissor in self.scissors.iter() {
+            check(
+                scissor.offset.0 < 0 || scissor.offset.1 < 0,
+                &"A scissor has a negative offset",
+            )?;
+            check(
+                scissor.offset.0 as i64 + scissor.extent.0 as i64 > i32::MAX as i64
+                    || scissor.offset.1 as i64 + scissor.extent.1 as i64 > i32::MAX as i64,
+                &"A scissor extends past the largest signed 32 bit coordinate",
+            )?;
+        }
+        // Dynamic viewports are checked by the device when they are set
+        if self.is_dynamic(vk::DynamicState::VIEWPORT) {
+            return Ok(());
+        }
+        for viewport in self.viewports.iter() {
+            check(
+                viewport.width.0 > limits.max_viewport_dimensions[0] as f32
+                    || viewport.height.0.abs() > limits.max_viewport_dimensions[1] as f32,
+                &"A viewport is larger than the maxViewportDimensions limit",
+            )?;
+            let bounds = limits.viewport_bounds_range;
+            check(
+                outside(viewport.x.0, bounds)
+                    || outside(viewport.y.0, bounds)
+                    || outside(viewport.x.0 + viewport.width.0, bounds)
+                    || outside(viewport.y.0 + viewport.height.0, bounds),
+                &"A viewport is outside the viewportBoundsRange limit",
+            )?;
+            check(
+                outside(viewport.min_depth.0, [0.0, 1.0])
+                    || outside(viewport.max_depth.0, [0.0, 1.0]),
+                &"Viewport depths must be between 0 and 1",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn validate_rasterizer(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        let rasterizer = &self.rasterizer;
+        check(
+            rasterizer.depth_clamp && features.depth_clamp == 0,
+            &"Depth clamping needs the depthClamp feature",
+        )?;
+        check(
+            rasterizer.polygon_mode != vk::PolygonMode::FILL && features.fill_mode_non_solid == 0,
+            &"Line and point polygon modes need the fillModeNonSolid feature",
+        )?;
+        check(
+            rasterizer.depth_bias
+                && rasterizer.depth_bias_clamp.0 != 0.0
+                && features.depth_bias_clamp == 0
+                && !self.is_dynamic(vk::DynamicState::DEPTH_BIAS),
+            &"A depth bias clamp needs the depthBiasClamp feature",
+        )?;
+        if !self.is_dynamic(vk::DynamicState::LINE_WIDTH) {
+            check(
+                rasterizer.line_width.0 != 1.0 && features.wide_lines == 0,
+                &"A line width other than 1.0 needs the wideLines feature",
+            )?;
+            check(
+                rasterizer.line_width.0 != 1.0
+                    && outside(rasterizer.line_width.0, limits.line_width_range),
+                &"The line width is outside the lineWidthRange limit",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn validate_multisampling(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        let multisample = &self.multisample;
+        let samples = multisample.samples.bitmask();
+        check(
+            !limits.framebuffer_color_sample_counts.contains(samples),
+            &"The sample count is not in the framebufferColorSampleCounts limit",
+        )?;
+        check(
+            self.depth_stencil.is_some()
+                && !limits.framebuffer_depth_sample_counts.contains(samples),
+            &"The sample count is not in the framebufferDepthSampleCounts limit",
+        )?;
+        check(
+            multisample.sample_shading && features.sample_rate_shading == 0,
+            &"Sample shading needs the sampleRateShading feature",
+        )?;
+        check(
+            multisample.sample_shading && outside(multisample.min_sample_shading.0, [0.0, 1.0]),
+            &"The minimum sample shading must be between 0 and 1",
+        )?;
+        check(
+            multisample.alpha_to_one && features.alpha_to_one == 0,
+            &"Alpha to one needs the alphaToOne feature",
+        )
+    }
+
+    fn validate_depth_stencil(&self, features: &vk::PhysicalDeviceFeatures) -> Result<(), Error> {
+        let depth_stencil = match &self.depth_stencil {
+            Some(depth_stencil) if depth_stencil.depth_bounds_test => depth_stencil,
+            _ => return Ok(()),
+        };
+        check(
+            features.depth_bounds == 0,
+            &"The depth bounds test needs the depthBounds feature",
+        )?;
+        check(
+            !self.is_dynamic(vk::DynamicState::DEPTH_BOUNDS)
+                && (outside(depth_stencil.min_depth_bounds.0, [0.0, 1.0])
+                    || outside(depth_stencil.max_depth_bounds.0, [0.0, 1.0])),
+            &"Depth bounds must be between 0 and 1",
+        )
+    }
+
+    fn validate_blending(
+        &self,
+        features: &vk::PhysicalDeviceFeatures,
+        limits: &vk::PhysicalDeviceLimits,
+    ) -> Result<(), Error> {
+        let color_blend = &self.color_blend;
+        check(
+            color_blend.logic_op.is_some() && features.logic_op == 0,
+            &"Bitwise blending needs the logicOp feature",
+        )?;
+        check(
+            color_blend.attachments.len() > limits.max_color_attachments as usize,
+            &"There are more blend attachments than the maxColorAttachments limit",
+        )?;
+        check(
+            color_blend
+                .attachments
+                .windows(2)
+                .any(|pair| pair[0] != pair[1])
+                && features.independent_blend == 0,
+            &"Blending attachments differently needs the independentBlend feature",
+        )?;
+        check(
+            color_blend.attachments.iter().any(uses_second_source) && features.dual_src_blend == 0,
+            &"Blend factors that read a second source need the dualSrcBlend feature",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        BlendMode, ColorBlendDescription, FloatBits, MultisampleDescription, RasterizerDescription,
+        ScissorDescription, ViewportDescription,
+    };
+    use super::*;
+    use crate::{Feature, Features, Vulkan12Features};
+
+    fn viewport() -> ViewportDescription {
+        ViewportDescription {
+            x: FloatBits(0.0),
+            y: FloatBits(0.0),
+            width: FloatBits(800.0),
+            height: FloatBits(600.0),
+            min_depth: FloatBits(0.0),
+            max_depth: FloatBits(1.0),
+        }
+    }
+
+    fn scissor() -> ScissorDescription {
+        ScissorDescription {
+            offset: (0, 0),
+            extent: (800, 600),
+        }
+    }
+
+    fn description() -> PipelineDescription {
+        PipelineDescription {
+            shaders: Vec::new(),
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            primitive_restart: false,
+            viewports: vec![viewport()],
+            scissors: vec![scissor()],
+            rasterizer: RasterizerDescription {
+                depth_clamp: false,
+                rasterizer_discard: false,
+                polygon_mode: vk::PolygonMode::FILL,
+                cull_mode: vk::CullModeFlags::BACK,
+                front_face: vk::FrontFace::CLOCKWISE,
+                depth_bias: false,
+                depth_bias_constant_factor: FloatBits(0.0),
+                depth_bias_clamp: FloatBits(0.0),
+                depth_bias_slope_factor: FloatBits(0.0),
+                line_width: FloatBits(1.0),
+            },
+            multisample: MultisampleDescription {
+                samples: vk::SampleCountFlagBits::_1,
+                sample_shading: false,
+                min_sample_shading: FloatBits(0.0),
+                sample_masks: Vec::new(),
+                alpha_to_coverage: false,
+                alpha_to_one: false,
+            },
+            depth_stencil: None,
+            color_blend: ColorBlendDescription {
+                logic_op: None,
+                attachments: Vec::new(),
+                constants: [FloatBits(0.0); 4],
+            },
+            dynamic_states: Vec::new(),
+            set_layouts: Vec::new(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+
+    fn limits() -> vk::PhysicalDeviceLimits {
+        vk::PhysicalDeviceLimits {
+            max_viewports: 16,
+            max_viewport_dimensions: [4096, 4096],
+            viewport_bounds_range: [-8192.0, 8191.0],
+            line_width_range: [1.0, 8.0],
+            framebuffer_color_sample_counts: vk::SampleCountFlags::_1 | vk::SampleCountFlags::_4,
+            framebuffer_depth_sample_counts: vk::SampleCountFlags::_1 | vk::SampleCountFlags::_4,
+            max_color_attachments: 8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_against_features_and_limits() {
+        let none = vk::PhysicalDeviceFeatures::default();
+        let all = vk::PhysicalDeviceFeatures {
+            wide_lines: 1,
+            fill_mode_non_solid: 1,
+            multi_viewport: 1,
+            depth_bounds: 1,
+            ..Default::default()
+        };
+        assert!(description().validate(&none, &limits()).is_ok());
+
+        let mut wide_lines = description();
+        wide_lines.rasterizer.line_width = FloatBits(4.0);
+        assert!(wide_lines.validate(&none, &limits()).is_err());
+        assert!(wide_lines.validate(&all, &limits()).is_ok());
+        wide_lines.rasterizer.line_width = FloatBits(16.0);
+        assert!(wide_lines.validate(&all, &limits()).is_err());
+        // A dynamic line width is set while recording instead
+        wide_lines.dynamic_states.push(vk::DynamicState::LINE_WIDTH);
+        assert!(wide_lines.validate(&all, &limits()).is_ok());
+
+        let mut wireframe = description();
+        wireframe.rasterizer.polygon_mode = vk::PolygonMode::LINE;
+        assert!(wireframe.validate(&none, &limits()).is_err());
+        assert!(wireframe.validate(&all, &limits()).is_ok());
+
+        let mut viewports = description();
+        viewports.viewports.push(viewport());
+        assert!(viewports.validate(&all, &limits()).is_err());
+        viewports.scissors.push(scissor());
+        assert!(viewports.validate(&none, &limits()).is_err());
+        assert!(viewports.validate(&all, &limits()).is_ok());
+        viewports.viewports[1].width = FloatBits(8000.0);
+        assert!(viewports.validate(&all, &limits()).is_err());
+
+        let mut samples = description();
+        samples.multisample.samples = vk::SampleCountFlagBits::_8;
+        assert!(samples.validate(&all, &limits()).is_err());
+        samples.multisample.samples = vk::SampleCountFlagBits::_4;
+        assert!(samples.validate(&all, &limits()).is_ok());
+
+        let mut blending = description();
+        for mode in [BlendMode::PremultipliedAlpha, BlendMode::Screen].iter() {
+            let attachment = mode.attachment();
+            blending
+                .color_blend
+                .attachments
+                .push(BlendAttachmentDescription::new(&attachment));
+        }
+        assert!(blending.validate(&all, &limits()).is_err());
+        let mut independent = vk::PhysicalDeviceFeatures::default();
+        let independent_blend = Features::IndependentBlend.device_feature(&mut independent);
+        Feature::new(true, independent_blend.unwrap()).enable();
+        assert!(blending.validate(&independent, &limits()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_enabled_feature() {
+        let mut wireframe = description();
+        wireframe.rasterizer.polygon_mode = vk::PolygonMode::LINE;
+        wireframe.rasterizer.line_width = FloatBits(2.0);
+        let mut enabled = vk::PhysicalDeviceFeatures::default();
+        for feature in [Features::FillModeNonSolid, Features::WideLines].iter() {
+            Feature::new(true, feature.device_feature(&mut enabled).unwrap()).enable();
+        }
+        assert!(Features::WideLines.is_set_in(&enabled, &Vulkan12Features::default()));
+        assert!(wireframe.validate(&enabled, &limits()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rendering_formats() {
+        let mut blending = description();
+        let attachment = BlendMode::PremultipliedAlpha.attachment();
+        blending
+            .color_blend
+            .attachments
+            .push(BlendAttachmentDescription::new(&attachment));
+        let formats = RenderingFormats::new(&[vk::Format::B8G8R8A8_SRGB], None);
+        assert!(blending
+            .validate_rendering_formats(&formats, &limits())
+            .is_ok());
+        let formats = RenderingFormats::new(
+            &[vk::Format::B8G8R8A8_SRGB, vk::Format::R16G16B16A16_SFLOAT],
+            Some(vk::Format::D32_SFLOAT),
+        );
+        assert!(blending
+            .validate_rendering_formats(&formats, &limits())
+            .is_err());
+        let formats = RenderingFormats::new(&[vk::Format::UNDEFINED], None);
+        assert!(blending
+            .validate_rendering_formats(&formats, &limits())
+            .is_err());
+    }
+}
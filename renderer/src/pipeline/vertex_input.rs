@@ -1,6 +1,6 @@
 use crate::Feature;
 
-use super::traits::{ConfigureInputAssembely, ConfigureVertexInput};
+use super::traits::{ConfigureInputAssembely, ConfigureVertexInput, Vertex, VertexAttributeType};
 use super::{AttributeFormat, ConfigurePipeline, VertexBinding, VertexInputSettings};
 
 use erupt::vk1_0 as vk;
@@ -31,9 +31,53 @@ impl<'a> ConfigureVertexInput for ConfigurePipeline<'a> {
     }
 }
 
-impl AttributeFormat {}
+impl AttributeFormat {
+    /// The number of locations the attribute uses, 64 bit vectors of more than two components use two
+    pub fn locations(self) -> u32 {
+        match self {
+            AttributeFormat::DVec3 | AttributeFormat::DVec4 => 2,
+            _ => 1,
+        }
+    }
+}
+
+macro_rules! attribute_type {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexAttributeType for $ty {
+                const FORMAT: AttributeFormat = AttributeFormat::$format;
+            }
+        )*
+    };
+}
 
-// TODO: Use rusts types
+// Arrays of bytes and shorts are most often colours and normals so they default to normalized formats
+attribute_type! {
+    f32 => Float,
+    [f32; 2] => Vec2,
+    [f32; 3] => Vec3,
+    [f32; 4] => Vec4,
+    f64 => Double,
+    [f64; 2] => DVec2,
+    [f64; 3] => DVec3,
+    [f64; 4] => DVec4,
+    u32 => UInt,
+    [u32; 2] => UVec2,
+    [u32; 3] => UVec3,
+    [u32; 4] => UVec4,
+    i32 => SInt,
+    [i32; 2] => SVec2,
+    [i32; 3] => SVec3,
+    [i32; 4] => SVec4,
+    [u8; 2] => UNormByte2,
+    [u8; 4] => UNormByte4,
+    [i8; 2] => SNormByte2,
+    [i8; 4] => SNormByte4,
+    [u16; 2] => UNormShort2,
+    [u16; 4] => UNormShort4,
+    [i16; 2] => SNormShort2,
+    [i16; 4] => SNormShort4,
+}
 
 impl From<AttributeFormat> for vk::Format {
     fn from(format: AttributeFormat) -> Self {
@@ -43,15 +87,27 @@ impl From<AttributeFormat> for vk::Format {
             AttributeFormat::Vec2 => Format::R32G32_SFLOAT,
             AttributeFormat::Vec3 => Format::R32G32B32_SFLOAT,
             AttributeFormat::Vec4 => Format::R32G32B32A32_SFLOAT,
+            AttributeFormat::UInt => Format::R32_UINT,
             AttributeFormat::UVec2 => Format::R32G32_UINT,
             AttributeFormat::UVec3 => Format::R32G32B32_UINT,
             AttributeFormat::UVec4 => Format::R32G32B32A32_UINT,
+            AttributeFormat::SInt => Format::R32_SINT,
             AttributeFormat::SVec2 => Format::R32G32_SINT,
             AttributeFormat::SVec3 => Format::R32G32B32_SINT,
             AttributeFormat::SVec4 => Format::R32G32B32A32_SINT,
             AttributeFormat::DVec2 => Format::R64G64_SFLOAT,
             AttributeFormat::DVec3 => Format::R64G64B64_SFLOAT,
             AttributeFormat::DVec4 => Format::R64G64B64A64_SFLOAT,
+            AttributeFormat::UNormByte2 => Format::R8G8_UNORM,
+            AttributeFormat::UNormByte4 => Format::R8G8B8A8_UNORM,
+            AttributeFormat::SNormByte2 => Format::R8G8_SNORM,
+            AttributeFormat::SNormByte4 => Format::R8G8B8A8_SNORM,
+            AttributeFormat::UNormShort2 => Format::R16G16_UNORM,
+            AttributeFormat::UNormShort4 => Format::R16G16B16A16_UNORM,
+            AttributeFormat::SNormShort2 => Format::R16G16_SNORM,
+            AttributeFormat::SNormShort4 => Format::R16G16B16A16_SNORM,
+            AttributeFormat::A2B10G10R10UNormPack32 => Format::A2B10G10R10_UNORM_PACK32,
+            AttributeFormat::B10G11R11UFloatPack32 => Format::B10G11R11_UFLOAT_PACK32,
         }
     }
 }
@@ -72,15 +128,15 @@ impl<'a> VertexBinding<'a> {
         offset: u32,
         format: AttributeFormat,
     ) -> &mut Self {
-        // binding
-        let builder = vk::VertexInputAttributeDescriptionBuilder::new();
-        builder.binding(self.binding.binding);
-        // The location in the shader
-        builder.location(location);
-        // type of attribute
-        builder.format(format.into());
-        // offset
-        builder.offset(offset);
+        let builder = vk::VertexInputAttributeDescriptionBuilder::new()
+            // binding
+            .binding(self.binding.binding)
+            // The location in the shader
+            .location(location)
+            // type of attribute
+            .format(format.into())
+            // offset
+            .offset(offset);
         self.attributes.push(*builder);
         self
     }
@@ -124,4 +180,81 @@ impl<'a> VertexInputSettings<'a> {
             &mut self.vertex_attributes,
         )
     }
+
+    /// Adds a binding with the stride and attributes of a Vertex type
+    pub fn add_vertex<V: Vertex>(
+        &mut self,
+        binding: u32,
+        input_rate: vk::VertexInputRate,
+    ) -> VertexBinding<'_> {
+        let mut vertex_binding = self.add_binding(binding, input_rate, V::stride());
+        for attribute in V::attributes() {
+            vertex_binding.add_attribute(attribute.location, attribute.offset, attribute.format);
+        }
+        vertex_binding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VertexAttribute;
+
+    #[derive(crate::Vertex)]
+    #[repr(C)]
+    struct TestVertex {
+        position: [f32; 3],
+        #[vertex(skip)]
+        _padding: f32,
+        normal: [i8; 4],
+        #[vertex(format = A2B10G10R10UNormPack32)]
+        tangent: u32,
+        weights: [f64; 3],
+        #[vertex(location = 8)]
+        layer: u32,
+    }
+
+    #[test]
+    fn test_derive_vertex() {
+        assert_eq!(TestVertex::stride(), 56);
+        assert_eq!(
+            TestVertex::attributes(),
+            vec![
+                VertexAttribute {
+                    location: 0,
+                    format: AttributeFormat::Vec3,
+                    offset: 0,
+                },
+                VertexAttribute {
+                    location: 1,
+                    format: AttributeFormat::SNormByte4,
+                    offset: 16,
+                },
+                VertexAttribute {
+                    location: 2,
+                    format: AttributeFormat::A2B10G10R10UNormPack32,
+                    offset: 20,
+                },
+                // Three doubles use two locations
+                VertexAttribute {
+                    location: 3,
+                    format: AttributeFormat::DVec3,
+                    offset: 24,
+                },
+                VertexAttribute {
+                    location: 8,
+                    format: AttributeFormat::UInt,
+                    offset: 48,
+                },
+            ]
+        );
+
+        let mut bindings = Vec::new();
+        let mut attributes = Vec::new();
+        VertexInputSettings::new(&mut bindings, &mut attributes)
+            .add_vertex::<TestVertex>(0, vk::VertexInputRate::VERTEX);
+        assert_eq!(bindings[0].stride, 56);
+        assert_eq!(attributes.len(), 5);
+        assert_eq!(attributes[3].format, vk::Format::R64G64B64_SFLOAT);
+    }
 }
@@ -1,37 +1,62 @@
 use super::traits::ConfigureViewport;
-use super::{traits::ConfigureRasterizer, ConfigurePipeline, Viewport, ViewportManager};
+use super::{traits::ConfigureRasterizer, ConfigurePipeline, ViewportManager};
 
 use erupt::vk1_0 as vk;
 
+// It's possible to create multiple viewports but its locked behind a gpu feature
+fn viewport_limit(features: &vk::PhysicalDeviceFeatures, limits: &vk::PhysicalDeviceLimits) -> u32 {
+    if features.multi_viewport != 0 {
+        limits.max_viewports
+    } else {
+        1
+    }
+}
+
 impl<'a> ConfigureViewport for ConfigurePipeline<'a> {
     fn configure_viewport(
         &mut self,
         create_viewport: &mut dyn FnMut(&mut ViewportManager),
     ) -> &mut dyn ConfigureRasterizer {
-        // It's possible to create multiple viewports but its locked behind a gpu feature,
-        // Each viewport has a scissor associated with it
-        let mut mng = ViewportManager::new();
+        let mut mng = ViewportManager::new(viewport_limit(&self.enabled_features, &self.limits));
         create_viewport(&mut mng);
-
-        // VkPipelineViewportStateCreateInfo viewportState{};
-        // viewportState.sType = VK_STRUCTURE_TYPE_PIPELINE_VIEWPORT_STATE_CREATE_INFO;
-        // viewportState.viewportCount = 1;
-        // viewportState.pViewports = &viewport;
-        // viewportState.scissorCount = 1;
-        // viewportState.pScissors = &scissor;
-        // TODO: Verify a viewport was created ie if len is 0 then leave it as None?
+        self.dynamic_viewports = mng.dynamic_count.is_some();
+        if let Some(count) = mng.dynamic_count {
+            // Only the number of viewports and scissors is used when they are dynamic
+            mng.viewports = vec![vk::Viewport::default(); count as usize];
+            mng.scissors = vec![vk::Rect2D::default(); count as usize];
+        }
         self.viewports_to_create = Some(mng.viewports);
+        self.scissors_to_create = mng.scissors;
         self
     }
 }
 
 impl ViewportManager {
-    pub fn new() -> ViewportManager {
+    pub fn new(max_viewports: u32) -> ViewportManager {
         ViewportManager {
             viewports: Vec::new(),
+            scissors: Vec::new(),
+            max_viewports,
+            dynamic_count: None,
         }
     }
-    pub fn create_viewport(
+
+    /// The number of viewports and scissors the device allows, this is 1 unless Features::MultiViewport is enabled.
+    /// Creating more fails when the pipeline is created
+    pub fn max_viewports(&self) -> u32 {
+        self.max_viewports
+    }
+
+    /// Sets the viewports and scissors while recording instead of when the pipeline is created so the pipeline
+    /// doesn't need to be recreated when the window is resized, count is the number of each and replaces any
+    /// that were added. A CommandRecorder with a render extent sets them to cover it when the pipeline is bound
+    pub fn dynamic(&mut self, count: u32) -> &mut Self {
+        self.dynamic_count = Some(count);
+        self
+    }
+
+    /// Adds a viewport, viewports are numbered in the order they are added
+    pub fn add_viewport(
         &mut self,
         x: f32,
         y: f32,
@@ -39,60 +64,53 @@ impl ViewportManager {
         height: f32,
         min_depth: f32,
         max_depth: f32,
-        scissor_x: i32,
-        scissor_y: i32,
-        scissor_width: u32,
-        scissor_height: u32,
-    ) {
-        // TODO: Check that mutiple viewports are supported before allowing their creation
-        // Width and Height are limited to the max values of a framebuffer
-        let viewport = Viewport::new(
-            x,
-            y,
-            width,
-            height,
-            min_depth,
-            max_depth,
-            scissor_x,
-            scissor_y,
-            scissor_width,
-            scissor_height,
-        );
+    ) -> &mut Self {
+        let viewport = vk::ViewportBuilder::new()
+            .x(x)
+            .y(y)
+            .width(width)
+            .height(height)
+            .min_depth(min_depth)
+            .max_depth(max_depth)
+            .build();
         self.viewports.push(viewport);
+        self
+    }
+
+    /// Adds a scissor that clips the viewport with the same index
+    pub fn add_scissor(&mut self, x: i32, y: i32, width: u32, height: u32) -> &mut Self {
+        let scissor = vk::Rect2D {
+            offset: vk::Offset2D { x, y },
+            extent: vk::Extent2D { width, height },
+        };
+        self.scissors.push(scissor);
+        self
     }
 }
 
-impl Viewport {
-    pub fn new(
-        x: f32,
-        y: f32,
-        width: f32,
-        height: f32,
-        min_depth: f32,
-        max_depth: f32,
-        scissor_x: i32,
-        scissor_y: i32,
-        scissor_width: u32,
-        scissor_height: u32,
-    ) -> Viewport {
-        Viewport {
-            viewport: vk::ViewportBuilder::new()
-                .height(height)
-                .width(width)
-                .x(x)
-                .y(y)
-                .min_depth(min_depth)
-                .max_depth(max_depth)
-                .build(),
-            scissor: vk::Rect2DBuilder::new()
-                .extent(
-                    vk::Extent2DBuilder::new()
-                        .height(scissor_height)
-                        .width(scissor_width)
-                        .build(),
-                )
-                .offset(vk::Offset2DBuilder::new().x(scissor_x).y(scissor_y).build())
-                .build(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Feature, Features};
+
+    #[test]
+    fn test_side_by_side_viewports() {
+        let limits = vk::PhysicalDeviceLimits {
+            max_viewports: 16,
+            ..Default::default()
+        };
+        let mut enabled = vk::PhysicalDeviceFeatures::default();
+        assert_eq!(viewport_limit(&enabled, &limits), 1);
+        let multi_viewport = Features::MultiViewport.device_feature(&mut enabled);
+        Feature::new(true, multi_viewport.unwrap()).enable();
+        let mut mng = ViewportManager::new(viewport_limit(&enabled, &limits));
+        assert_eq!(mng.max_viewports(), 16);
+        // Two images compared side by side
+        mng.add_viewport(0.0, 0.0, 400.0, 600.0, 0.0, 1.0)
+            .add_scissor(0, 0, 400, 600)
+            .add_viewport(400.0, 0.0, 400.0, 600.0, 0.0, 1.0)
+            .add_scissor(400, 0, 400, 600);
+        assert_eq!(mng.viewports.len(), 2);
+        assert_eq!(mng.scissors[1].offset.x, 400);
     }
 }
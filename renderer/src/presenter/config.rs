@@ -1,12 +1,14 @@
+use super::presenter::clamp_image_count;
 use super::{
     ConfigurePresenter, PresentMode, Presenter, SurfaceColourSpace, SurfaceFormat, SwapchainExtent,
     SwapchainImageCount,
 };
 use crate::{
     error::{Error, ErrorKind},
-    PickManager,
+    DeviceExtensions, OperationQueue, PickManager, VulkanDevice,
 };
 use erupt::extensions::khr_surface as surface;
+use erupt::extensions::khr_swapchain as swapchain;
 use erupt::vk1_0 as vk;
 
 impl ConfigurePresenter {
@@ -25,6 +27,7 @@ impl ConfigurePresenter {
             image_count: None,
             surface_format: None,
             present_mode: None,
+            view_components: vk::ComponentMapping::default(),
         }
     }
 
@@ -111,7 +114,7 @@ impl ConfigurePresenter {
             );
             // if extent is 0,0 then window is minimized or hidden, basically it's surface is currently unavailable
             custom_extent(&mut swapchain_extent);
-        // TODO: Set extent to the returned extent
+            self.swapchain_extent = swapchain_extent.extent();
         } else {
             // The size of the window defines the resolution of the swapchain
             self.swapchain_extent = Some(self.surface_capabilities.current_extent);
@@ -133,21 +136,16 @@ impl ConfigurePresenter {
         self
     }
 
-    /// Select the ImageViews that are used in conjunction with the swapchain
-    pub fn select_presentation_view_type<F: Fn(&mut u32) -> ()>(
+    /// Select the swizzle of the views of the swapchain images, the components are not swizzled by default
+    pub fn select_presentation_view_type<F: Fn(&mut vk::ComponentMapping) -> ()>(
         mut self,
         select_view_settings: F,
     ) -> Self {
-        // TODO: Do the presentation image views ever really change
-        // NOTE: This is configuring the image types for presentation only
-        // let mut settings = ImageViewSettings::new();
-        // select_view_settings(&mut settings);
-        // This should be a simple VkImageViewCreateInfo
-        // self.presentation_image_view_settings = Some(settings);
+        select_view_settings(&mut self.view_components);
         self
     }
 
-    // Ensure optional values have a value
+    // Ensure optional values have a value, the error names the select step that wasn't called
     fn validate_swapchain(
         &self,
     ) -> Result<
@@ -159,26 +157,143 @@ impl ConfigurePresenter {
         ),
         Error,
     > {
+        let missing = |step| Error::new(ErrorKind::SwapchainConfigurationMissing(step), None);
         let image_count = self
             .image_count
-            .ok_or(Error::new(ErrorKind::SwapchainConfigurationMissing, None))?;
+            .filter(|count| *count != 0)
+            .ok_or_else(|| missing("select_presentation_image_count"))?;
         let present_mode = self
             .present_mode
-            .ok_or(Error::new(ErrorKind::SwapchainConfigurationMissing, None))?;
+            .ok_or_else(|| missing("select_present_mode"))?;
         let surface_format = self
             .surface_format
-            .ok_or(Error::new(ErrorKind::SwapchainConfigurationMissing, None))?;
+            .filter(|format| format.format != vk::Format::UNDEFINED)
+            .ok_or_else(|| missing("select_surface_format"))?;
         let extent = self
             .swapchain_extent
-            .ok_or(Error::new(ErrorKind::SwapchainConfigurationMissing, None))?;
+            .ok_or_else(|| missing("select_extent"))?;
+        let image_count = clamp_image_count(image_count, &self.surface_capabilities);
         Ok((image_count, surface_format, extent, present_mode))
     }
 
-    fn validate_presentation_image_views(&self) -> Result<(), Error> {
-        Ok(())
+    /// Creates the swapchain and a view of each of its images, the device must have loaded the swapchain extension
+    /// and the queue must be able to present to the surface. Images are shared between the present queue and the
+    /// graphics queue when they are in different families
+    pub fn create_presenter(
+        self,
+        device: &VulkanDevice,
+        present_queue: &OperationQueue,
+    ) -> Result<Presenter, Error> {
+        let (image_count, surface_format, extent, present_mode) = self.validate_swapchain()?;
+        if device.extensions_loaded.get(&DeviceExtensions::Swapchain) != Some(&true) {
+            return Err(Error::new(
+                ErrorKind::DeviceExtensionsNotFound(vec![DeviceExtensions::Swapchain]),
+                None,
+            ));
+        }
+        if !present_queue.can_present() {
+            return Err(Error::new(ErrorKind::NoDevicesCanPresent, None)
+                .with_context(&"The queue given to the presenter can't present"));
+        }
+        let present_family = present_queue.family_index();
+        let mut families = vec![present_family];
+        if let Some(graphics) = device.queues().graphics_queues().first() {
+            if graphics.family_index() != present_family {
+                families.push(graphics.family_index());
+            }
+        }
+        let mut presenter = Presenter {
+            surface: self.surface,
+            surface_format,
+            extent,
+            present_mode,
+            swapchain: swapchain::SwapchainKHR::null(),
+            images: Vec::new(),
+            views: Vec::new(),
+            present_queue: device.get_queue(present_queue),
+            image_count,
+            view_components: self.view_components,
+            queue_families: families,
+            generation: 0,
+        };
+        if let Err(error) = presenter.create_swapchain(device, &self.surface_capabilities) {
+            presenter.destroy_swapchain(device);
+            return Err(error);
+        }
+        Ok(presenter)
     }
+}
 
-    pub fn create_presenter(self) -> Presenter {
-        Presenter::from(self)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configure() -> ConfigurePresenter {
+        let capabilities = surface::SurfaceCapabilitiesKHR {
+            min_image_count: 2,
+            max_image_count: 3,
+            current_extent: vk::Extent2D {
+                width: 800,
+                height: 600,
+            },
+            ..Default::default()
+        };
+        let formats = vec![surface::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_SRGB,
+            color_space: surface::ColorSpaceKHR::SRGB_NONLINEAR_KHR,
+        }];
+        let modes = vec![surface::PresentModeKHR::FIFO_KHR];
+        ConfigurePresenter::new(surface::SurfaceKHR::null(), capabilities, formats, modes)
+    }
+
+    fn missing_step(configure: &ConfigurePresenter) -> Option<ErrorKind> {
+        configure
+            .validate_swapchain()
+            .err()
+            .map(|error| error.kind().clone())
+    }
+
+    #[test]
+    fn test_validate_swapchain() {
+        let configure = configure();
+        assert_eq!(
+            missing_step(&configure),
+            Some(ErrorKind::SwapchainConfigurationMissing(
+                "select_presentation_image_count"
+            ))
+        );
+        let configure = configure.select_presentation_image_count(|count| {
+            count.set_image_count(count.max_images() + 1);
+        });
+        assert_eq!(
+            missing_step(&configure),
+            Some(ErrorKind::SwapchainConfigurationMissing(
+                "select_present_mode"
+            ))
+        );
+        let configure = configure
+            .select_present_mode(|mng| {
+                mng.pick(PresentMode::Fifo);
+            })
+            .unwrap();
+        assert_eq!(
+            missing_step(&configure),
+            Some(ErrorKind::SwapchainConfigurationMissing(
+                "select_surface_format"
+            ))
+        );
+        let configure = configure.select_surface_format(|mng| {
+            mng.pick(SurfaceFormat::B8G8R8A8SRGB);
+        });
+        assert_eq!(
+            missing_step(&configure),
+            Some(ErrorKind::SwapchainConfigurationMissing("select_extent"))
+        );
+        let configure = configure.select_extent(|_| {});
+        let (image_count, format, extent, _) = configure.validate_swapchain().unwrap();
+        // The image count is capped to the max image count of the surface
+        assert_eq!(image_count, 3);
+        assert_eq!(format.format, vk::Format::B8G8R8A8_SRGB);
+        assert_eq!((extent.width, extent.height), (800, 600));
     }
 }
@@ -2,7 +2,9 @@ mod config;
 mod presenter;
 mod types;
 
+use crate::ImageView;
 use erupt::extensions::khr_surface as surface;
+use erupt::extensions::khr_swapchain as swapchain;
 use erupt::vk1_0 as vk;
 use winit::window::Window;
 
@@ -37,10 +39,8 @@ pub struct ConfigurePresenter {
     present_mode: Option<surface::PresentModeKHR>,
     swapchain_extent: Option<vk::Extent2D>,
     image_count: Option<u32>,
-    // This needs to accept a bunch of borrows of surface capabilities since the are obtained during device creation
-    // Create the swapchain
-    // Create the ImageViews
-    // Create the Framebuffers
+    // The swizzle of the views of the swapchain images
+    view_components: vk::ComponentMapping,
 }
 
 /// Responsible for maintaining the swapchain and its backing imageviews and framebuffers,
@@ -49,4 +49,17 @@ pub struct Presenter {
     surface: surface::SurfaceKHR,
     surface_format: surface::SurfaceFormatKHR,
     extent: vk::Extent2D,
+    present_mode: surface::PresentModeKHR,
+    swapchain: swapchain::SwapchainKHR,
+    // The images are owned by the swapchain, only the views are destroyed with the presenter
+    images: Vec<vk::Image>,
+    views: Vec<ImageView>,
+    present_queue: vk::Queue,
+    // The selected image count, kept so a recreated swapchain uses the same count
+    image_count: u32,
+    view_components: vk::ComponentMapping,
+    // The families that use the swapchain images, images are shared when there is more than one
+    queue_families: Vec<u32>,
+    // Increases each time a swapchain is created
+    generation: u64,
 }
@@ -1,8 +1,182 @@
-use super::{ConfigurePresenter, Presenter};
+use super::Presenter;
+use crate::error::Error;
+use crate::{ImageView, VulkanApi, VulkanDevice};
 use erupt::extensions::khr_surface as surface;
+use erupt::extensions::khr_swapchain as swapchain;
 use erupt::vk1_0 as vk;
 
+// The image count limited to the counts the surface supports, a max image count of zero means there is no limit
+pub(super) fn clamp_image_count(
+    image_count: u32,
+    capabilities: &surface::SurfaceCapabilitiesKHR,
+) -> u32 {
+    let image_count = image_count.max(capabilities.min_image_count);
+    if capabilities.max_image_count != 0 {
+        image_count.min(capabilities.max_image_count)
+    } else {
+        image_count
+    }
+}
+
+// The first composite alpha mode the surface supports in order of preference
+fn composite_alpha(
+    capabilities: &surface::SurfaceCapabilitiesKHR,
+) -> surface::CompositeAlphaFlagBitsKHR {
+    [
+        surface::CompositeAlphaFlagBitsKHR::OPAQUE_KHR,
+        surface::CompositeAlphaFlagBitsKHR::PRE_MULTIPLIED_KHR,
+        surface::CompositeAlphaFlagBitsKHR::POST_MULTIPLIED_KHR,
+        surface::CompositeAlphaFlagBitsKHR::INHERIT_KHR,
+    ]
+    .iter()
+    .copied()
+    .find(|alpha| {
+        capabilities
+            .supported_composite_alpha
+            .contains(alpha.bitmask())
+    })
+    .unwrap_or(surface::CompositeAlphaFlagBitsKHR::OPAQUE_KHR)
+}
+
+// The extent of a recreated swapchain, the surface decides it unless its current extent is the special value
+// u32::MAX. A window without any area is left as it is so it isn't clamped to the minimum extent
+fn surface_extent(
+    capabilities: &surface::SurfaceCapabilitiesKHR,
+    extent: vk::Extent2D,
+) -> vk::Extent2D {
+    if capabilities.current_extent.width != u32::MAX {
+        return capabilities.current_extent;
+    }
+    if extent.width == 0 || extent.height == 0 {
+        return extent;
+    }
+    vk::Extent2D {
+        width: extent
+            .width
+            .max(capabilities.min_image_extent.width)
+            .min(capabilities.max_image_extent.width),
+        height: extent
+            .height
+            .max(capabilities.min_image_extent.height)
+            .min(capabilities.max_image_extent.height),
+    }
+}
+
 impl Presenter {
+    // Creates a swapchain that replaces the current one along with a view of each of its images. Rendered images
+    // can be copied to the swapchain when the surface allows it
+    pub(super) fn create_swapchain(
+        &mut self,
+        device: &VulkanDevice,
+        capabilities: &surface::SurfaceCapabilitiesKHR,
+    ) -> Result<(), Error> {
+        let sharing_mode = if self.queue_families.len() > 1 {
+            vk::SharingMode::CONCURRENT
+        } else {
+            vk::SharingMode::EXCLUSIVE
+        };
+        let mut usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+        if capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_DST)
+        {
+            usage |= vk::ImageUsageFlags::TRANSFER_DST;
+        }
+        let create_info = swapchain::SwapchainCreateInfoKHRBuilder::new()
+            .surface(self.surface)
+            .min_image_count(self.image_count)
+            .image_format(self.surface_format.format)
+            .image_color_space(self.surface_format.color_space)
+            .image_extent(self.extent)
+            .image_array_layers(1)
+            .image_usage(usage)
+            .image_sharing_mode(sharing_mode)
+            .queue_family_indices(self.queue_families.as_slice())
+            .pre_transform(capabilities.current_transform)
+            .composite_alpha(composite_alpha(capabilities))
+            .present_mode(self.present_mode)
+            .clipped(true)
+            .old_swapchain(self.swapchain);
+        let created = unsafe {
+            device
+                .handle()
+                .create_swapchain_khr(&create_info, None, None)
+        }
+        .result();
+        // The old swapchain is retired even when creating the new one fails
+        self.destroy_swapchain(device);
+        self.swapchain = created?;
+        self.generation += 1;
+        self.images = unsafe {
+            device
+                .handle()
+                .get_swapchain_images_khr(self.swapchain, None)
+        }
+        .result()?;
+        for image in self.images.iter().copied() {
+            let view = ImageView::for_swapchain_image(
+                device,
+                image,
+                self.surface_format.format,
+                self.extent,
+                self.view_components,
+            )?;
+            self.views.push(view);
+        }
+        Ok(())
+    }
+
+    // Destroys the image views and the swapchain leaving the surface
+    pub(super) fn destroy_swapchain(&mut self, device: &VulkanDevice) {
+        for view in self.views.drain(..) {
+            view.destroy(device);
+        }
+        self.images.clear();
+        if !self.swapchain.is_null() {
+            unsafe {
+                device
+                    .handle()
+                    .destroy_swapchain_khr(Some(self.swapchain), None)
+            };
+            self.swapchain = swapchain::SwapchainKHR::null();
+        }
+    }
+
+    /// Recreates the swapchain and its views for the current size of the surface, call when the window is resized
+    /// or presenting returns ERROR_OUT_OF_DATE_KHR. The extent is only used when the surface lets the swapchain pick
+    /// its size. Returns false without recreating anything while the surface has no area, ie the window is minimized
+    pub fn recreate(
+        &mut self,
+        api: &VulkanApi,
+        device: &VulkanDevice,
+        extent: vk::Extent2D,
+    ) -> Result<bool, Error> {
+        let capabilities = unsafe {
+            api.instance().get_physical_device_surface_capabilities_khr(
+                device.physical_device,
+                self.surface,
+                None,
+            )
+        }
+        .result()?;
+        let extent = surface_extent(&capabilities, extent);
+        if extent.width == 0 || extent.height == 0 {
+            return Ok(false);
+        }
+        // The old views may still be used by commands that are executing
+        unsafe { device.handle().device_wait_idle() }.result()?;
+        self.extent = extent;
+        self.image_count = clamp_image_count(self.image_count, &capabilities);
+        self.create_swapchain(device, &capabilities)?;
+        Ok(true)
+    }
+
+    /// Increases each time the swapchain is recreated, framebuffers and anything else created from the image views
+    /// are out of date when it changes
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     pub fn get_width(&self) -> u32 {
         self.extent.width
     }
@@ -11,6 +185,11 @@ impl Presenter {
         self.extent.height
     }
 
+    /// The extent of the swapchain images, used as the render extent of each frame
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
     pub fn get_format(&self) -> vk::Format {
         self.surface_format.format
     }
@@ -18,20 +197,86 @@ impl Presenter {
     pub fn get_color_space(&self) -> surface::ColorSpaceKHR {
         self.surface_format.color_space
     }
+
+    pub fn present_mode(&self) -> surface::PresentModeKHR {
+        self.present_mode
+    }
+
+    pub fn swapchain(&self) -> swapchain::SwapchainKHR {
+        self.swapchain
+    }
+
+    /// The queue images are presented on
+    pub fn present_queue(&self) -> vk::Queue {
+        self.present_queue
+    }
+
+    /// The number of images in the swapchain, which can be more than the image count that was selected
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn images(&self) -> &[vk::Image] {
+        self.images.as_slice()
+    }
+
+    /// The views of the swapchain images in the order of the image indices returned when acquiring an image
+    pub fn image_views(&self) -> &[ImageView] {
+        self.views.as_slice()
+    }
+
+    /// Destroys the image views, the swapchain and the surface, they must not be in use by the device. Call before
+    /// the instance is destroyed
+    pub fn destroy(mut self, api: &VulkanApi, device: &VulkanDevice) {
+        self.destroy_swapchain(device);
+        unsafe { api.instance().destroy_surface_khr(Some(self.surface), None) };
+    }
 }
 
-impl From<ConfigurePresenter> for Presenter {
-    fn from(configuration: ConfigurePresenter) -> Self {
-        let ConfigurePresenter {
-            surface,
-            surface_format,
-            swapchain_extent,
-            ..
-        } = configuration;
-        Presenter {
-            surface: surface,
-            surface_format: surface_format.unwrap(),
-            extent: swapchain_extent.unwrap(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities(current_width: u32) -> surface::SurfaceCapabilitiesKHR {
+        surface::SurfaceCapabilitiesKHR {
+            min_image_count: 2,
+            max_image_count: 0,
+            current_extent: vk::Extent2D {
+                width: current_width,
+                height: 600,
+            },
+            min_image_extent: vk::Extent2D {
+                width: 1,
+                height: 1,
+            },
+            max_image_extent: vk::Extent2D {
+                width: 1920,
+                height: 1080,
+            },
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn test_recreated_extent() {
+        let window = vk::Extent2D {
+            width: 2560,
+            height: 720,
+        };
+        // The surface decides the extent when it reports one
+        let extent = surface_extent(&capabilities(800), window);
+        assert_eq!((extent.width, extent.height), (800, 600));
+        // Otherwise the window size is clamped
+        let extent = surface_extent(&capabilities(u32::MAX), window);
+        assert_eq!((extent.width, extent.height), (1920, 720));
+        // A minimized window has no area
+        let minimized = vk::Extent2D {
+            width: 2560,
+            height: 0,
+        };
+        let extent = surface_extent(&capabilities(u32::MAX), minimized);
+        assert_eq!(extent.height, 0);
+        assert_eq!(clamp_image_count(1, &capabilities(800)), 2);
+        assert_eq!(clamp_image_count(8, &capabilities(800)), 8);
+    }
 }
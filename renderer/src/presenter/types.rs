@@ -89,6 +89,17 @@ impl SwapchainExtent {
         self.width = width;
         self.height = height;
     }
+
+    // The extent clamped to the extents the surface supports, none if no extent was set
+    pub(super) fn extent(&self) -> Option<vk::Extent2D> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        Some(vk::Extent2D {
+            width: self.width.max(self.min.width).min(self.max.width),
+            height: self.height.max(self.min.height).min(self.max.height),
+        })
+    }
 }
 
 pub struct SwapchainImageCount {
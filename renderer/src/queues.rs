@@ -151,6 +151,24 @@ pub struct RendererQueuesBuilder {
     compute_queues: Vec<OperationQueue>,
 }
 
+impl RendererQueues {
+    pub fn graphics_queues(&self) -> &[OperationQueue] {
+        self.graphics_queues.as_slice()
+    }
+
+    pub fn transfer_queues(&self) -> &[OperationQueue] {
+        self.transfer_queues.as_slice()
+    }
+
+    pub fn compute_queues(&self) -> &[OperationQueue] {
+        self.compute_queues.as_slice()
+    }
+
+    pub fn sparse_queues(&self) -> &[OperationQueue] {
+        self.sparse_queues.as_slice()
+    }
+}
+
 impl RendererQueuesBuilder {
     pub fn new() -> RendererQueuesBuilder {
         RendererQueuesBuilder {
@@ -245,6 +263,10 @@ impl OperationQueue {
         self.can_present
     }
 
+    pub fn family_index(&self) -> u32 {
+        self.family_index
+    }
+
     pub fn operations_supported(&self) -> vk::QueueFlags {
         self.operations_supported
     }
@@ -0,0 +1,171 @@
+use super::ffi::{
+    PipelineRenderingCreateInfo, RenderingAttachmentInfo, STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO,
+};
+use super::{RenderingAttachment, RenderingFormats};
+use crate::image::aspect_for_format;
+use crate::ImageView;
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+
+fn has_stencil(format: vk::Format) -> bool {
+    aspect_for_format(format).contains(vk::ImageAspectFlags::STENCIL)
+}
+
+impl RenderingAttachment {
+    /// Renders to the view, which is cleared and stored by default. Colour views must be in the colour
+    /// attachment layout and depth views in the depth attachment layout, depth is cleared to 1
+    pub fn new(view: &ImageView) -> RenderingAttachment {
+        let format = view.format();
+        let (layout, clear_value) = if aspect_for_format(format) == vk::ImageAspectFlags::COLOR {
+            (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 0.0],
+                    },
+                },
+            )
+        } else {
+            (
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: 1.0,
+                        stencil: 0,
+                    },
+                },
+            )
+        };
+        RenderingAttachment {
+            view: view.handle(),
+            format,
+            layout,
+            resolve: None,
+            resolve_mode: vk1_2::ResolveModeFlagBits::NONE,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            clear_value,
+        }
+    }
+
+    /// Resolves the multisampled view to a single sampled view in the colour attachment layout when rendering
+    /// ends, colour samples are averaged and depth uses the first sample
+    pub fn resolve(mut self, view: &ImageView) -> Self {
+        self.resolve_mode = if aspect_for_format(self.format) == vk::ImageAspectFlags::COLOR {
+            vk1_2::ResolveModeFlagBits::AVERAGE
+        } else {
+            vk1_2::ResolveModeFlagBits::SAMPLE_ZERO
+        };
+        self.resolve = Some((view.handle(), self.layout));
+        self
+    }
+
+    /// The layout the view is in while rendering
+    pub fn layout(mut self, layout: vk::ImageLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn load_op(mut self, load_op: vk::AttachmentLoadOp) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    pub fn store_op(mut self, store_op: vk::AttachmentStoreOp) -> Self {
+        self.store_op = store_op;
+        self
+    }
+
+    /// The value the view is cleared to when the load op is CLEAR
+    pub fn clear_value(mut self, clear_value: vk::ClearValue) -> Self {
+        self.clear_value = clear_value;
+        self
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    pub(super) fn has_stencil(&self) -> bool {
+        has_stencil(self.format)
+    }
+
+    pub(super) fn to_vulkan(self) -> RenderingAttachmentInfo {
+        let (resolve_image_view, resolve_image_layout) = self
+            .resolve
+            .unwrap_or((vk::ImageView::null(), vk::ImageLayout::UNDEFINED));
+        RenderingAttachmentInfo {
+            s_type: STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO,
+            p_next: std::ptr::null(),
+            image_view: self.view,
+            image_layout: self.layout,
+            resolve_mode: self.resolve_mode,
+            resolve_image_view,
+            resolve_image_layout,
+            load_op: self.load_op,
+            store_op: self.store_op,
+            clear_value: self.clear_value,
+        }
+    }
+}
+
+impl RenderingFormats {
+    /// The formats of the colour attachments in the order of the fragment shader outputs and the format of the
+    /// depth attachment if there is one, a depth format with a stencil aspect is also the stencil format
+    pub fn new(color_formats: &[vk::Format], depth_format: Option<vk::Format>) -> RenderingFormats {
+        let depth_format = depth_format.unwrap_or(vk::Format::UNDEFINED);
+        let stencil_format = if has_stencil(depth_format) {
+            depth_format
+        } else {
+            vk::Format::UNDEFINED
+        };
+        RenderingFormats {
+            color_formats: color_formats.to_vec(),
+            depth_format,
+            stencil_format,
+        }
+    }
+
+    pub fn color_formats(&self) -> &[vk::Format] {
+        self.color_formats.as_slice()
+    }
+
+    pub fn depth_format(&self) -> Option<vk::Format> {
+        match self.depth_format {
+            vk::Format::UNDEFINED => None,
+            format => Some(format),
+        }
+    }
+
+    // The create info borrows the colour formats
+    pub(crate) fn to_vulkan(&self) -> PipelineRenderingCreateInfo {
+        PipelineRenderingCreateInfo::new(
+            self.color_formats.as_slice(),
+            self.depth_format,
+            self.stencil_format,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendering_formats() {
+        let formats = RenderingFormats::new(
+            &[vk::Format::B8G8R8A8_SRGB],
+            Some(vk::Format::D24_UNORM_S8_UINT),
+        );
+        assert_eq!(formats.depth_format(), Some(vk::Format::D24_UNORM_S8_UINT));
+        assert_eq!(formats.stencil_format, vk::Format::D24_UNORM_S8_UINT);
+
+        let formats =
+            RenderingFormats::new(&[vk::Format::B8G8R8A8_SRGB], Some(vk::Format::D32_SFLOAT));
+        assert_eq!(formats.stencil_format, vk::Format::UNDEFINED);
+
+        let formats = RenderingFormats::new(&[vk::Format::B8G8R8A8_SRGB], None);
+        assert_eq!(formats.depth_format(), None);
+        assert_eq!(formats.color_formats(), &[vk::Format::B8G8R8A8_SRGB]);
+    }
+}
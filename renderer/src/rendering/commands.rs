@@ -0,0 +1,73 @@
+use super::ffi::{
+    PFN_vkCmdBeginRenderingKHR, PFN_vkCmdEndRenderingKHR, RenderingAttachmentInfo, RenderingInfo,
+    STRUCTURE_TYPE_RENDERING_INFO,
+};
+use super::{DynamicRendering, RenderingAttachment};
+use erupt::vk1_0 as vk;
+use std::ffi::CStr;
+
+impl DynamicRendering {
+    /// Loads the commands from a device created with VK_KHR_dynamic_rendering enabled
+    pub(crate) fn load(device: &erupt::DeviceLoader) -> Option<DynamicRendering> {
+        let load = |name: &[u8]| {
+            let name = CStr::from_bytes_with_nul(name).ok()?;
+            unsafe { device.get_device_proc_addr(Some(name)) }
+        };
+        let begin = load(b"vkCmdBeginRenderingKHR\0")?;
+        let end = load(b"vkCmdEndRenderingKHR\0")?;
+        // SAFE: The commands were loaded by name so they have the signatures of those commands
+        unsafe {
+            Some(DynamicRendering {
+                cmd_begin_rendering: std::mem::transmute::<
+                    vk::PFN_vkVoidFunction,
+                    PFN_vkCmdBeginRenderingKHR,
+                >(begin),
+                cmd_end_rendering: std::mem::transmute::<
+                    vk::PFN_vkVoidFunction,
+                    PFN_vkCmdEndRenderingKHR,
+                >(end),
+            })
+        }
+    }
+
+    pub(crate) fn begin_rendering(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        extent: vk::Extent2D,
+        color_attachments: &[RenderingAttachment],
+        depth_attachment: Option<&RenderingAttachment>,
+    ) {
+        let colors: Vec<RenderingAttachmentInfo> = color_attachments
+            .iter()
+            .map(|attachment| attachment.to_vulkan())
+            .collect();
+        let depth = depth_attachment.map(|attachment| attachment.to_vulkan());
+        let depth_ptr = depth
+            .as_ref()
+            .map_or(std::ptr::null(), |depth| depth as *const _);
+        let stencil_ptr = match depth_attachment {
+            Some(attachment) if attachment.has_stencil() => depth_ptr,
+            _ => std::ptr::null(),
+        };
+        let rendering_info = RenderingInfo {
+            s_type: STRUCTURE_TYPE_RENDERING_INFO,
+            p_next: std::ptr::null(),
+            flags: 0,
+            render_area: vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            },
+            layer_count: 1,
+            view_mask: 0,
+            color_attachment_count: colors.len() as u32,
+            p_color_attachments: colors.as_ptr(),
+            p_depth_attachment: depth_ptr,
+            p_stencil_attachment: stencil_ptr,
+        };
+        unsafe { (self.cmd_begin_rendering)(command_buffer, &rendering_info) };
+    }
+
+    pub(crate) fn end_rendering(&self, command_buffer: vk::CommandBuffer) {
+        unsafe { (self.cmd_end_rendering)(command_buffer) };
+    }
+}
@@ -0,0 +1,100 @@
+// The structures and commands of VK_KHR_dynamic_rendering, which was released after the Vulkan headers erupt
+// is generated from
+#![allow(non_camel_case_types)]
+
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+use std::ffi::c_void;
+
+pub(super) const STRUCTURE_TYPE_RENDERING_INFO: vk::StructureType = vk::StructureType(1000044000);
+pub(super) const STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO: vk::StructureType =
+    vk::StructureType(1000044001);
+pub(super) const STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO: vk::StructureType =
+    vk::StructureType(1000044002);
+pub(super) const STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES: vk::StructureType =
+    vk::StructureType(1000044003);
+
+pub(super) type PFN_vkCmdBeginRenderingKHR = unsafe extern "system" fn(
+    command_buffer: vk::CommandBuffer,
+    rendering_info: *const RenderingInfo,
+);
+pub(super) type PFN_vkCmdEndRenderingKHR =
+    unsafe extern "system" fn(command_buffer: vk::CommandBuffer);
+
+#[repr(C)]
+pub(super) struct RenderingAttachmentInfo {
+    pub(super) s_type: vk::StructureType,
+    pub(super) p_next: *const c_void,
+    pub(super) image_view: vk::ImageView,
+    pub(super) image_layout: vk::ImageLayout,
+    pub(super) resolve_mode: vk1_2::ResolveModeFlagBits,
+    pub(super) resolve_image_view: vk::ImageView,
+    pub(super) resolve_image_layout: vk::ImageLayout,
+    pub(super) load_op: vk::AttachmentLoadOp,
+    pub(super) store_op: vk::AttachmentStoreOp,
+    pub(super) clear_value: vk::ClearValue,
+}
+
+#[repr(C)]
+pub(super) struct RenderingInfo {
+    pub(super) s_type: vk::StructureType,
+    pub(super) p_next: *const c_void,
+    pub(super) flags: u32,
+    pub(super) render_area: vk::Rect2D,
+    pub(super) layer_count: u32,
+    pub(super) view_mask: u32,
+    pub(super) color_attachment_count: u32,
+    pub(super) p_color_attachments: *const RenderingAttachmentInfo,
+    pub(super) p_depth_attachment: *const RenderingAttachmentInfo,
+    pub(super) p_stencil_attachment: *const RenderingAttachmentInfo,
+}
+
+/// Chained to a graphics pipeline created without a renderpass
+#[repr(C)]
+pub(crate) struct PipelineRenderingCreateInfo {
+    s_type: vk::StructureType,
+    p_next: *const c_void,
+    view_mask: u32,
+    color_attachment_count: u32,
+    p_color_attachment_formats: *const vk::Format,
+    depth_attachment_format: vk::Format,
+    stencil_attachment_format: vk::Format,
+}
+
+/// Chained to the device create info to enable the dynamicRendering feature
+#[repr(C)]
+pub(crate) struct PhysicalDeviceDynamicRenderingFeatures {
+    s_type: vk::StructureType,
+    p_next: *mut c_void,
+    dynamic_rendering: vk::Bool32,
+}
+
+impl PipelineRenderingCreateInfo {
+    // The formats must outlive the create info
+    pub(super) fn new(
+        color_formats: &[vk::Format],
+        depth_format: vk::Format,
+        stencil_format: vk::Format,
+    ) -> PipelineRenderingCreateInfo {
+        PipelineRenderingCreateInfo {
+            s_type: STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO,
+            p_next: std::ptr::null(),
+            view_mask: 0,
+            color_attachment_count: color_formats.len() as u32,
+            p_color_attachment_formats: color_formats.as_ptr(),
+            depth_attachment_format: depth_format,
+            stencil_attachment_format: stencil_format,
+        }
+    }
+}
+
+impl PhysicalDeviceDynamicRenderingFeatures {
+    /// The feature enabled and chained in front of p_next
+    pub(crate) fn enabled(p_next: *const c_void) -> PhysicalDeviceDynamicRenderingFeatures {
+        PhysicalDeviceDynamicRenderingFeatures {
+            s_type: STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES,
+            p_next: p_next as *mut c_void,
+            dynamic_rendering: vk::TRUE,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+mod attachment;
+mod commands;
+mod ffi;
+
+use erupt::vk1_0 as vk;
+use erupt::vk1_2;
+
+pub(crate) use ffi::PhysicalDeviceDynamicRenderingFeatures;
+
+/// An image view rendered to between begin_rendering and end_rendering, the view must already be in the layout
+/// as dynamic rendering doesn't transition images
+#[derive(Clone, Copy)]
+pub struct RenderingAttachment {
+    view: vk::ImageView,
+    format: vk::Format,
+    layout: vk::ImageLayout,
+    // The single sampled view a multisampled attachment is resolved to along with its layout
+    resolve: Option<(vk::ImageView, vk::ImageLayout)>,
+    resolve_mode: vk1_2::ResolveModeFlagBits,
+    load_op: vk::AttachmentLoadOp,
+    store_op: vk::AttachmentStoreOp,
+    clear_value: vk::ClearValue,
+}
+
+/// The formats of the attachments a pipeline renders to when it is used with dynamic rendering instead of a renderpass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RenderingFormats {
+    color_formats: Vec<vk::Format>,
+    depth_format: vk::Format,
+    stencil_format: vk::Format,
+}
+
+// The commands of VK_KHR_dynamic_rendering, erupt doesn't load them so they are loaded when the device is created
+pub(crate) struct DynamicRendering {
+    cmd_begin_rendering: ffi::PFN_vkCmdBeginRenderingKHR,
+    cmd_end_rendering: ffi::PFN_vkCmdEndRenderingKHR,
+}
@@ -0,0 +1,173 @@
+use super::{Attachment, SubpassSettings};
+use crate::image::aspect_for_format;
+use erupt::vk1_0 as vk;
+
+impl Attachment {
+    /// An attachment that is cleared when the renderpass begins and stored when it ends, it is left in the
+    /// colour or depth attachment layout depending on the format
+    pub fn new(format: vk::Format) -> Attachment {
+        let final_layout = if aspect_for_format(format) == vk::ImageAspectFlags::COLOR {
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+        } else {
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        };
+        let description = vk::AttachmentDescriptionBuilder::new()
+            .format(format)
+            .samples(vk::SampleCountFlagBits::_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(final_layout);
+        Attachment {
+            description: *description,
+        }
+    }
+
+    /// A colour attachment that is presented after the renderpass, usually a swapchain image
+    pub fn presented(format: vk::Format) -> Attachment {
+        Attachment::new(format).final_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+    }
+
+    /// A depth attachment that is only needed while the renderpass runs so is not stored
+    pub fn depth(format: vk::Format) -> Attachment {
+        Attachment::new(format).store_op(vk::AttachmentStoreOp::DONT_CARE)
+    }
+
+    /// The number of samples, a multisampled colour attachment needs a single sampled attachment to resolve to
+    pub fn samples(mut self, samples: vk::SampleCountFlagBits) -> Self {
+        self.description.samples = samples;
+        self
+    }
+
+    /// What happens to the contents of the attachment when the renderpass begins, the default is to clear it
+    pub fn load_op(mut self, load_op: vk::AttachmentLoadOp) -> Self {
+        self.description.load_op = load_op;
+        self
+    }
+
+    /// What happens to the contents of the attachment when the renderpass ends, the default is to store it
+    pub fn store_op(mut self, store_op: vk::AttachmentStoreOp) -> Self {
+        self.description.store_op = store_op;
+        self
+    }
+
+    pub fn stencil_load_op(mut self, load_op: vk::AttachmentLoadOp) -> Self {
+        self.description.stencil_load_op = load_op;
+        self
+    }
+
+    pub fn stencil_store_op(mut self, store_op: vk::AttachmentStoreOp) -> Self {
+        self.description.stencil_store_op = store_op;
+        self
+    }
+
+    /// The layout of the image when the renderpass begins, must be set when the contents are loaded
+    pub fn initial_layout(mut self, layout: vk::ImageLayout) -> Self {
+        self.description.initial_layout = layout;
+        self
+    }
+
+    /// The layout the image is transitioned to when the renderpass ends
+    pub fn final_layout(mut self, layout: vk::ImageLayout) -> Self {
+        self.description.final_layout = layout;
+        self
+    }
+
+    /// Set when the attachment shares memory with another attachment of the renderpass
+    pub fn may_alias(mut self, may_alias: bool) -> Self {
+        self.description.flags = if may_alias {
+            vk::AttachmentDescriptionFlags::MAY_ALIAS
+        } else {
+            vk::AttachmentDescriptionFlags::empty()
+        };
+        self
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.description.format
+    }
+
+    pub(super) fn description(&self) -> vk::AttachmentDescription {
+        self.description
+    }
+}
+
+impl SubpassSettings {
+    pub fn new() -> SubpassSettings {
+        SubpassSettings::default()
+    }
+
+    /// Adds a colour attachment the fragment shader writes to, the location of each output is the order
+    /// the colour attachments are added
+    pub fn color(&mut self, attachment: u32) -> &mut Self {
+        self.color_attachments.push(attachment);
+        if !self.resolve_attachments.is_empty() {
+            self.resolve_attachments.push(None);
+        }
+        self
+    }
+
+    /// Adds a multisampled colour attachment that is resolved to a single sampled attachment at the end of the subpass
+    pub fn resolved_color(&mut self, attachment: u32, resolve_attachment: u32) -> &mut Self {
+        if self.resolve_attachments.is_empty() {
+            self.resolve_attachments = vec![None; self.color_attachments.len()];
+        }
+        self.color_attachments.push(attachment);
+        self.resolve_attachments.push(Some(resolve_attachment));
+        self
+    }
+
+    /// Sets the depth and stencil attachment, replacing any that was set
+    pub fn depth_stencil(&mut self, attachment: u32) -> &mut Self {
+        self.depth_stencil_attachment = Some(attachment);
+        self
+    }
+
+    /// Adds an attachment written by an earlier subpass that the fragment shader reads as an input attachment,
+    /// the input attachment index is the order they are added
+    pub fn input(&mut self, attachment: u32) -> &mut Self {
+        self.input_attachments.push(attachment);
+        self
+    }
+
+    /// Keeps the contents of an attachment this subpass doesn't use for a later subpass that does
+    pub fn preserve(&mut self, attachment: u32) -> &mut Self {
+        self.preserve_attachments.push(attachment);
+        self
+    }
+
+    pub fn color_attachments(&self) -> &[u32] {
+        self.color_attachments.as_slice()
+    }
+
+    pub fn depth_stencil_attachment(&self) -> Option<u32> {
+        self.depth_stencil_attachment
+    }
+
+    pub fn input_attachments(&self) -> &[u32] {
+        self.input_attachments.as_slice()
+    }
+
+    /// Every attachment the subpass reads or writes, preserved attachments are not included
+    pub(super) fn used_attachments(&self) -> impl Iterator<Item = u32> + '_ {
+        self.color_attachments
+            .iter()
+            .chain(self.resolve_attachments.iter().flatten())
+            .chain(self.depth_stencil_attachment.iter())
+            .chain(self.input_attachments.iter())
+            .copied()
+    }
+
+    // An attachment that is both read as an input and written must use the general layout
+    pub(super) fn layout_of(&self, attachment: u32, layout: vk::ImageLayout) -> vk::ImageLayout {
+        let written = self.color_attachments.contains(&attachment)
+            || self.depth_stencil_attachment == Some(attachment);
+        if written && self.input_attachments.contains(&attachment) {
+            vk::ImageLayout::GENERAL
+        } else {
+            layout
+        }
+    }
+}
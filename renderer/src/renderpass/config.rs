@@ -1,162 +1,271 @@
+use super::dependency::infer_dependencies;
+use super::{Attachment, ConfigureRenderpass, Renderpass, SubpassSettings};
+use crate::error::{Error, ErrorKind};
+use crate::image::aspect_for_format;
+use crate::VulkanDevice;
 use erupt::vk1_0 as vk;
-use erupt::DeviceLoader;
-pub struct Renderpass<'a, 'b> {
-    device: &'a DeviceLoader,
-    attachments: Vec<&'b Attachment>,
-    render_pass: Option<vk::RenderPass>,
-}
-
-impl<'a, 'b> Renderpass<'a, 'b> {
-    // TODO: Subpass instead of attachment although each attachment is used in a subpass
-    // pub fn add_subpass(mut self, format: vk::Format) -> Self {
-    //     // TODO: Each subpass can have multiple attachments
-    //     // TODO: Multiple subpasses can reference the same attachments as inputs or outputs
-    //     // TODO: So we build a list of render passes and then reference the attachments as needed
-    //     let subpass_builder = vk::SubpassDescriptionBuilder::new()
-    //         .color_attachments(color_attachments)
-    //         Attachments used for multisampling color attachments
-    //         .resolve_attachments(resolve_attachments)
-    //         Attachments that are not used by this subpass, but for which the data must be preserved
-    //         .preserve_attachments(preserve_attachments)
-    //         .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-    //         Attachments that are read from a shader
-    //         .input_attachments(input_attachments)
-    //         Attachment for depth and stencil data
-    //         .depth_stencil_attachment(depth_stencil_attachment)
-    //         .flags(vk::SubpassDescriptionFlags::SHADER_RESOLVE_QCOM);
-    //     let reference_builder = vk::AttachmentReferenceBuilder::new()
-    //         .attachment(0)
-    //         .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
-    //     self
-    // }
 
-    // pub fn create_attachment(mut self) -> Self {
-    //     let attach_builder = vk::AttachmentDescriptionBuilder::new()
-    //         .format(format)
-    //         .store_op(vk::AttachmentStoreOp::STORE)
-    //         .samples(vk::SampleCountFlagBits::_1)
-    //         .flags(vk::AttachmentDescriptionFlags::MAY_ALIAS)
-    //         .load_op(vk::AttachmentLoadOp::DONT_CARE)
-    //         .stencil_load_op(stencil_load_op)
-    //         .stencil_store_op(stencil_store_op)
-    //         .final_layout(final_layout);
-    //     self
-    // }
+use std::sync::atomic::{AtomicU64, Ordering};
 
-    pub fn create_test_renderpass(&mut self, swapchain_format: vk::Format) {
-        // VkAttachmentDescription colorAttachment{};
-        // colorAttachment.format = swapChainImageFormat;
-        // colorAttachment.samples = VK_SAMPLE_COUNT_1_BIT;
-        // colorAttachment.loadOp = VK_ATTACHMENT_LOAD_OP_CLEAR;
-        // colorAttachment.storeOp = VK_ATTACHMENT_STORE_OP_STORE;
-        // colorAttachment.stencilLoadOp = VK_ATTACHMENT_LOAD_OP_DONT_CARE;
-        // colorAttachment.stencilStoreOp = VK_ATTACHMENT_STORE_OP_DONT_CARE;
-        // colorAttachment.initialLayout = VK_IMAGE_LAYOUT_UNDEFINED;
-        // colorAttachment.finalLayout = VK_IMAGE_LAYOUT_PRESENT_SRC_KHR;
-        let color_attachment = vk::AttachmentDescriptionBuilder::new()
-            .format(swapchain_format)
-            .samples(vk::SampleCountFlagBits::_1)
-            .load_op(vk::AttachmentLoadOp::LOAD)
-            .store_op(vk::AttachmentStoreOp::STORE)
-            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
-            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
-            .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+// Handed out to each renderpass as it's created so pipelines can tell recreated renderpasses apart
+static NEXT_RENDERPASS_ID: AtomicU64 = AtomicU64::new(0);
 
-        // VkAttachmentReference colorAttachmentRef{};
-        // colorAttachmentRef.attachment = 0;
-        // colorAttachmentRef.layout = VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL;
-        let reference = vk::AttachmentReferenceBuilder::new()
-            .attachment(0)
-            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
-        // VkSubpassDescription subpass{};
-        // subpass.pipelineBindPoint = VK_PIPELINE_BIND_POINT_GRAPHICS;
-        // subpass.colorAttachmentCount = 1;
-        // subpass.pColorAttachments = &colorAttachmentRef;
-        let subpass = vk::SubpassDescriptionBuilder::new()
-            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-            .color_attachments(std::slice::from_ref(&reference));
-        // VkRenderPassCreateInfo renderPassInfo{};
-        // renderPassInfo.sType = VK_STRUCTURE_TYPE_RENDER_PASS_CREATE_INFO;
-        // renderPassInfo.attachmentCount = 1;
-        // renderPassInfo.pAttachments = &colorAttachment;
-        // renderPassInfo.subpassCount = 1;
-        // renderPassInfo.pSubpasses = &subpass;
-        let sexy = std::slice::from_ref(&color_attachment);
-        let renderpass_create_info = vk::RenderPassCreateInfoBuilder::new()
-            .attachments(sexy)
-            .subpasses(std::slice::from_ref(&subpass));
+fn invalid_config(context: &'static &'static str) -> Error {
+    Error::new(ErrorKind::InvalidRenderpassConfig, None).with_context(context)
+}
 
-        // if (vkCreateRenderPass(device, &renderPassInfo, nullptr, &renderPass) != VK_SUCCESS) {
-        //     throw std::runtime_error("failed to create render pass!");
-        // }
-        let rp = unsafe {
-            self.device
-                .create_render_pass(&renderpass_create_info, None, None)
-        }
-        .expect("Failed to create test render pass");
-        self.render_pass = Some(rp);
+fn check(failed: bool, context: &'static &'static str) -> Result<(), Error> {
+    if failed {
+        Err(invalid_config(context))
+    } else {
+        Ok(())
     }
+}
 
-    pub fn add_attachment_input(&mut self, attachment: &Attachment) {}
-
-    pub fn add_colour_output(&mut self, attachment: &Attachment) {}
-
-    pub fn add_presentation_output(&mut self, attachment: &Attachment) {}
-
-    pub fn new(device: &DeviceLoader) -> Renderpass {
-        Renderpass {
-            attachments: Vec::new(),
+impl<'a> ConfigureRenderpass<'a> {
+    pub fn new(device: &'a VulkanDevice) -> ConfigureRenderpass<'a> {
+        ConfigureRenderpass {
             device,
-            render_pass: None,
+            attachments: Vec::new(),
+            subpasses: Vec::new(),
+            dependencies: Vec::new(),
         }
     }
 
-    // pub fn create_renderpass(mut self) -> Self {
-    //     // TODO: We pass in an object that allows the creation of attachments and subpasses after initial config?
-    //     // TODO: How to pass this info to the render graph - ie v2, create the render graph as part of the sub pass/ attachment configuration
-
-    //     // create attachments
-
-    //     // create sub passes using attachments
-
-    //     // build render graph
+    /// Adds an attachment, subpasses refer to attachments by the order they are added starting at 0
+    pub fn add_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment.description());
+        self
+    }
 
-    //     // Can render passes use dynamic attachments?
+    /// Adds a subpass, subpasses run in the order they are added and are numbered starting at 0
+    pub fn add_subpass<F>(mut self, configure_subpass: F) -> Self
+    where
+        F: FnOnce(&mut SubpassSettings),
+    {
+        let mut subpass = SubpassSettings::new();
+        configure_subpass(&mut subpass);
+        self.subpasses.push(subpass);
+        self
+    }
 
-    //     // return renderpass object
-    //     self
-    // }
-}
+    /// Adds a dependency between two subpasses or between a subpass and vk::SUBPASS_EXTERNAL, when no
+    /// dependencies are added they are inferred from how the subpasses use the attachments
+    pub fn add_dependency(mut self, dependency: vk::SubpassDependencyBuilder) -> Self {
+        self.dependencies.push(*dependency);
+        self
+    }
 
-pub struct Attachment {
-    attachment: vk::AttachmentDescription,
-}
+    // Checks the attachment references of each subpass and the subpasses of each dependency
+    fn validate(&self) -> Result<(), Error> {
+        check(
+            self.subpasses.is_empty(),
+            &"A renderpass needs at least one subpass",
+        )?;
+        let attachment_count = self.attachments.len() as u32;
+        let max_color_attachments = self.device.limits().max_color_attachments as usize;
+        for subpass in self.subpasses.iter() {
+            check(
+                subpass
+                    .used_attachments()
+                    .chain(subpass.preserve_attachments.iter().copied())
+                    .any(|attachment| attachment >= attachment_count),
+                &"A subpass refers to an attachment that was not added",
+            )?;
+            check(
+                subpass.color_attachments.len() > max_color_attachments,
+                &"A subpass has more colour attachments than the maxColorAttachments limit",
+            )?;
+            let attachment = |index: u32| &self.attachments[index as usize];
+            check(
+                subpass
+                    .color_attachments
+                    .iter()
+                    .chain(subpass.resolve_attachments.iter().flatten())
+                    .any(|a| {
+                        aspect_for_format(attachment(*a).format) != vk::ImageAspectFlags::COLOR
+                    }),
+                &"A colour or resolve attachment must have a colour format",
+            )?;
+            check(
+                subpass.depth_stencil_attachment.is_some_and(|a| {
+                    aspect_for_format(attachment(a).format) == vk::ImageAspectFlags::COLOR
+                }),
+                &"The depth attachment of a subpass must have a depth or stencil format",
+            )?;
+            let mut samples = subpass
+                .color_attachments
+                .iter()
+                .chain(subpass.depth_stencil_attachment.iter())
+                .map(|a| attachment(*a).samples);
+            if let Some(first) = samples.next() {
+                check(
+                    samples.any(|count| count != first),
+                    &"The colour and depth attachments of a subpass must have the same sample count",
+                )?;
+            }
+            check(
+                subpass
+                    .color_attachments
+                    .iter()
+                    .zip(subpass.resolve_attachments.iter())
+                    .any(|(color, resolve)| match resolve {
+                        Some(resolve) => {
+                            attachment(*color).samples == vk::SampleCountFlagBits::_1
+                                || attachment(*resolve).samples != vk::SampleCountFlagBits::_1
+                        }
+                        None => false,
+                    }),
+                &"A resolve attachment must be single sampled and resolve a multisampled colour attachment",
+            )?;
+            check(
+                subpass
+                    .used_attachments()
+                    .any(|a| subpass.preserve_attachments.contains(&a)),
+                &"A subpass can't preserve an attachment it uses",
+            )?;
+        }
+        let subpass_count = self.subpasses.len() as u32;
+        let valid = |subpass: u32| subpass == vk::SUBPASS_EXTERNAL || subpass < subpass_count;
+        check(
+            self.dependencies
+                .iter()
+                .any(|d| !valid(d.src_subpass) || !valid(d.dst_subpass)),
+            &"A dependency refers to a subpass that was not added",
+        )?;
+        check(
+            self.dependencies.iter().any(|d| {
+                d.src_subpass != vk::SUBPASS_EXTERNAL
+                    && d.dst_subpass != vk::SUBPASS_EXTERNAL
+                    && d.src_subpass > d.dst_subpass
+            }),
+            &"A subpass can only depend on itself or subpasses added before it",
+        )
+    }
 
-impl Attachment {
-    pub fn new() -> Attachment {
-        Attachment {
-            attachment: vk::AttachmentDescription::default(),
+    /// Creates the renderpass, the attachment references and their layouts are derived from the subpasses
+    pub fn create(self) -> Result<Renderpass, Error> {
+        self.validate()?;
+        let dependencies = if self.dependencies.is_empty() {
+            infer_dependencies(self.attachments.as_slice(), self.subpasses.as_slice())
+        } else {
+            self.dependencies
+        };
+        let attachments = self.attachments;
+        let input_layout = |attachment: u32| {
+            if aspect_for_format(attachments[attachment as usize].format)
+                == vk::ImageAspectFlags::COLOR
+            {
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            } else {
+                vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+            }
+        };
+        let reference = |subpass: &SubpassSettings, attachment: u32, layout| {
+            vk::AttachmentReferenceBuilder::new()
+                .attachment(attachment)
+                .layout(subpass.layout_of(attachment, layout))
+        };
+        // The references are kept alive until the renderpass is created as the descriptions point to them
+        let references: Vec<_> = self
+            .subpasses
+            .iter()
+            .map(|subpass| {
+                let color: Vec<_> = subpass
+                    .color_attachments
+                    .iter()
+                    .map(|a| reference(subpass, *a, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL))
+                    .collect();
+                let resolve: Vec<_> = subpass
+                    .resolve_attachments
+                    .iter()
+                    .map(|a| match a {
+                        Some(a) => {
+                            reference(subpass, *a, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                        }
+                        None => vk::AttachmentReferenceBuilder::new()
+                            .attachment(vk::ATTACHMENT_UNUSED)
+                            .layout(vk::ImageLayout::UNDEFINED),
+                    })
+                    .collect();
+                let depth = subpass.depth_stencil_attachment.map(|a| {
+                    reference(
+                        subpass,
+                        a,
+                        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                    )
+                });
+                let input: Vec<_> = subpass
+                    .input_attachments
+                    .iter()
+                    .map(|a| reference(subpass, *a, input_layout(*a)))
+                    .collect();
+                (color, resolve, depth, input)
+            })
+            .collect();
+        let descriptions: Vec<_> = self
+            .subpasses
+            .iter()
+            .zip(references.iter())
+            .map(|(subpass, (color, resolve, depth, input))| {
+                let mut description = vk::SubpassDescriptionBuilder::new()
+                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                    .color_attachments(color.as_slice())
+                    .input_attachments(input.as_slice())
+                    .preserve_attachments(subpass.preserve_attachments.as_slice());
+                if !resolve.is_empty() {
+                    description = description.resolve_attachments(resolve.as_slice());
+                }
+                if let Some(depth) = depth {
+                    description = description.depth_stencil_attachment(depth);
+                }
+                description
+            })
+            .collect();
+        let attachment_builders: Vec<_> = attachments
+            .iter()
+            .map(|attachment| {
+                vk::AttachmentDescriptionBuilder::new()
+                    .flags(attachment.flags)
+                    .format(attachment.format)
+                    .samples(attachment.samples)
+                    .load_op(attachment.load_op)
+                    .store_op(attachment.store_op)
+                    .stencil_load_op(attachment.stencil_load_op)
+                    .stencil_store_op(attachment.stencil_store_op)
+                    .initial_layout(attachment.initial_layout)
+                    .final_layout(attachment.final_layout)
+            })
+            .collect();
+        let dependency_builders: Vec<_> = dependencies
+            .iter()
+            .map(|dependency| {
+                vk::SubpassDependencyBuilder::new()
+                    .src_subpass(dependency.src_subpass)
+                    .dst_subpass(dependency.dst_subpass)
+                    .src_stage_mask(dependency.src_stage_mask)
+                    .dst_stage_mask(dependency.dst_stage_mask)
+                    .src_access_mask(dependency.src_access_mask)
+                    .dst_access_mask(dependency.dst_access_mask)
+                    .dependency_flags(dependency.dependency_flags)
+            })
+            .collect();
+        let create_info = vk::RenderPassCreateInfoBuilder::new()
+            .attachments(attachment_builders.as_slice())
+            .subpasses(descriptions.as_slice())
+            .dependencies(dependency_builders.as_slice());
+        let handle = unsafe {
+            self.device
+                .handle()
+                .create_render_pass(&create_info, None, None)
         }
+        .result()?;
+        Ok(Renderpass {
+            handle,
+            id: NEXT_RENDERPASS_ID.fetch_add(1, Ordering::Relaxed),
+            attachments,
+            subpasses: self.subpasses,
+            dependencies,
+        })
     }
 }
-
-pub struct Subpass {}
-
-// Create attachment
-// VkAttachmentDescription colorAttachment{};
-//     colorAttachment.format = swapChainImageFormat;
-//     colorAttachment.samples = VK_SAMPLE_COUNT_1_BIT;
-// VkSubpassDescription subpass{};
-// subpass.pipelineBindPoint = VK_PIPELINE_BIND_POINT_GRAPHICS;
-// Create renderpass based on attachments created
-// VkRenderPassCreateInfo renderPassInfo{};
-// renderPassInfo.sType = VK_STRUCTURE_TYPE_RENDER_PASS_CREATE_INFO;
-// renderPassInfo.attachmentCount = 1;
-// renderPassInfo.pAttachments = &colorAttachment;
-// renderPassInfo.subpassCount = 1;
-// renderPassInfo.pSubpasses = &subpass;
-
-// if (vkCreateRenderPass(device, &renderPassInfo, nullptr, &renderPass) != VK_SUCCESS) {
-//     throw std::runtime_error("failed to create render pass!");
-// }
@@ -0,0 +1,236 @@
+use super::SubpassSettings;
+use crate::image::access_for_layout;
+use erupt::vk1_0 as vk;
+
+// The stages and accesses of every attachment a subpass uses
+fn attachment_uses(
+    subpass: &SubpassSettings,
+) -> Vec<(u32, vk::PipelineStageFlags, vk::AccessFlags)> {
+    let color = (
+        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+    );
+    let resolve = (
+        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+    );
+    let depth = (
+        vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    );
+    let input = (
+        vk::PipelineStageFlags::FRAGMENT_SHADER,
+        vk::AccessFlags::INPUT_ATTACHMENT_READ,
+    );
+    let mut uses = Vec::new();
+    uses.extend(
+        subpass
+            .color_attachments
+            .iter()
+            .map(|a| (*a, color.0, color.1)),
+    );
+    uses.extend(
+        subpass
+            .resolve_attachments
+            .iter()
+            .flatten()
+            .map(|a| (*a, resolve.0, resolve.1)),
+    );
+    uses.extend(
+        subpass
+            .depth_stencil_attachment
+            .iter()
+            .map(|a| (*a, depth.0, depth.1)),
+    );
+    uses.extend(
+        subpass
+            .input_attachments
+            .iter()
+            .map(|a| (*a, input.0, input.1)),
+    );
+    uses
+}
+
+// Only writes need to be made available, reads just need to finish before the next write
+fn writes(access: vk::AccessFlags) -> vk::AccessFlags {
+    access
+        & (vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+}
+
+// Merges into an existing dependency between the same subpasses so there is at most one for each pair
+fn add_dependency(
+    dependencies: &mut Vec<vk::SubpassDependency>,
+    src_subpass: u32,
+    dst_subpass: u32,
+    src: (vk::PipelineStageFlags, vk::AccessFlags),
+    dst: (vk::PipelineStageFlags, vk::AccessFlags),
+) {
+    if let Some(dependency) = dependencies
+        .iter_mut()
+        .find(|d| d.src_subpass == src_subpass && d.dst_subpass == dst_subpass)
+    {
+        dependency.src_stage_mask |= src.0;
+        dependency.src_access_mask |= src.1;
+        dependency.dst_stage_mask |= dst.0;
+        dependency.dst_access_mask |= dst.1;
+        return;
+    }
+    // Subpasses only read the attachments at the same pixel so the dependency can be by region
+    let flags = if src_subpass == vk::SUBPASS_EXTERNAL || dst_subpass == vk::SUBPASS_EXTERNAL {
+        vk::DependencyFlags::empty()
+    } else {
+        vk::DependencyFlags::BY_REGION
+    };
+    let dependency = vk::SubpassDependencyBuilder::new()
+        .src_subpass(src_subpass)
+        .dst_subpass(dst_subpass)
+        .src_stage_mask(src.0)
+        .src_access_mask(src.1)
+        .dst_stage_mask(dst.0)
+        .dst_access_mask(dst.1)
+        .dependency_flags(flags);
+    dependencies.push(*dependency);
+}
+
+/// Infers the dependencies between subpasses from the attachments they use, each subpass waits for the last
+/// subpass that used the same attachment. The first use of an attachment waits for earlier writes to it outside
+/// the renderpass, such as the previous frame or acquiring the swapchain image, and attachments left in a layout
+/// that is read outside the renderpass are made visible to those reads
+pub(super) fn infer_dependencies(
+    attachments: &[vk::AttachmentDescription],
+    subpasses: &[SubpassSettings],
+) -> Vec<vk::SubpassDependency> {
+    let mut dependencies = Vec::new();
+    // The subpass that last used each attachment and how it was used
+    let mut last_use: Vec<Option<(u32, vk::PipelineStageFlags, vk::AccessFlags)>> =
+        vec![None; attachments.len()];
+    for (index, subpass) in subpasses.iter().enumerate() {
+        let index = index as u32;
+        let uses = attachment_uses(subpass);
+        for (attachment, stage, access) in uses.iter().copied() {
+            match last_use[attachment as usize] {
+                Some((src_subpass, src_stage, src_access)) => {
+                    add_dependency(
+                        &mut dependencies,
+                        src_subpass,
+                        index,
+                        (src_stage, writes(src_access)),
+                        (stage, access),
+                    );
+                }
+                None => {
+                    // Input attachments were written as a colour or depth attachment outside the renderpass
+                    let src = if access == vk::AccessFlags::INPUT_ATTACHMENT_READ {
+                        (
+                            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                        )
+                    } else {
+                        (stage, writes(access))
+                    };
+                    add_dependency(
+                        &mut dependencies,
+                        vk::SUBPASS_EXTERNAL,
+                        index,
+                        src,
+                        (stage, access),
+                    );
+                }
+            }
+        }
+        for (attachment, stage, access) in uses {
+            last_use[attachment as usize] = match last_use[attachment as usize] {
+                // An attachment used twice by a subpass combines both uses
+                Some((subpass, last_stage, last_access)) if subpass == index => {
+                    Some((index, last_stage | stage, last_access | access))
+                }
+                _ => Some((index, stage, access)),
+            };
+        }
+    }
+    for (attachment, last) in attachments.iter().zip(last_use) {
+        let read_afterwards = matches!(
+            attachment.final_layout,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+                | vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+                | vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+        );
+        if let (true, Some((src_subpass, stage, access))) = (read_afterwards, last) {
+            let (dst_access, dst_stage) = access_for_layout(attachment.final_layout);
+            add_dependency(
+                &mut dependencies,
+                src_subpass,
+                vk::SUBPASS_EXTERNAL,
+                (stage, writes(access)),
+                (dst_stage, dst_access),
+            );
+        }
+    }
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(format: vk::Format, final_layout: vk::ImageLayout) -> vk::AttachmentDescription {
+        *vk::AttachmentDescriptionBuilder::new()
+            .format(format)
+            .final_layout(final_layout)
+    }
+
+    #[test]
+    fn test_infer_dependencies() {
+        // A gbuffer and depth written by the first subpass, read as inputs by the second which writes
+        // to the presented image, the gbuffer is also sampled after the renderpass
+        let attachments = [
+            attachment(
+                vk::Format::R8G8B8A8_UNORM,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ),
+            attachment(
+                vk::Format::D32_SFLOAT,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ),
+            attachment(vk::Format::B8G8R8A8_SRGB, vk::ImageLayout::PRESENT_SRC_KHR),
+        ];
+        let mut geometry = SubpassSettings::new();
+        geometry.color(0).depth_stencil(1);
+        let mut lighting = SubpassSettings::new();
+        lighting.input(0).input(1).color(2);
+        let dependencies = infer_dependencies(&attachments, &[geometry, lighting]);
+        let pairs: Vec<(u32, u32)> = dependencies
+            .iter()
+            .map(|d| (d.src_subpass, d.dst_subpass))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (vk::SUBPASS_EXTERNAL, 0),
+                (vk::SUBPASS_EXTERNAL, 1),
+                (0, 1),
+                (1, vk::SUBPASS_EXTERNAL)
+            ]
+        );
+        // Both attachments written by the first subpass are merged into one dependency
+        let between = dependencies[2];
+        assert_eq!(
+            between.src_access_mask,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+        );
+        assert_eq!(
+            between.dst_access_mask,
+            vk::AccessFlags::INPUT_ATTACHMENT_READ
+        );
+        assert_eq!(between.dependency_flags, vk::DependencyFlags::BY_REGION);
+        // The gbuffer is last used as an input so there is nothing to make available
+        let after = dependencies[3];
+        assert_eq!(after.src_access_mask, vk::AccessFlags::empty());
+        assert_eq!(after.dst_access_mask, vk::AccessFlags::SHADER_READ);
+    }
+}
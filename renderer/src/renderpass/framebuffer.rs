@@ -0,0 +1,167 @@
+use super::{Framebuffer, Renderpass, SwapchainFramebuffers};
+use crate::error::{Error, ErrorKind};
+use crate::{ImageView, Presenter, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+impl Framebuffer {
+    /// Creates a framebuffer of any size for the renderpass, there must be a view for each attachment of the
+    /// renderpass in the same order with the same format, and each view must be at least as large as the extent
+    pub fn new(
+        device: &VulkanDevice,
+        renderpass: &Renderpass,
+        attachments: &[&ImageView],
+        extent: vk::Extent2D,
+    ) -> Result<Framebuffer, Error> {
+        let invalid = |context: &'static &'static str| {
+            Err(Error::new(ErrorKind::InvalidFramebuffer, None).with_context(context))
+        };
+        if attachments.len() != renderpass.attachments().len() {
+            return invalid(&"A framebuffer needs a view for each attachment of the renderpass");
+        }
+        if attachments
+            .iter()
+            .zip(renderpass.attachments())
+            .any(|(view, attachment)| view.format() != attachment.format)
+        {
+            return invalid(&"The format of a view doesn't match its attachment in the renderpass");
+        }
+        let limits = device.limits();
+        if extent.width == 0 || extent.height == 0 {
+            return invalid(&"The width and height of a framebuffer must be greater than zero");
+        }
+        if extent.width > limits.max_framebuffer_width
+            || extent.height > limits.max_framebuffer_height
+        {
+            return invalid(&"The framebuffer is larger than the maxFramebufferWidth or maxFramebufferHeight limits");
+        }
+        if attachments
+            .iter()
+            .any(|view| view.extent().width < extent.width || view.extent().height < extent.height)
+        {
+            return invalid(&"A view is smaller than the framebuffer");
+        }
+        let handles: Vec<vk::ImageView> = attachments.iter().map(|view| view.handle()).collect();
+        let create_info = vk::FramebufferCreateInfoBuilder::new()
+            .render_pass(renderpass.handle())
+            .attachments(handles.as_slice())
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1);
+        let handle =
+            unsafe { device.handle().create_framebuffer(&create_info, None, None) }.result()?;
+        Ok(Framebuffer {
+            handle,
+            render_pass: renderpass.handle(),
+            extent,
+            attachments: handles,
+        })
+    }
+
+    pub fn handle(&self) -> vk::Framebuffer {
+        self.handle
+    }
+
+    /// The area the renderpass renders to
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// The renderpass the framebuffer was created for, it can also be used with compatible renderpasses
+    pub fn render_pass(&self) -> vk::RenderPass {
+        self.render_pass
+    }
+
+    pub fn attachments(&self) -> &[vk::ImageView] {
+        self.attachments.as_slice()
+    }
+
+    /// Destroys the framebuffer, it must not be in use by the device, the image views are not destroyed
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_framebuffer(Some(self.handle), None) };
+    }
+}
+
+impl SwapchainFramebuffers {
+    pub fn new() -> SwapchainFramebuffers {
+        SwapchainFramebuffers::default()
+    }
+
+    /// Call before recording each frame, the swapchain view is the first attachment of each framebuffer followed
+    /// by the views shared by every swapchain image, ie a depth buffer. The framebuffers are only rebuilt when
+    /// the presenter recreated its swapchain or the renderpass or shared views were replaced, the device is waited
+    /// on before the old framebuffers are destroyed. Returns true if they were rebuilt
+    pub fn update(
+        &mut self,
+        device: &VulkanDevice,
+        renderpass: &Renderpass,
+        presenter: &Presenter,
+        shared_views: &[&ImageView],
+    ) -> Result<bool, Error> {
+        let current = !self.framebuffers.is_empty()
+            && self.generation == presenter.generation()
+            && self.framebuffers.iter().all(|framebuffer| {
+                framebuffer.render_pass == renderpass.handle()
+                    && framebuffer.attachments.len() == shared_views.len() + 1
+                    && framebuffer.attachments[1..]
+                        .iter()
+                        .zip(shared_views)
+                        .all(|(handle, view)| *handle == view.handle())
+            });
+        if current {
+            return Ok(false);
+        }
+        let swapchain_views = presenter.image_views();
+        let mut framebuffers = Vec::with_capacity(swapchain_views.len());
+        for swapchain_view in swapchain_views {
+            let mut attachments = vec![swapchain_view];
+            attachments.extend_from_slice(shared_views);
+            match Framebuffer::new(
+                device,
+                renderpass,
+                attachments.as_slice(),
+                presenter.extent(),
+            ) {
+                Ok(framebuffer) => framebuffers.push(framebuffer),
+                Err(error) => {
+                    for framebuffer in framebuffers {
+                        framebuffer.destroy(device);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        if !self.framebuffers.is_empty() {
+            if let Err(error) = unsafe { device.handle().device_wait_idle() }.result() {
+                for framebuffer in framebuffers {
+                    framebuffer.destroy(device);
+                }
+                return Err(Error::from(error));
+            }
+        }
+        for framebuffer in std::mem::replace(&mut self.framebuffers, framebuffers) {
+            framebuffer.destroy(device);
+        }
+        self.generation = presenter.generation();
+        Ok(true)
+    }
+
+    /// The framebuffer of the swapchain image that was acquired
+    pub fn get(&self, image_index: u32) -> Option<&Framebuffer> {
+        self.framebuffers.get(image_index as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.framebuffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.framebuffers.is_empty()
+    }
+
+    /// Destroys every framebuffer, they must not be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        for framebuffer in self.framebuffers {
+            framebuffer.destroy(device);
+        }
+    }
+}
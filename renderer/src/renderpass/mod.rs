@@ -1,3 +1,75 @@
+mod attachment;
 mod config;
+mod dependency;
+mod framebuffer;
+mod renderpass;
+mod target;
 
-pub use config::Renderpass;
+pub(crate) use target::cap_sample_count;
+
+use crate::{Image, ImageView, VulkanDevice};
+use erupt::vk1_0 as vk;
+
+/// How an attachment of a renderpass is loaded, stored and laid out before and after the renderpass
+#[derive(Debug, Clone, Copy)]
+pub struct Attachment {
+    description: vk::AttachmentDescription,
+}
+
+/// The attachments a subpass uses, attachments are referred to by the order they were added to the renderpass
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubpassSettings {
+    color_attachments: Vec<u32>,
+    // One for each colour attachment when any colour attachment is resolved
+    resolve_attachments: Vec<Option<u32>>,
+    depth_stencil_attachment: Option<u32>,
+    input_attachments: Vec<u32>,
+    preserve_attachments: Vec<u32>,
+}
+
+/// Collects the attachments, subpasses and dependencies of a renderpass before it is created
+pub struct ConfigureRenderpass<'a> {
+    device: &'a VulkanDevice,
+    attachments: Vec<vk::AttachmentDescription>,
+    subpasses: Vec<SubpassSettings>,
+    // Inferred from how the subpasses use the attachments when empty
+    dependencies: Vec<vk::SubpassDependency>,
+}
+
+/// A created renderpass along with the attachments and subpasses it was created with
+pub struct Renderpass {
+    handle: vk::RenderPass,
+    // Unique for the lifetime of the process, unlike the handle which can be reused once the renderpass is destroyed
+    id: u64,
+    attachments: Vec<vk::AttachmentDescription>,
+    subpasses: Vec<SubpassSettings>,
+    dependencies: Vec<vk::SubpassDependency>,
+}
+
+/// The image views a renderpass renders to, one for each attachment of the renderpass
+pub struct Framebuffer {
+    handle: vk::Framebuffer,
+    render_pass: vk::RenderPass,
+    extent: vk::Extent2D,
+    attachments: Vec<vk::ImageView>,
+}
+
+/// A framebuffer for each swapchain image, rebuilt when the swapchain is recreated
+#[derive(Default)]
+pub struct SwapchainFramebuffers {
+    framebuffers: Vec<Framebuffer>,
+    // The generation of the presenter the framebuffers were created for, image view handles can be reused
+    // by a recreated swapchain so they can't tell if the framebuffers are current
+    generation: u64,
+}
+
+/// Offscreen colour and depth images rendered at the same sample count, a multisampled colour image is resolved
+/// to a single sampled image at the end of the subpass
+pub struct RenderTarget {
+    extent: vk::Extent2D,
+    samples: vk::SampleCountFlagBits,
+    color: (Image, ImageView),
+    depth: Option<(Image, ImageView)>,
+    // Only created when the colour image is multisampled
+    resolve: Option<(Image, ImageView)>,
+}
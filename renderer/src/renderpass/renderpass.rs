@@ -0,0 +1,48 @@
+use super::{Renderpass, SubpassSettings};
+use crate::error::{Error, ErrorKind};
+use crate::VulkanDevice;
+use erupt::vk1_0 as vk;
+
+impl Renderpass {
+    pub fn handle(&self) -> vk::RenderPass {
+        self.handle
+    }
+
+    /// Identifies the renderpass for as long as the process runs, even after it has been recreated
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The attachments in the order they were added, framebuffers need an image view for each
+    pub fn attachments(&self) -> &[vk::AttachmentDescription] {
+        self.attachments.as_slice()
+    }
+
+    pub fn subpasses(&self) -> &[SubpassSettings] {
+        self.subpasses.as_slice()
+    }
+
+    pub fn subpass_count(&self) -> u32 {
+        self.subpasses.len() as u32
+    }
+
+    /// The dependencies the renderpass was created with, either the ones that were added or the inferred ones
+    pub fn dependencies(&self) -> &[vk::SubpassDependency] {
+        self.dependencies.as_slice()
+    }
+
+    // Pipelines can only be created for a subpass that is part of the renderpass
+    pub(crate) fn subpass_handle(&self, subpass: u32) -> Result<vk::RenderPass, Error> {
+        if subpass < self.subpass_count() {
+            Ok(self.handle)
+        } else {
+            Err(Error::new(ErrorKind::InvalidPipelineConfig, None)
+                .with_context(&"The subpass is not part of the renderpass"))
+        }
+    }
+
+    /// Destroys the renderpass, pipelines and framebuffers created for it must be destroyed first
+    pub fn destroy(self, device: &VulkanDevice) {
+        unsafe { device.handle().destroy_render_pass(Some(self.handle), None) };
+    }
+}
@@ -0,0 +1,281 @@
+use super::{Attachment, Framebuffer, RenderTarget, Renderpass, SubpassSettings};
+use crate::error::Error;
+use crate::{
+    CommandRecorder, Image, ImageView, RenderingAttachment, RenderingFormats, VulkanDevice,
+};
+use erupt::vk1_0 as vk;
+
+const SAMPLE_COUNTS: [vk::SampleCountFlagBits; 7] = [
+    vk::SampleCountFlagBits::_64,
+    vk::SampleCountFlagBits::_32,
+    vk::SampleCountFlagBits::_16,
+    vk::SampleCountFlagBits::_8,
+    vk::SampleCountFlagBits::_4,
+    vk::SampleCountFlagBits::_2,
+    vk::SampleCountFlagBits::_1,
+];
+
+/// The largest sample count in counts that is no larger than the requested count, a single sample is always supported
+pub(crate) fn cap_sample_count(
+    counts: vk::SampleCountFlags,
+    requested: vk::SampleCountFlagBits,
+) -> vk::SampleCountFlagBits {
+    SAMPLE_COUNTS
+        .iter()
+        .copied()
+        .find(|count| count.0 <= requested.0 && counts.contains(count.bitmask()))
+        .unwrap_or(vk::SampleCountFlagBits::_1)
+}
+
+// Creates an image used only as an attachment of the target along with a view of the entire image
+fn create_attachment(
+    device: &VulkanDevice,
+    extent: vk::Extent2D,
+    format: vk::Format,
+    samples: vk::SampleCountFlagBits,
+    usage: vk::ImageUsageFlags,
+) -> Result<(Image, ImageView), Error> {
+    let image = device
+        .create_image()
+        .extent(extent.width, extent.height)
+        .format(format)
+        .samples(samples)
+        .usage(usage)
+        .build()?;
+    match device.create_image_view(&image, |_| {}) {
+        Ok(view) => Ok((image, view)),
+        Err(error) => {
+            image.destroy(device);
+            Err(error)
+        }
+    }
+}
+
+impl RenderTarget {
+    /// Creates the colour image and optionally a depth image of the target, the sample count is lowered to the
+    /// largest count in the framebufferColorSampleCounts limit, and the framebufferDepthSampleCounts limit when
+    /// there is a depth image. A multisampled colour image is resolved to a single sampled image that can be
+    /// sampled, copied from or presented
+    pub fn new(
+        device: &VulkanDevice,
+        extent: vk::Extent2D,
+        color_format: vk::Format,
+        depth_format: Option<vk::Format>,
+        samples: vk::SampleCountFlagBits,
+    ) -> Result<RenderTarget, Error> {
+        let samples = device.supported_sample_count(samples, depth_format.is_some());
+        let output_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::SAMPLED
+            | vk::ImageUsageFlags::TRANSFER_SRC;
+        let multisampled = samples != vk::SampleCountFlagBits::_1;
+        // The multisampled image is only needed until it is resolved
+        let color_usage = if multisampled {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+        } else {
+            output_usage
+        };
+        let color = create_attachment(device, extent, color_format, samples, color_usage)?;
+        let mut target = RenderTarget {
+            extent,
+            samples,
+            color,
+            depth: None,
+            resolve: None,
+        };
+        if let Some(depth_format) = depth_format {
+            let usage = vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+            match create_attachment(device, extent, depth_format, samples, usage) {
+                Ok(depth) => target.depth = Some(depth),
+                Err(error) => {
+                    target.destroy(device);
+                    return Err(error);
+                }
+            }
+        }
+        if multisampled {
+            let resolve = vk::SampleCountFlagBits::_1;
+            match create_attachment(device, extent, color_format, resolve, output_usage) {
+                Ok(resolve) => target.resolve = Some(resolve),
+                Err(error) => {
+                    target.destroy(device);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(target)
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// The sample count after it was capped by the device, the pipelines that render to the target must use it
+    pub fn samples(&self) -> vk::SampleCountFlagBits {
+        self.samples
+    }
+
+    pub fn is_multisampled(&self) -> bool {
+        self.resolve.is_some()
+    }
+
+    /// The single sampled image that holds the rendered result, the resolve image when the target is multisampled
+    pub fn output(&self) -> &Image {
+        match &self.resolve {
+            Some((image, _)) => image,
+            None => &self.color.0,
+        }
+    }
+
+    pub fn output_view(&self) -> &ImageView {
+        match &self.resolve {
+            Some((_, view)) => view,
+            None => &self.color.1,
+        }
+    }
+
+    /// The attachments to add to a renderpass, in order the colour image, the depth image if there is one and the
+    /// resolve image if the target is multisampled. Only the output is stored, it is left in the final layout
+    pub fn attachments(&self, final_layout: vk::ImageLayout) -> Vec<Attachment> {
+        let mut color = Attachment::new(self.color.0.format()).samples(self.samples);
+        color = match self.resolve {
+            Some(_) => color.store_op(vk::AttachmentStoreOp::DONT_CARE),
+            None => color.final_layout(final_layout),
+        };
+        let mut attachments = vec![color];
+        if let Some((depth, _)) = &self.depth {
+            attachments.push(Attachment::depth(depth.format()).samples(self.samples));
+        }
+        if let Some((resolve, _)) = &self.resolve {
+            attachments.push(
+                Attachment::new(resolve.format())
+                    .load_op(vk::AttachmentLoadOp::DONT_CARE)
+                    .final_layout(final_layout),
+            );
+        }
+        attachments
+    }
+
+    /// Renders to the target in a subpass, first is the index of the first attachment of the target in the renderpass
+    pub fn configure_subpass(&self, subpass: &mut SubpassSettings, first: u32) {
+        let depth = self.depth.as_ref().map(|_| first + 1);
+        match self.resolve {
+            Some(_) => subpass.resolved_color(first, depth.unwrap_or(first) + 1),
+            None => subpass.color(first),
+        };
+        if let Some(depth) = depth {
+            subpass.depth_stencil(depth);
+        }
+    }
+
+    /// The views of the target in the same order as its attachments
+    pub fn views(&self) -> Vec<&ImageView> {
+        std::iter::once(&self.color.1)
+            .chain(self.depth.iter().map(|(_, view)| view))
+            .chain(self.resolve.iter().map(|(_, view)| view))
+            .collect()
+    }
+
+    /// Creates a framebuffer for a renderpass whose only attachments are those of the target
+    pub fn create_framebuffer(
+        &self,
+        device: &VulkanDevice,
+        renderpass: &Renderpass,
+    ) -> Result<Framebuffer, Error> {
+        Framebuffer::new(device, renderpass, self.views().as_slice(), self.extent)
+    }
+
+    /// The colour and depth attachments for dynamic rendering to the target, a multisampled colour image is
+    /// resolved to the output and nothing but the output is stored
+    pub fn rendering_attachments(&self) -> (RenderingAttachment, Option<RenderingAttachment>) {
+        let mut color = RenderingAttachment::new(&self.color.1);
+        if let Some((_, resolve)) = &self.resolve {
+            color = color
+                .resolve(resolve)
+                .store_op(vk::AttachmentStoreOp::DONT_CARE);
+        }
+        let depth = self.depth.as_ref().map(|(_, view)| {
+            RenderingAttachment::new(view).store_op(vk::AttachmentStoreOp::DONT_CARE)
+        });
+        (color, depth)
+    }
+
+    /// The formats pipelines that render to the target with dynamic rendering are created for
+    pub fn rendering_formats(&self) -> RenderingFormats {
+        RenderingFormats::new(
+            &[self.color.0.format()],
+            self.depth.as_ref().map(|(image, _)| image.format()),
+        )
+    }
+
+    /// Transitions the images into their attachment layouts and begins dynamic rendering to the target, the
+    /// colour image is cleared to the colour
+    pub fn begin_rendering(
+        &mut self,
+        recorder: &mut CommandRecorder,
+        clear_color: vk::ClearColorValue,
+    ) -> Result<(), Error> {
+        let (color, depth) = self.rendering_attachments();
+        let color = color.clear_value(vk::ClearValue { color: clear_color });
+        recorder.transition_image(&mut self.color.0, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        if let Some((image, _)) = self.resolve.as_mut() {
+            recorder.transition_image(image, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        }
+        if let Some((image, _)) = self.depth.as_mut() {
+            recorder.transition_image(image, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+        }
+        recorder.begin_rendering(self.extent, &[color], depth.as_ref())
+    }
+
+    /// Ends dynamic rendering and transitions the output into the layout it is used in next, ie
+    /// SHADER_READ_ONLY_OPTIMAL to sample it or TRANSFER_SRC_OPTIMAL to copy it
+    pub fn end_rendering(
+        &mut self,
+        recorder: &mut CommandRecorder,
+        output_layout: vk::ImageLayout,
+    ) {
+        recorder.end_rendering();
+        let output = match self.resolve.as_mut() {
+            Some((image, _)) => image,
+            None => &mut self.color.0,
+        };
+        recorder.transition_image(output, output_layout);
+    }
+
+    /// Destroys the images of the target, they must not be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        let attachments = std::iter::once(self.color)
+            .chain(self.depth)
+            .chain(self.resolve);
+        for (image, view) in attachments {
+            view.destroy(device);
+            image.destroy(device);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_sample_count() {
+        let counts = vk::SampleCountFlags::_1 | vk::SampleCountFlags::_2 | vk::SampleCountFlags::_4;
+        assert_eq!(
+            cap_sample_count(counts, vk::SampleCountFlagBits::_4),
+            vk::SampleCountFlagBits::_4
+        );
+        assert_eq!(
+            cap_sample_count(counts, vk::SampleCountFlagBits::_16),
+            vk::SampleCountFlagBits::_4
+        );
+        assert_eq!(
+            cap_sample_count(counts, vk::SampleCountFlagBits::_2),
+            vk::SampleCountFlagBits::_2
+        );
+        assert_eq!(
+            cap_sample_count(vk::SampleCountFlags::empty(), vk::SampleCountFlagBits::_8),
+            vk::SampleCountFlagBits::_1
+        );
+    }
+}
@@ -4,7 +4,7 @@ use super::{
 };
 use crate::{
     error::{Error, ErrorKind},
-    ConfigureDevice, ConfigurePresenter, PciVendor, QueueFamily,
+    ConfigureDevice, ConfigurePresenter, PciVendor, QueueFamily, Vulkan12Features,
 };
 use erupt::extensions::khr_surface as surface;
 use erupt::vk1_0 as vk;
@@ -79,6 +79,8 @@ impl<'a> DeviceSelector<'a> {
 
         let device_features =
             unsafe { instance.get_physical_device_features(physical_device, None) };
+        let vulkan12_features =
+            Vulkan12Features::query(instance, physical_device, device_properties.api_version);
 
         // TODO: We only get surface details if a surface was provided and only then if the device can present
         let surface_formats = Self::get_surface_formats(instance, physical_device, surface)
@@ -95,6 +97,7 @@ impl<'a> DeviceSelector<'a> {
             device_queues,
             available_extensions,
             device_features,
+            vulkan12_features,
             surface_capabilities,
             surface_formats,
             present_modes,
@@ -250,6 +253,8 @@ impl<'a> DeviceSelector<'a> {
             api_version,
             available_extensions,
             device_features,
+            vulkan12_features,
+            limits,
             device_handle,
             device_id,
             device_name,
@@ -279,6 +284,8 @@ impl<'a> DeviceSelector<'a> {
             device_type,
             available_extensions,
             device_features,
+            vulkan12_features,
+            limits,
         );
         (device_selected, configure_presenter)
     }
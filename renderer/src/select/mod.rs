@@ -7,7 +7,7 @@ use erupt::vk1_0 as vk;
 use super::Gpu;
 
 use crate::error;
-use crate::{DeviceExtensions, Features, PciVendor, QueueFamily};
+use crate::{DeviceExtensions, Features, PciVendor, QueueFamily, Vulkan12Features};
 use crate::{ExtensionManager, Version};
 use erupt::extensions::khr_surface as surface;
 use std::collections::HashSet;
@@ -67,4 +67,6 @@ pub struct SelectedDevice {
     pub(super) device_type: vk::PhysicalDeviceType,
     pub(super) available_extensions: Vec<vk::ExtensionProperties>,
     pub(super) device_features: vk::PhysicalDeviceFeatures,
+    pub(super) vulkan12_features: Vulkan12Features,
+    pub(super) limits: vk::PhysicalDeviceLimits,
 }
@@ -1,9 +1,9 @@
 use super::SelectedDevice;
-use crate::{Gpu, PciVendor, QueueFamily, Version};
+use crate::{Gpu, PciVendor, QueueFamily, Version, Vulkan12Features};
 use erupt::vk1_0 as vk;
 
 impl SelectedDevice {
-    pub fn new(
+    pub(crate) fn new(
         device_handle: vk::PhysicalDevice,
         queue_families: Vec<QueueFamily>,
         api_version: Version,
@@ -14,6 +14,8 @@ impl SelectedDevice {
         device_type: vk::PhysicalDeviceType,
         available_extensions: Vec<vk::ExtensionProperties>,
         device_features: vk::PhysicalDeviceFeatures,
+        vulkan12_features: Vulkan12Features,
+        limits: vk::PhysicalDeviceLimits,
     ) -> SelectedDevice {
         SelectedDevice {
             device_handle,
@@ -26,6 +28,8 @@ impl SelectedDevice {
             device_type,
             available_extensions,
             device_features,
+            vulkan12_features,
+            limits,
         }
     }
 }
@@ -43,6 +47,8 @@ impl From<Gpu> for SelectedDevice {
             gpu.device_type,
             gpu.available_extensions,
             gpu.device_features,
+            gpu.vulkan12_features,
+            gpu.limits,
         )
     }
 }
@@ -0,0 +1,170 @@
+use super::mipmap::bytes_per_pixel;
+use super::texture::configure_sampler;
+use super::{ResidentTile, Texture, TextureFiltering, TileCache, TileDraw, TileKey, TiledImage};
+use crate::error::Error;
+use crate::{ImageView, OperationQueue, Sampler, VulkanDevice};
+
+use std::collections::{HashMap, HashSet};
+
+impl TileCache {
+    /// Creates an empty cache that keeps at most budget bytes of tiles resident, tiles that are visible
+    /// are never evicted so the budget can be exceeded when a huge screen shows many tiles at once
+    pub fn new(
+        device: &VulkanDevice,
+        budget: u64,
+        filtering: TextureFiltering,
+    ) -> Result<TileCache, Error> {
+        let sampler = device.create_sampler(|sampler| {
+            configure_sampler(device, sampler, filtering);
+        })?;
+        Ok(TileCache {
+            resident: HashMap::new(),
+            retired: Vec::new(),
+            sampler,
+            budget,
+            used: 0,
+            frame: 0,
+            frames_in_flight: 2,
+            uploads_per_update: 4,
+        })
+    }
+
+    /// The number of frames that can be in flight at once, evicted tiles are destroyed after this many updates
+    pub fn frames_in_flight(&mut self, frames: u64) {
+        self.frames_in_flight = frames;
+    }
+
+    /// Limits how many tiles are uploaded by each update so that scrolling doesn't stall a frame,
+    /// tiles that haven't been uploaded yet are drawn using coarser tiles
+    pub fn uploads_per_update(&mut self, uploads: usize) {
+        self.uploads_per_update = uploads.max(1);
+    }
+
+    /// Call once per frame with the visible tiles, uploads missing tiles with the nearest ones first in a single
+    /// submission, evicts the least recently used tiles that are over budget and returns what should be drawn
+    pub fn update(
+        &mut self,
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        image: &TiledImage,
+        visible: &[TileKey],
+    ) -> Result<Vec<TileDraw>, Error> {
+        self.frame += 1;
+        self.destroy_retired(device, false);
+        let top = image.top_tile();
+        let mut missing = Vec::with_capacity(self.uploads_per_update);
+        // The top tile is always uploaded first so that there is a fallback for every other tile
+        for key in std::iter::once(&top).chain(visible.iter()) {
+            match self.resident.get_mut(key) {
+                Some(tile) => tile.last_used = self.frame,
+                None if missing.len() < self.uploads_per_update && !missing.contains(key) => {
+                    missing.push(*key)
+                }
+                None => (),
+            }
+        }
+        self.upload(device, queue, image, missing.as_slice())?;
+        let visible_set: HashSet<&TileKey> = visible.iter().collect();
+        self.evict(|key| *key == top || visible_set.contains(key));
+        let resident = &self.resident;
+        Ok(image.plan_draws(visible, |key| resident.contains_key(key)))
+    }
+
+    fn upload(
+        &mut self,
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        image: &TiledImage,
+        keys: &[TileKey],
+    ) -> Result<(), Error> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let tiles = keys
+            .iter()
+            .map(|key| image.tile_pixels(key))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let uploaded = Texture::upload_images(device, queue, tiles.as_slice())?;
+        for ((key, pixels), (tile_image, view)) in keys.iter().zip(tiles.iter()).zip(uploaded) {
+            // Estimated from the texel size as the exact requirements are only known to the image
+            let size = pixels.width() as u64
+                * pixels.height() as u64
+                * bytes_per_pixel(pixels.format()) as u64;
+            self.used += size;
+            self.resident.insert(
+                *key,
+                ResidentTile {
+                    image: tile_image,
+                    view,
+                    size,
+                    last_used: self.frame,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // Evicts the least recently used tiles until the cache is within budget, pinned tiles are skipped
+    fn evict<F>(&mut self, is_pinned: F)
+    where
+        F: Fn(&TileKey) -> bool,
+    {
+        if self.used <= self.budget {
+            return;
+        }
+        let mut candidates: Vec<(u64, TileKey)> = self
+            .resident
+            .iter()
+            .filter(|(key, _)| !is_pinned(key))
+            .map(|(key, tile)| (tile.last_used, *key))
+            .collect();
+        candidates.sort();
+        for (_, key) in candidates {
+            if self.used <= self.budget {
+                break;
+            }
+            if let Some(tile) = self.resident.remove(&key) {
+                self.used -= tile.size;
+                self.retired.push((self.frame, tile));
+            }
+        }
+    }
+
+    fn destroy_retired(&mut self, device: &VulkanDevice, all: bool) {
+        let oldest_in_use = self.frame.saturating_sub(self.frames_in_flight);
+        let (expired, retained) = self
+            .retired
+            .drain(..)
+            .partition(|(frame, _)| all || *frame <= oldest_in_use);
+        self.retired = retained;
+        for (_, tile) in expired {
+            tile.view.destroy(device);
+            tile.image.destroy(device);
+        }
+    }
+
+    /// The view of a resident tile, used when writing the descriptor for a tile that will be drawn
+    pub fn view(&self, key: &TileKey) -> Option<&ImageView> {
+        self.resident.get(key).map(|tile| &tile.view)
+    }
+
+    /// The sampler shared by every tile
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// The estimated number of bytes used by resident tiles
+    pub fn memory_used(&self) -> u64 {
+        self.used
+    }
+
+    /// Destroys every tile and the sampler, the tiles must no longer be in use by the device
+    pub fn destroy(mut self, device: &VulkanDevice) {
+        self.destroy_retired(device, true);
+        for (_, tile) in self.resident.drain() {
+            tile.view.destroy(device);
+            tile.image.destroy(device);
+        }
+        self.sampler.destroy(device);
+    }
+}
@@ -0,0 +1,236 @@
+use super::mipmap::{f32_to_f16, srgb_to_linear};
+use super::{decode_error, DecodedImage};
+use crate::error::Error;
+use ::image::{DynamicImage, ImageDecoder, ImageReader};
+use erupt::vk1_0 as vk;
+
+use std::io::{BufRead, Cursor, Seek};
+use std::path::Path;
+
+/// Reads and decodes a PNG, JPEG, WebP, BMP or TIFF file on the calling thread
+pub fn decode_file<P: AsRef<Path>>(path: P) -> Result<DecodedImage, Error> {
+    let reader = ImageReader::open(path).map_err(|error| decode_error(error.to_string()))?;
+    decode(reader)
+}
+
+/// Decodes an image that has already been loaded into memory, the format is guessed from the data
+pub fn decode_memory(data: &[u8]) -> Result<DecodedImage, Error> {
+    decode(ImageReader::new(Cursor::new(data)))
+}
+
+fn decode<R: BufRead + Seek>(reader: ImageReader<R>) -> Result<DecodedImage, Error> {
+    let mut reader = reader
+        .with_guessed_format()
+        .map_err(|error| decode_error(error.to_string()))?;
+    // The default limits refuse anything over 512 MiB, which the large images that get tiled easily exceed
+    reader.no_limits();
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|error| decode_error(error.to_string()))?;
+    let orientation = decoder
+        .orientation()
+        .map_err(|error| decode_error(error.to_string()))?;
+    let mut image =
+        DynamicImage::from_decoder(decoder).map_err(|error| decode_error(error.to_string()))?;
+    image.apply_orientation(orientation);
+    Ok(DecodedImage::from(image))
+}
+
+// Swizzles used to present single and dual channel images as grayscale
+const GRAY: vk::ComponentMapping = vk::ComponentMapping {
+    r: vk::ComponentSwizzle::R,
+    g: vk::ComponentSwizzle::R,
+    b: vk::ComponentSwizzle::R,
+    a: vk::ComponentSwizzle::ONE,
+};
+const GRAY_ALPHA: vk::ComponentMapping = vk::ComponentMapping {
+    r: vk::ComponentSwizzle::R,
+    g: vk::ComponentSwizzle::R,
+    b: vk::ComponentSwizzle::R,
+    a: vk::ComponentSwizzle::G,
+};
+const IDENTITY: vk::ComponentMapping = vk::ComponentMapping {
+    r: vk::ComponentSwizzle::IDENTITY,
+    g: vk::ComponentSwizzle::IDENTITY,
+    b: vk::ComponentSwizzle::IDENTITY,
+    a: vk::ComponentSwizzle::IDENTITY,
+};
+
+// Three channel formats are rarely supported for sampling so RGB images are expanded to RGBA
+impl From<DynamicImage> for DecodedImage {
+    fn from(image: DynamicImage) -> DecodedImage {
+        let (width, height) = (image.width(), image.height());
+        let (format, swizzle, pixels) = match image {
+            // Gray pixels are sRGB encoded like colour ones, two channel sRGB formats are rarely supported
+            // so gray with alpha is expanded to RGBA
+            DynamicImage::ImageLuma8(image) => (vk::Format::R8_SRGB, GRAY, image.into_raw()),
+            DynamicImage::ImageLumaA8(_) => (
+                vk::Format::R8G8B8A8_SRGB,
+                IDENTITY,
+                image.into_rgba8().into_raw(),
+            ),
+            // 16 bit images are converted to linear half floats, keeping more precision in the shadows than
+            // sRGB encoded integers would once they're filtered
+            DynamicImage::ImageLuma16(image) => (
+                vk::Format::R16_SFLOAT,
+                GRAY,
+                linear_half_floats(image.into_raw(), 1),
+            ),
+            DynamicImage::ImageLumaA16(image) => (
+                vk::Format::R16G16_SFLOAT,
+                GRAY_ALPHA,
+                linear_half_floats(image.into_raw(), 2),
+            ),
+            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => (
+                vk::Format::R8G8B8A8_SRGB,
+                IDENTITY,
+                image.into_rgba8().into_raw(),
+            ),
+            DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => (
+                vk::Format::R16G16B16A16_SFLOAT,
+                IDENTITY,
+                linear_half_floats(image.into_rgba16().into_raw(), 4),
+            ),
+            // Floating point and any future formats are uploaded as 32 bit floats
+            image => (
+                vk::Format::R32G32B32A32_SFLOAT,
+                IDENTITY,
+                image
+                    .into_rgba32f()
+                    .into_raw()
+                    .iter()
+                    .flat_map(|channel| channel.to_ne_bytes())
+                    .collect(),
+            ),
+        };
+        DecodedImage {
+            width,
+            height,
+            format,
+            swizzle,
+            pixels,
+            mip_chain: Vec::new(),
+        }
+    }
+}
+
+// Decodes sRGB encoded 16 bit channels to linear light, the last channel of two and four channel images is alpha
+// which is already linear
+fn linear_half_floats(channels: Vec<u16>, channel_count: usize) -> Vec<u8> {
+    let encoded = channel_count.max(2) - 1;
+    channels
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &channel)| {
+            let value = channel as f32 / 65535.0;
+            let value = if index % channel_count < encoded {
+                srgb_to_linear(value)
+            } else {
+                value
+            };
+            f32_to_f16(value).to_ne_bytes()
+        })
+        .collect()
+}
+
+impl DecodedImage {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The format the pixels are stored in
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    /// The swizzle a view needs so that grayscale images are sampled as gray rather than red
+    pub fn swizzle(&self) -> vk::ComponentMapping {
+        self.swizzle
+    }
+
+    /// The pixels of the full size image
+    pub fn pixels(&self) -> &[u8] {
+        self.pixels.as_slice()
+    }
+
+    /// The number of mip levels that have pixels, this is one until mipmaps are generated
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_chain.len() as u32 + 1
+    }
+
+    /// The pixels of a mip level, level zero is the full size image
+    pub fn level(&self, mip_level: u32) -> &[u8] {
+        match mip_level {
+            0 => self.pixels.as_slice(),
+            level => self.mip_chain[level as usize - 1].as_slice(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage};
+
+    #[test]
+    fn test_format_selection() {
+        let gray = DecodedImage::from(DynamicImage::ImageLuma8(GrayImage::new(4, 2)));
+        assert_eq!(gray.format(), vk::Format::R8_SRGB);
+        assert_eq!(gray.swizzle().g, vk::ComponentSwizzle::R);
+        assert_eq!(gray.pixels().len(), 8);
+        let gray16: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::new(4, 2);
+        let gray16 = DecodedImage::from(DynamicImage::ImageLuma16(gray16));
+        assert_eq!(gray16.format(), vk::Format::R16_SFLOAT);
+        assert_eq!(gray16.pixels().len(), 16);
+        let rgb16: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::new(4, 2);
+        let rgb16 = DecodedImage::from(DynamicImage::ImageRgb16(rgb16));
+        assert_eq!(rgb16.format(), vk::Format::R16G16B16A16_SFLOAT);
+        assert_eq!(rgb16.pixels().len(), 64);
+    }
+
+    #[test]
+    fn test_16_bit_is_decoded_to_linear_light() {
+        let gray = ImageBuffer::from_pixel(1, 1, LumaA([32768u16, 32768]));
+        let decoded = DecodedImage::from(DynamicImage::ImageLumaA16(gray));
+        let channels: Vec<u16> = decoded
+            .pixels()
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect();
+        // Mid gray is about a fifth as bright in linear light while alpha is left as it is
+        assert_eq!(channels[0], f32_to_f16(srgb_to_linear(32768.0 / 65535.0)));
+        assert_eq!(channels[1], f32_to_f16(32768.0 / 65535.0));
+        assert_ne!(channels[0], channels[1]);
+    }
+
+    #[test]
+    fn test_rgb_is_expanded_to_rgba() {
+        let rgb = RgbImage::from_pixel(3, 1, Rgb([10, 20, 30]));
+        let decoded = DecodedImage::from(DynamicImage::ImageRgb8(rgb));
+        assert_eq!(decoded.format(), vk::Format::R8G8B8A8_SRGB);
+        assert_eq!(&decoded.pixels()[..4], &[10, 20, 30, 255]);
+        assert_eq!(decoded.pixels().len(), 12);
+    }
+
+    #[test]
+    fn test_gray_alpha_is_expanded_to_rgba() {
+        let gray = GrayAlphaImage::from_pixel(2, 1, LumaA([40, 128]));
+        let decoded = DecodedImage::from(DynamicImage::ImageLumaA8(gray));
+        assert_eq!(decoded.format(), vk::Format::R8G8B8A8_SRGB);
+        assert_eq!(decoded.swizzle().a, vk::ComponentSwizzle::IDENTITY);
+        assert_eq!(&decoded.pixels()[..4], &[40, 40, 40, 128]);
+    }
+
+    #[test]
+    fn test_decode_invalid_data() {
+        let error = decode_memory(&[0, 1, 2, 3]).err().unwrap();
+        assert_eq!(
+            error.kind(),
+            &crate::error::ErrorKind::ImageDecodeFailed(String::new())
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use super::{
+    decode_error, decode_file, decode_memory, DecodeJob, DecodeResult, DecodedImage, PendingImage,
+    TextureLoader,
+};
+use crate::error::{Error, ErrorKind};
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+impl TextureLoader {
+    /// Starts the worker thread, images are decoded one at a time in the order they were requested
+    pub fn new() -> Result<TextureLoader, Error> {
+        let (jobs, receiver) = mpsc::channel::<(DecodeJob, mpsc::Sender<DecodeResult>)>();
+        let worker = std::thread::Builder::new()
+            .name(String::from("texture-loader"))
+            .spawn(move || {
+                // The loop ends when the loader is dropped and the sender is closed
+                for (job, result) in receiver {
+                    let (decoded, generate_mipmaps) = match job {
+                        DecodeJob::File(path, mipmaps) => (decode_file(path), mipmaps),
+                        DecodeJob::Memory(data, mipmaps) => {
+                            (decode_memory(data.as_slice()), mipmaps)
+                        }
+                    };
+                    let decoded = decoded.map(|mut decoded| {
+                        if generate_mipmaps {
+                            decoded.generate_mipmaps();
+                        }
+                        decoded
+                    });
+                    // If the pending image was dropped then no one is waiting on the result
+                    let _ = result.send(decoded.map_err(|error| match error.kind() {
+                        ErrorKind::ImageDecodeFailed(message) => message.clone(),
+                        _ => error.to_string(),
+                    }));
+                }
+            })
+            .map_err(|_| Error::new(ErrorKind::InitializationFailed, None))?;
+        Ok(TextureLoader {
+            jobs: Some(jobs),
+            worker: Some(worker),
+            generate_mipmaps: false,
+        })
+    }
+
+    /// When set the worker also generates the mip chain of every image it decodes in linear light,
+    /// keeping the work off the render thread for textures that use Mipmaps::LinearLight
+    pub fn generate_mipmaps(&mut self, generate: bool) {
+        self.generate_mipmaps = generate;
+    }
+
+    /// Queues a file to be decoded
+    pub fn load_file<P: Into<PathBuf>>(&self, path: P) -> PendingImage {
+        self.queue(DecodeJob::File(path.into(), self.generate_mipmaps))
+    }
+
+    /// Queues an image that is already in memory to be decoded
+    pub fn load_memory(&self, data: Vec<u8>) -> PendingImage {
+        self.queue(DecodeJob::Memory(data, self.generate_mipmaps))
+    }
+
+    fn queue(&self, job: DecodeJob) -> PendingImage {
+        let (sender, result) = mpsc::channel();
+        if let Some(jobs) = &self.jobs {
+            // Should the worker have stopped the sender is dropped here and the pending image reports it
+            let _ = jobs.send((job, sender));
+        }
+        PendingImage { result }
+    }
+}
+
+impl Drop for TextureLoader {
+    fn drop(&mut self) {
+        // Closing the channel lets the worker finish any queued images and then exit
+        self.jobs.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl PendingImage {
+    /// Returns the decoded image if it is ready without blocking
+    pub fn try_take(&self) -> Option<Result<DecodedImage, Error>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result.map_err(decode_error)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(stopped())),
+        }
+    }
+
+    /// Blocks until the image has been decoded
+    pub fn wait(self) -> Result<DecodedImage, Error> {
+        match self.result.recv() {
+            Ok(result) => result.map_err(decode_error),
+            Err(_) => Err(stopped()),
+        }
+    }
+}
+
+fn stopped() -> Error {
+    decode_error(String::from(
+        "The texture loader stopped before the image was decoded",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loader_reports_errors() {
+        let loader = TextureLoader::new().unwrap();
+        let pending = loader.load_memory(vec![0, 1, 2, 3]);
+        let error = pending.wait().err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ImageDecodeFailed(String::new()));
+    }
+}
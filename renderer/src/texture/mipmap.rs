@@ -0,0 +1,310 @@
+use super::DecodedImage;
+use crate::mip_levels_for_extent;
+use erupt::vk1_0 as vk;
+
+// How the channels of a texture format are stored
+struct ChannelLayout {
+    channels: usize,
+    bytes_per_channel: usize,
+    // The leading channels that hold sRGB encoded colour, alpha is always stored linearly
+    encoded: usize,
+    has_alpha: bool,
+}
+
+fn channel_layout(format: vk::Format) -> ChannelLayout {
+    // Pixels read from image files are display referred, so the 8 bit formats hold encoded colour while
+    // 16 bit images are converted to linear half floats when they're decoded
+    match format {
+        vk::Format::R8_UNORM | vk::Format::R8_SRGB => ChannelLayout::new(1, 1, 1, false),
+        vk::Format::R8G8_UNORM => ChannelLayout::new(2, 1, 1, true),
+        vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => ChannelLayout::new(4, 1, 3, true),
+        vk::Format::R16_SFLOAT => ChannelLayout::new(1, 2, 0, false),
+        vk::Format::R16G16_SFLOAT => ChannelLayout::new(2, 2, 0, true),
+        vk::Format::R16G16B16A16_SFLOAT => ChannelLayout::new(4, 2, 0, true),
+        _ => ChannelLayout::new(4, 4, 0, true),
+    }
+}
+
+impl ChannelLayout {
+    // Channels of 1 byte are normalized integers, 2 bytes are half floats and 4 bytes are floats
+    fn new(channels: usize, bytes_per_channel: usize, encoded: usize, has_alpha: bool) -> Self {
+        ChannelLayout {
+            channels,
+            bytes_per_channel,
+            encoded,
+            has_alpha,
+        }
+    }
+
+    fn decode(&self, pixels: &[u8]) -> Vec<f32> {
+        pixels
+            .chunks_exact(self.bytes_per_channel)
+            .enumerate()
+            .map(|(index, bytes)| {
+                let value = match bytes.len() {
+                    1 => bytes[0] as f32 / 255.0,
+                    2 => f16_to_f32(u16::from_ne_bytes([bytes[0], bytes[1]])),
+                    _ => f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                };
+                if index % self.channels < self.encoded {
+                    srgb_to_linear(value)
+                } else {
+                    value
+                }
+            })
+            .collect()
+    }
+
+    fn encode(&self, values: &[f32]) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(values.len() * self.bytes_per_channel);
+        for (index, &value) in values.iter().enumerate() {
+            let value = if index % self.channels < self.encoded {
+                linear_to_srgb(value)
+            } else {
+                value
+            };
+            match self.bytes_per_channel {
+                1 => pixels.push((value.clamp(0.0, 1.0) * 255.0).round() as u8),
+                2 => pixels.extend_from_slice(&f32_to_f16(value).to_ne_bytes()),
+                _ => pixels.extend_from_slice(&value.to_ne_bytes()),
+            }
+        }
+        pixels
+    }
+
+    // Halves each dimension using a box filter, colour is weighted by alpha so that transparent pixels
+    // don't darken the edges of what remains visible. Only two rows are decoded at a time so that
+    // very large images don't need to be held in memory as floats
+    fn downsample(&self, pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+        let (width, height) = (width as usize, height as usize);
+        let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+        let stride = width * self.channels * self.bytes_per_channel;
+        let alpha = self.channels - 1;
+        let mut next = Vec::with_capacity(next_width * next_height * stride / width);
+        let mut next_row = Vec::with_capacity(next_width * self.channels);
+        for y in 0..next_height {
+            let upper = self.decode(&pixels[2 * y * stride..][..stride]);
+            let lower = self.decode(&pixels[(2 * y + 1).min(height - 1) * stride..][..stride]);
+            next_row.clear();
+            for x in 0..next_width {
+                let columns = [2 * x, (2 * x + 1).min(width - 1)];
+                let texels: Vec<&[f32]> = [&upper, &lower]
+                    .iter()
+                    .flat_map(|row| {
+                        columns
+                            .iter()
+                            .map(move |column| &row[column * self.channels..][..self.channels])
+                    })
+                    .collect();
+                let total_alpha: f32 = if self.has_alpha {
+                    texels.iter().map(|texel| texel[alpha]).sum()
+                } else {
+                    0.0
+                };
+                for channel in 0..self.channels {
+                    let value = if self.has_alpha && channel != alpha && total_alpha > 0.0 {
+                        texels
+                            .iter()
+                            .map(|texel| texel[channel] * texel[alpha])
+                            .sum::<f32>()
+                            / total_alpha
+                    } else {
+                        texels.iter().map(|texel| texel[channel]).sum::<f32>() / 4.0
+                    };
+                    next_row.push(value);
+                }
+            }
+            next.extend_from_slice(self.encode(next_row.as_slice()).as_slice());
+        }
+        next
+    }
+}
+
+pub(super) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+// Rounds to the nearest half float, values too large for one become infinite
+pub(super) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x007f_ffff;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // Subnormal, the implicit leading bit becomes part of the mantissa
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+    // A carry out of the mantissa correctly moves on to the next exponent
+    let round = (mantissa >> 12) & 1;
+    sign | ((((exponent as u32) << 10) | (mantissa >> 13)) + round) as u16
+}
+
+/// The size of a single pixel in one of the formats a decoded image can be stored in
+pub(super) fn bytes_per_pixel(format: vk::Format) -> usize {
+    let layout = channel_layout(format);
+    layout.channels * layout.bytes_per_channel
+}
+
+/// Halves a level in linear light, used to build a pyramid one level at a time without keeping the levels above
+pub(super) fn downsample(format: vk::Format, pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    channel_layout(format).downsample(pixels, width, height)
+}
+
+impl DecodedImage {
+    /// Generates every mip level below the full size image, the downsampling is done in linear light
+    /// so that zoomed out photos keep their brightness, does nothing if the levels already exist
+    pub fn generate_mipmaps(&mut self) {
+        let extent = vk::Extent2D {
+            width: self.width,
+            height: self.height,
+        };
+        let total_levels = mip_levels_for_extent(extent) as usize;
+        if self.mip_chain.len() + 1 >= total_levels {
+            return;
+        }
+        let layout = channel_layout(self.format);
+        let (mut width, mut height) = (self.width, self.height);
+        let mut mip_chain: Vec<Vec<u8>> = Vec::with_capacity(total_levels - 1);
+        for _ in 1..total_levels {
+            let previous = mip_chain.last().unwrap_or(&self.pixels);
+            let next = layout.downsample(previous.as_slice(), width, height);
+            mip_chain.push(next);
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+        }
+        self.mip_chain = mip_chain;
+    }
+
+    /// Converts every level to four channels of a format that more devices can sample, 8 bit images are
+    /// converted to R8G8B8A8_SRGB and anything else to one of the float formats. Grayscale is copied into
+    /// the colour channels so the image no longer needs a swizzle
+    pub(super) fn expand_to_rgba(&self, format: vk::Format) -> DecodedImage {
+        let source = channel_layout(self.format);
+        let target = channel_layout(format);
+        let expand = |pixels: &[u8]| {
+            let values: Vec<f32> = source
+                .decode(pixels)
+                .chunks_exact(source.channels)
+                .flat_map(|texel| match texel.len() {
+                    1 => [texel[0], texel[0], texel[0], 1.0],
+                    2 => [texel[0], texel[0], texel[0], texel[1]],
+                    _ => [texel[0], texel[1], texel[2], texel[3]],
+                })
+                .collect();
+            target.encode(values.as_slice())
+        };
+        DecodedImage {
+            width: self.width,
+            height: self.height,
+            format,
+            swizzle: vk::ComponentMapping::default(),
+            pixels: expand(self.pixels.as_slice()),
+            mip_chain: self
+                .mip_chain
+                .iter()
+                .map(|level| expand(level.as_slice()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{DynamicImage, ImageBuffer, LumaA, Rgba, RgbaImage};
+
+    #[test]
+    fn test_mip_chain_sizes() {
+        let image = RgbaImage::new(5, 3);
+        let mut decoded = DecodedImage::from(DynamicImage::ImageRgba8(image));
+        decoded.generate_mipmaps();
+        assert_eq!(decoded.mip_levels(), 3);
+        // 5x3 -> 2x1 -> 1x1
+        assert_eq!(decoded.level(1).len(), 2 * 4);
+        assert_eq!(decoded.level(2).len(), 4);
+    }
+
+    #[test]
+    fn test_downsample_in_linear_light() {
+        // Averaging black and white in linear light gives a mid gray that is brighter than 128 once encoded
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 255]));
+        let mut decoded = DecodedImage::from(DynamicImage::ImageRgba8(image));
+        decoded.generate_mipmaps();
+        let level = decoded.level(1);
+        assert_eq!(level[0], 188);
+        assert_eq!(level[3], 255);
+    }
+
+    #[test]
+    fn test_half_float_conversion() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        for &value in &[0.0, 0.5, 0.25, 1.0 / 3.0, 6.0e-5] {
+            let error = (f16_to_f32(f32_to_f16(value)) - value).abs();
+            assert!(
+                error <= value * 0.001,
+                "{} converted with error {}",
+                value,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_to_rgba() {
+        let gray: ImageBuffer<LumaA<u16>, Vec<u16>> =
+            ImageBuffer::from_pixel(2, 2, LumaA([65535, 32768]));
+        let mut decoded = DecodedImage::from(DynamicImage::ImageLumaA16(gray));
+        decoded.generate_mipmaps();
+        let expanded = decoded.expand_to_rgba(vk::Format::R32G32B32A32_SFLOAT);
+        assert_eq!(expanded.mip_levels(), 2);
+        assert_eq!(expanded.swizzle().g, vk::ComponentSwizzle::IDENTITY);
+        let texel: Vec<f32> = expanded.level(1)[..16]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        // White stays white in linear light and alpha is copied across
+        assert_eq!(&texel[..3], &[1.0, 1.0, 1.0]);
+        assert!((texel[3] - 0.5).abs() < 0.001);
+        let half = expanded.expand_to_rgba(vk::Format::R16G16B16A16_SFLOAT);
+        assert_eq!(half.pixels().len(), 2 * 2 * 8);
+    }
+}
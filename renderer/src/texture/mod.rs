@@ -0,0 +1,162 @@
+mod cache;
+mod decode;
+mod loader;
+mod mipmap;
+mod texture;
+mod tiled;
+
+use crate::error::Error;
+use crate::{Image, ImageView, Sampler};
+use erupt::vk1_0 as vk;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+pub use decode::{decode_file, decode_memory};
+pub use tiled::TILE_SIZE;
+
+/// Pixels decoded from an image file along with the format they should be uploaded as,
+/// the pixels are tightly packed rows that have already been rotated to match the EXIF orientation
+#[derive(Clone)]
+pub struct DecodedImage {
+    width: u32,
+    height: u32,
+    format: vk::Format,
+    // Grayscale images are stored in one or two channels and expanded back out when sampled
+    swizzle: vk::ComponentMapping,
+    pixels: Vec<u8>,
+    // The pixels of every mip level after the first, empty unless mipmaps were generated
+    mip_chain: Vec<Vec<u8>>,
+}
+
+// The decoded image or a message describing why it couldn't be decoded
+type DecodeResult = Result<DecodedImage, String>;
+
+// The work sent to the loader thread
+enum DecodeJob {
+    File(std::path::PathBuf, bool),
+    Memory(Vec<u8>, bool),
+}
+
+/// Decodes images on a worker thread so that large reference images don't stall the render loop
+pub struct TextureLoader {
+    jobs: Option<mpsc::Sender<(DecodeJob, mpsc::Sender<DecodeResult>)>>,
+    worker: Option<JoinHandle<()>>,
+    generate_mipmaps: bool,
+}
+
+/// An image that is being decoded by a TextureLoader
+pub struct PendingImage {
+    result: mpsc::Receiver<DecodeResult>,
+}
+
+/// How the levels below the full size image are produced when a texture is created
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mipmaps {
+    /// The texture only has a single level, zoomed out textures may shimmer
+    None,
+    /// Each level is blitted from the one above on the device, SRGB textures are filtered in linear light,
+    /// falls back to LinearLight when the format can't be blitted with a linear filter
+    Blit,
+    /// Levels are downsampled on the host in linear light, already generated levels are used as is
+    LinearLight,
+}
+
+/// How a texture is filtered when it is sampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFiltering {
+    /// Trilinear filtering, anisotropic when the SamplerAnisotropy feature is enabled
+    Smooth,
+    /// Every texel is drawn as a hard edged block, used when inspecting individual pixels
+    Nearest,
+}
+
+/// The settings used when uploading a decoded image as a texture
+pub struct TextureSettings {
+    mipmaps: Mipmaps,
+    filtering: TextureFiltering,
+}
+
+/// A sampled image that has been uploaded to the device along with a view covering all of it
+/// and a sampler matching its filtering
+pub struct Texture {
+    image: Image,
+    view: ImageView,
+    sampler: Sampler,
+    filtering: TextureFiltering,
+}
+
+/// Identifies a tile by its level in the pyramid and its column and row within that level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileKey {
+    pub level: u32,
+    pub column: u32,
+    pub row: u32,
+}
+
+/// A rectangle measured in pixels of the full size image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageRegion {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+// The size of a level of the pyramid in pixels and in tiles
+struct PyramidLevel {
+    width: u32,
+    height: u32,
+    columns: u32,
+    rows: u32,
+}
+
+/// An image too large to be a single texture, split into a pyramid of tiles where each level is half the size
+/// of the one below it, the top of the pyramid is a single tile
+pub struct TiledImage {
+    width: u32,
+    height: u32,
+    format: vk::Format,
+    swizzle: vk::ComponentMapping,
+    levels: Vec<PyramidLevel>,
+    // The directory the tiles are written to, removed when the image is dropped
+    spill: PathBuf,
+}
+
+/// A tile that should be drawn, the region is where it appears in the full size image and the
+/// texture coordinates select the part of the tile that covers that region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileDraw {
+    pub key: TileKey,
+    pub region: ImageRegion,
+    pub uv: ImageRegion,
+}
+
+struct ResidentTile {
+    image: Image,
+    view: ImageView,
+    size: u64,
+    last_used: u64,
+}
+
+/// Keeps the tiles of a TiledImage that are visible resident on the device, the least recently used tiles are
+/// evicted when the memory budget is exceeded, the top of the pyramid is never evicted so there is always
+/// something to draw while finer tiles are being uploaded
+pub struct TileCache {
+    resident: HashMap<TileKey, ResidentTile>,
+    // Evicted tiles are kept until the frames that may be using them have finished
+    retired: Vec<(u64, ResidentTile)>,
+    sampler: Sampler,
+    budget: u64,
+    used: u64,
+    frame: u64,
+    frames_in_flight: u64,
+    uploads_per_update: usize,
+}
+
+// Errors returned by the image crate are converted to a message so they can be sent between threads
+fn decode_error(message: String) -> Error {
+    Error::new(crate::error::ErrorKind::ImageDecodeFailed(message), None)
+}
@@ -0,0 +1,390 @@
+use super::mipmap::bytes_per_pixel;
+use super::{DecodedImage, Mipmaps, Texture, TextureFiltering, TextureSettings};
+use crate::error::Error;
+use crate::{
+    mip_levels_for_extent, Buffer, Features, Image, ImageView, OperationQueue, Sampler,
+    SamplerSettings, VulkanDevice,
+};
+use erupt::vk1_0 as vk;
+
+use std::borrow::Cow;
+
+// The highest anisotropy worth asking for, higher values cost bandwidth for little visible difference
+const PREFERRED_ANISOTROPY: f32 = 16.0;
+
+// The format to convert an image to when the device can't sample its own format, single channel 8 bit formats
+// are optional for sampling while R8G8B8A8_SRGB and R16G16B16A16_SFLOAT are always supported
+fn sampled_fallback(device: &VulkanDevice, format: vk::Format) -> Option<vk::Format> {
+    let sampled = |format| {
+        device
+            .optimal_tiling_features(format)
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE)
+    };
+    if sampled(format) {
+        return None;
+    }
+    match format {
+        vk::Format::R8_SRGB | vk::Format::R8_UNORM | vk::Format::R8G8_UNORM => {
+            Some(vk::Format::R8G8B8A8_SRGB)
+        }
+        _ if sampled(vk::Format::R16G16B16A16_SFLOAT) => Some(vk::Format::R16G16B16A16_SFLOAT),
+        _ => Some(vk::Format::R32G32B32A32_SFLOAT),
+    }
+}
+
+// Packs pixels one after another in a staging buffer, a copy needs its offset to be a multiple of 4 and of the
+// texel size and as texel sizes are powers of two the larger of the two is a multiple of both.
+// Returns the offset of each and the size of the buffer
+fn staging_layout<I>(pixels: I) -> (Vec<vk::DeviceSize>, vk::DeviceSize)
+where
+    I: Iterator<Item = (vk::Format, usize)>,
+{
+    let mut offsets = Vec::new();
+    let mut size: vk::DeviceSize = 0;
+    for (format, length) in pixels {
+        let alignment = bytes_per_pixel(format).max(4) as vk::DeviceSize;
+        let offset = size.div_ceil(alignment) * alignment;
+        offsets.push(offset);
+        size = offset + length as vk::DeviceSize;
+    }
+    (offsets, size)
+}
+
+impl TextureSettings {
+    pub fn new() -> TextureSettings {
+        TextureSettings {
+            mipmaps: Mipmaps::None,
+            filtering: TextureFiltering::Smooth,
+        }
+    }
+
+    /// How the mip chain is generated, by default the texture has a single level
+    pub fn mipmaps(&mut self, mipmaps: Mipmaps) {
+        self.mipmaps = mipmaps;
+    }
+
+    /// How the texture is filtered, by default filtering is as smooth as the device allows
+    pub fn filtering(&mut self, filtering: TextureFiltering) {
+        self.filtering = filtering;
+    }
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        TextureSettings::new()
+    }
+}
+
+/// Configures a sampler for the filtering mode using the best quality the enabled features and limits allow
+pub(crate) fn configure_sampler(
+    device: &VulkanDevice,
+    settings: &mut SamplerSettings,
+    filtering: TextureFiltering,
+) {
+    match filtering {
+        TextureFiltering::Nearest => {
+            settings.filter(vk::Filter::NEAREST, vk::Filter::NEAREST);
+            settings.mipmap_mode(vk::SamplerMipmapMode::NEAREST);
+        }
+        TextureFiltering::Smooth => {
+            settings.filter(vk::Filter::LINEAR, vk::Filter::LINEAR);
+            settings.mipmap_mode(vk::SamplerMipmapMode::LINEAR);
+            if device.feature_enabled(&Features::SamplerAnisotropy)
+                && device.limits().max_sampler_anisotropy > 1.0
+            {
+                settings.anisotropy(PREFERRED_ANISOTROPY);
+            }
+        }
+    }
+}
+
+impl Texture {
+    /// Uploads the image and creates a sampler that matches the filtering in the settings
+    pub(crate) fn upload(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        decoded: &DecodedImage,
+        settings: TextureSettings,
+    ) -> Result<Texture, Error> {
+        let (image, view) = Texture::upload_image(device, queue, decoded, settings.mipmaps)?;
+        let sampler = match device.create_sampler(|sampler| {
+            configure_sampler(device, sampler, settings.filtering);
+        }) {
+            Ok(sampler) => sampler,
+            Err(error) => {
+                view.destroy(device);
+                image.destroy(device);
+                return Err(error);
+            }
+        };
+        Ok(Texture {
+            image,
+            view,
+            sampler,
+            filtering: settings.filtering,
+        })
+    }
+
+    /// Copies the decoded pixels into a device local image through a staging buffer, waits for the copy to
+    /// finish and leaves the image ready to be sampled by fragment shaders. Pixels in a format the device
+    /// can't sample are converted to one it can
+    fn upload_image(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        decoded: &DecodedImage,
+        mipmaps: Mipmaps,
+    ) -> Result<(Image, ImageView), Error> {
+        let extent = vk::Extent2D {
+            width: decoded.width(),
+            height: decoded.height(),
+        };
+        let converted;
+        let decoded = match sampled_fallback(device, decoded.format()) {
+            Some(format) => {
+                converted = decoded.expand_to_rgba(format);
+                &converted
+            }
+            None => decoded,
+        };
+        let blit_features = vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+        let mipmaps = match mipmaps {
+            // Levels that were already generated by the loader are better than blitting
+            Mipmaps::Blit if decoded.mip_levels() > 1 => Mipmaps::LinearLight,
+            Mipmaps::Blit
+                if !device
+                    .optimal_tiling_features(decoded.format())
+                    .contains(blit_features) =>
+            {
+                Mipmaps::LinearLight
+            }
+            mipmaps => mipmaps,
+        };
+        let generated;
+        let decoded = match mipmaps {
+            Mipmaps::LinearLight if decoded.mip_levels() < mip_levels_for_extent(extent) => {
+                // Generated here on the calling thread as the loader wasn't asked to do it
+                generated = {
+                    let mut generated = decoded.clone();
+                    generated.generate_mipmaps();
+                    generated
+                };
+                &generated
+            }
+            _ => decoded,
+        };
+        let (mip_levels, uploaded_levels) = match mipmaps {
+            Mipmaps::None => (1, 1),
+            Mipmaps::Blit => (mip_levels_for_extent(extent), 1),
+            Mipmaps::LinearLight => (decoded.mip_levels(), decoded.mip_levels()),
+        };
+        let (offsets, staging_size) = staging_layout(
+            (0..uploaded_levels).map(|level| (decoded.format(), decoded.level(level).len())),
+        );
+        let staging = Buffer::new(
+            device,
+            staging_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        let mut usage = vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST;
+        if mipmaps == Mipmaps::Blit {
+            usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+        }
+        let result = device
+            .create_image()
+            .extent(extent.width, extent.height)
+            .format(decoded.format())
+            .mip_levels(mip_levels)
+            .usage(usage)
+            .build()
+            .and_then(|image| {
+                Texture::upload_from(device, queue, decoded, &staging, image, &offsets)
+            });
+        staging.destroy(device);
+        result
+    }
+
+    /// Uploads images that have a single level through one staging buffer and a single submission, used so that
+    /// each tile uploaded in a frame doesn't need its own command pool and wait
+    pub(crate) fn upload_images(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        images: &[DecodedImage],
+    ) -> Result<Vec<(Image, ImageView)>, Error> {
+        let images: Vec<Cow<DecodedImage>> = images
+            .iter()
+            .map(|decoded| match sampled_fallback(device, decoded.format()) {
+                Some(format) => Cow::Owned(decoded.expand_to_rgba(format)),
+                None => Cow::Borrowed(decoded),
+            })
+            .collect();
+        let (offsets, staging_size) = staging_layout(
+            images
+                .iter()
+                .map(|decoded| (decoded.format(), decoded.pixels().len())),
+        );
+        let staging = Buffer::new(
+            device,
+            staging_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        let mut created = Vec::with_capacity(images.len());
+        let copied = Texture::copy_images(device, queue, &images, &staging, &offsets, &mut created);
+        staging.destroy(device);
+        let mut views = Vec::with_capacity(created.len());
+        let mut result = copied;
+        if result.is_ok() {
+            for (image, decoded) in created.iter().zip(images.iter()) {
+                let swizzle = decoded.swizzle();
+                match device.create_image_view(image, |settings| {
+                    settings.swizzle(swizzle.r, swizzle.g, swizzle.b, swizzle.a)
+                }) {
+                    Ok(view) => views.push(view),
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                }
+            }
+        }
+        if let Err(error) = result {
+            for view in views {
+                view.destroy(device);
+            }
+            for image in created {
+                image.destroy(device);
+            }
+            return Err(error);
+        }
+        Ok(created.into_iter().zip(views).collect())
+    }
+
+    // Creates an image for each decoded image and copies them all from the staging buffer in one submission,
+    // the images are added to created as they're made so they can be destroyed if something fails
+    fn copy_images(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        images: &[Cow<DecodedImage>],
+        staging: &Buffer,
+        offsets: &[vk::DeviceSize],
+        created: &mut Vec<Image>,
+    ) -> Result<(), Error> {
+        for (decoded, &offset) in images.iter().zip(offsets.iter()) {
+            staging.write(device, offset, decoded.pixels())?;
+            created.push(
+                device
+                    .create_image()
+                    .extent(decoded.width(), decoded.height())
+                    .format(decoded.format())
+                    .mip_levels(1)
+                    .usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST)
+                    .build()?,
+            );
+        }
+        device.submit_and_wait(queue, |recorder| {
+            for (image, &offset) in created.iter_mut().zip(offsets.iter()) {
+                recorder.transition_image(image, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+                recorder.copy_buffer_to_mip_level(staging, offset, image, 0);
+                recorder.transition_image(image, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            }
+            Ok(())
+        })
+    }
+
+    fn upload_from(
+        device: &VulkanDevice,
+        queue: &OperationQueue,
+        decoded: &DecodedImage,
+        staging: &Buffer,
+        mut image: Image,
+        offsets: &[vk::DeviceSize],
+    ) -> Result<(Image, ImageView), Error> {
+        for (level, &offset) in offsets.iter().enumerate() {
+            if let Err(error) = staging.write(device, offset, decoded.level(level as u32)) {
+                image.destroy(device);
+                return Err(error);
+            }
+        }
+        let copied = device.submit_and_wait(queue, |recorder| {
+            recorder.transition_image(&mut image, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+            for (level, &offset) in offsets.iter().enumerate() {
+                recorder.copy_buffer_to_mip_level(staging, offset, &image, level as u32);
+            }
+            if (offsets.len() as u32) < image.mip_levels() {
+                recorder.blit_mip_chain(&mut image);
+            }
+            recorder.transition_image(&mut image, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            Ok(())
+        });
+        if let Err(error) = copied {
+            image.destroy(device);
+            return Err(error);
+        }
+        let swizzle = decoded.swizzle();
+        match device.create_image_view(&image, |settings| {
+            settings.swizzle(swizzle.r, swizzle.g, swizzle.b, swizzle.a)
+        }) {
+            Ok(view) => Ok((image, view)),
+            Err(error) => {
+                image.destroy(device);
+                Err(error)
+            }
+        }
+    }
+
+    /// Replaces the sampler, used to switch between smooth and pixel exact filtering while inspecting an image,
+    /// the previous sampler is destroyed so it must no longer be in use by the device
+    pub fn set_filtering(
+        &mut self,
+        device: &VulkanDevice,
+        filtering: TextureFiltering,
+    ) -> Result<(), Error> {
+        if filtering == self.filtering {
+            return Ok(());
+        }
+        let sampler = device.create_sampler(|sampler| {
+            configure_sampler(device, sampler, filtering);
+        })?;
+        std::mem::replace(&mut self.sampler, sampler).destroy(device);
+        self.filtering = filtering;
+        Ok(())
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn view(&self) -> &ImageView {
+        &self.view
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    pub fn filtering(&self) -> TextureFiltering {
+        self.filtering
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.image.mip_levels()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.extent().width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.extent().height
+    }
+
+    /// Destroys the sampler, view and image, the texture must no longer be in use by the device
+    pub fn destroy(self, device: &VulkanDevice) {
+        self.sampler.destroy(device);
+        self.view.destroy(device);
+        self.image.destroy(device);
+    }
+}
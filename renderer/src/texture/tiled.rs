@@ -0,0 +1,392 @@
+use super::mipmap::{bytes_per_pixel, downsample};
+use super::{DecodedImage, ImageRegion, PyramidLevel, TileDraw, TileKey, TiledImage};
+use crate::error::{Error, ErrorKind};
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The width and height of the pixels a tile covers, tiles at the right and bottom edges may be smaller
+pub const TILE_SIZE: u32 = 512;
+// Each tile has a border copied from its neighbours so that linear filtering doesn't show seams between tiles
+const TILE_BORDER: u32 = 1;
+
+// Gives every tiled image its own directory when several are open at once
+static NEXT_SPILL: AtomicUsize = AtomicUsize::new(0);
+
+fn storage_error(error: std::io::Error) -> Error {
+    Error::new(ErrorKind::TileStorageFailed(error.to_string()), None)
+}
+
+impl ImageRegion {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> ImageRegion {
+        ImageRegion {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    fn intersect(&self, other: &ImageRegion) -> Option<ImageRegion> {
+        let region = ImageRegion {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        };
+        if region.left < region.right && region.top < region.bottom {
+            Some(region)
+        } else {
+            None
+        }
+    }
+}
+
+impl TiledImage {
+    /// Builds the pyramid one level at a time, the tiles of a level are written to a temporary directory
+    /// before the next level is downsampled from it and the level is dropped, so at most two levels are in
+    /// memory at once. This can take a while for very large images so it's best done on a worker thread
+    pub fn new(source: DecodedImage) -> Result<TiledImage, Error> {
+        let spill = std::env::temp_dir().join(format!(
+            "artref-tiles-{}-{}",
+            std::process::id(),
+            NEXT_SPILL.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&spill).map_err(storage_error)?;
+        // Created before any tiles are written so that returning early removes the directory
+        let mut image = TiledImage {
+            width: source.width,
+            height: source.height,
+            format: source.format,
+            swizzle: source.swizzle,
+            levels: Vec::new(),
+            spill,
+        };
+        let (mut width, mut height) = (source.width, source.height);
+        let mut pixels = source.pixels;
+        loop {
+            image.levels.push(PyramidLevel {
+                width,
+                height,
+                columns: width.div_ceil(TILE_SIZE),
+                rows: height.div_ceil(TILE_SIZE),
+            });
+            image.write_tiles(image.levels() - 1, pixels.as_slice())?;
+            if width <= TILE_SIZE && height <= TILE_SIZE {
+                break;
+            }
+            pixels = downsample(image.format, pixels.as_slice(), width, height);
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+        }
+        Ok(image)
+    }
+
+    fn write_tiles(&self, level: u32, level_pixels: &[u8]) -> Result<(), Error> {
+        let pyramid_level = &self.levels[level as usize];
+        for row in 0..pyramid_level.rows {
+            for column in 0..pyramid_level.columns {
+                let key = TileKey { level, column, row };
+                let pixels = self.cut_tile(&key, level_pixels);
+                std::fs::write(self.tile_path(&key), pixels).map_err(storage_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn tile_path(&self, key: &TileKey) -> PathBuf {
+        self.spill
+            .join(format!("{}-{}-{}", key.level, key.column, key.row))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of levels in the pyramid, the last level is a single tile
+    pub fn levels(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// The key of the single tile at the top of the pyramid
+    pub fn top_tile(&self) -> TileKey {
+        TileKey {
+            level: self.levels() - 1,
+            column: 0,
+            row: 0,
+        }
+    }
+
+    /// Picks the level whose pixels are closest to the size they appear on screen without being smaller,
+    /// scale is the number of screen pixels that one pixel of the full size image covers
+    pub fn level_for_scale(&self, scale: f32) -> u32 {
+        if scale >= 1.0 || scale <= 0.0 {
+            return 0;
+        }
+        let level = (1.0 / scale).log2().floor() as u32;
+        level.min(self.levels() - 1)
+    }
+
+    /// The tiles at the level matching the scale that overlap the visible region of the image
+    pub fn visible_tiles(&self, visible: ImageRegion, scale: f32) -> Vec<TileKey> {
+        let level = self.level_for_scale(scale);
+        self.tiles_in_region(level, &visible)
+    }
+
+    fn tiles_in_region(&self, level: u32, region: &ImageRegion) -> Vec<TileKey> {
+        let pyramid_level = &self.levels[level as usize];
+        let (scale_x, scale_y) = self.level_scale(level);
+        let tile_width = TILE_SIZE as f32 * scale_x;
+        let tile_height = TILE_SIZE as f32 * scale_y;
+        let first_column = (region.left.max(0.0) / tile_width) as u32;
+        let first_row = (region.top.max(0.0) / tile_height) as u32;
+        let last_column = ((region.right / tile_width).ceil() as u32).min(pyramid_level.columns);
+        let last_row = ((region.bottom / tile_height).ceil() as u32).min(pyramid_level.rows);
+        let mut tiles = Vec::new();
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                tiles.push(TileKey { level, column, row });
+            }
+        }
+        tiles
+    }
+
+    // The number of full size pixels covered by one pixel of the level on each axis
+    fn level_scale(&self, level: u32) -> (f32, f32) {
+        let pyramid_level = &self.levels[level as usize];
+        (
+            self.width() as f32 / pyramid_level.width as f32,
+            self.height() as f32 / pyramid_level.height as f32,
+        )
+    }
+
+    // The pixels of the level that a tile covers, not including its border
+    fn tile_bounds(&self, key: &TileKey) -> (u32, u32, u32, u32) {
+        let pyramid_level = &self.levels[key.level as usize];
+        let left = key.column * TILE_SIZE;
+        let top = key.row * TILE_SIZE;
+        let right = (left + TILE_SIZE).min(pyramid_level.width);
+        let bottom = (top + TILE_SIZE).min(pyramid_level.height);
+        (left, top, right, bottom)
+    }
+
+    /// Where the tile appears in the full size image
+    pub fn tile_region(&self, key: &TileKey) -> ImageRegion {
+        let (left, top, right, bottom) = self.tile_bounds(key);
+        let (scale_x, scale_y) = self.level_scale(key.level);
+        ImageRegion::new(
+            left as f32 * scale_x,
+            top as f32 * scale_y,
+            right as f32 * scale_x,
+            bottom as f32 * scale_y,
+        )
+    }
+
+    // The size of a tile including its border
+    fn tile_extent(&self, key: &TileKey) -> (u32, u32) {
+        let (left, top, right, bottom) = self.tile_bounds(key);
+        (
+            right - left + 2 * TILE_BORDER,
+            bottom - top + 2 * TILE_BORDER,
+        )
+    }
+
+    // Copies the pixels of a tile along with its border, pixels past the edge of the image repeat the edge
+    fn cut_tile(&self, key: &TileKey, level_pixels: &[u8]) -> Vec<u8> {
+        let pyramid_level = &self.levels[key.level as usize];
+        let (left, top, _, _) = self.tile_bounds(key);
+        let (width, height) = self.tile_extent(key);
+        let pixel_size = bytes_per_pixel(self.format);
+        let level_stride = pyramid_level.width as usize * pixel_size;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * pixel_size);
+        for y in 0..height {
+            let source_y = (top + y)
+                .saturating_sub(TILE_BORDER)
+                .min(pyramid_level.height - 1);
+            let row = &level_pixels[source_y as usize * level_stride..][..level_stride];
+            for x in 0..width {
+                let source_x = (left + x)
+                    .saturating_sub(TILE_BORDER)
+                    .min(pyramid_level.width - 1);
+                let offset = source_x as usize * pixel_size;
+                pixels.extend_from_slice(&row[offset..offset + pixel_size]);
+            }
+        }
+        pixels
+    }
+
+    /// Reads the pixels of a tile along with its border back from disk
+    pub fn tile_pixels(&self, key: &TileKey) -> Result<DecodedImage, Error> {
+        let (width, height) = self.tile_extent(key);
+        let pixels = std::fs::read(self.tile_path(key)).map_err(storage_error)?;
+        Ok(DecodedImage {
+            width,
+            height,
+            format: self.format,
+            swizzle: self.swizzle,
+            pixels,
+            mip_chain: Vec::new(),
+        })
+    }
+
+    /// Lists what to draw so that the visible tiles appear as one seamless image, a tile that isn't resident
+    /// is replaced by the part of the closest coarser tile that is, so nothing is left empty while tiles load
+    pub fn plan_draws<F>(&self, visible: &[TileKey], is_resident: F) -> Vec<TileDraw>
+    where
+        F: Fn(&TileKey) -> bool,
+    {
+        let mut draws = Vec::new();
+        for key in visible {
+            let region = self.tile_region(key);
+            self.plan_region(key, &region, &is_resident, &mut draws);
+        }
+        draws
+    }
+
+    fn plan_region<F>(
+        &self,
+        key: &TileKey,
+        region: &ImageRegion,
+        is_resident: &F,
+        draws: &mut Vec<TileDraw>,
+    ) where
+        F: Fn(&TileKey) -> bool,
+    {
+        if is_resident(key) {
+            draws.push(TileDraw {
+                key: *key,
+                region: *region,
+                uv: self.uv_for_region(key, region),
+            });
+            return;
+        }
+        let coarser = key.level + 1;
+        if coarser >= self.levels() {
+            return;
+        }
+        // Level sizes are rounded down when halved so a tile may straddle more than one coarser tile
+        for parent in self.tiles_in_region(coarser, region) {
+            if let Some(overlap) = self.tile_region(&parent).intersect(region) {
+                self.plan_region(&parent, &overlap, is_resident, draws);
+            }
+        }
+    }
+
+    // The texture coordinates of the tile that cover a region inside it
+    fn uv_for_region(&self, key: &TileKey, region: &ImageRegion) -> ImageRegion {
+        let (left, top, right, bottom) = self.tile_bounds(key);
+        let (scale_x, scale_y) = self.level_scale(key.level);
+        let texture_width = (right - left + 2 * TILE_BORDER) as f32;
+        let texture_height = (bottom - top + 2 * TILE_BORDER) as f32;
+        let to_u = |x: f32| (x / scale_x - left as f32 + TILE_BORDER as f32) / texture_width;
+        let to_v = |y: f32| (y / scale_y - top as f32 + TILE_BORDER as f32) / texture_height;
+        ImageRegion::new(
+            to_u(region.left),
+            to_v(region.top),
+            to_u(region.right),
+            to_v(region.bottom),
+        )
+    }
+}
+
+impl Drop for TiledImage {
+    fn drop(&mut self) {
+        // Nothing can be done if the directory can't be removed, it's in the temporary directory at least
+        let _ = std::fs::remove_dir_all(&self.spill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{DynamicImage, GrayImage, Luma};
+
+    fn tiled(width: u32, height: u32) -> TiledImage {
+        let image = GrayImage::new(width, height);
+        TiledImage::new(DecodedImage::from(DynamicImage::ImageLuma8(image))).unwrap()
+    }
+
+    #[test]
+    fn test_pyramid_levels() {
+        let image = tiled(1300, 600);
+        // 1300x600 -> 650x300 -> 325x150
+        assert_eq!(image.levels(), 3);
+        assert_eq!(image.levels[0].columns, 3);
+        assert_eq!(image.levels[0].rows, 2);
+        assert_eq!(image.levels[2].columns, 1);
+        let tile = image
+            .tile_pixels(&TileKey {
+                level: 0,
+                column: 2,
+                row: 1,
+            })
+            .unwrap();
+        assert_eq!(
+            (tile.width(), tile.height()),
+            (1300 - 1024 + 2, 600 - 512 + 2)
+        );
+    }
+
+    #[test]
+    fn test_tiles_are_read_back_from_disk() {
+        let mut source = GrayImage::new(1300, 600);
+        source.put_pixel(1024, 512, Luma([200]));
+        let image = TiledImage::new(DecodedImage::from(DynamicImage::ImageLuma8(source))).unwrap();
+        let key = TileKey {
+            level: 0,
+            column: 2,
+            row: 1,
+        };
+        let tile = image.tile_pixels(&key).unwrap();
+        // The first pixel of the tile comes after its border, which is copied from the tile to the left
+        let stride = tile.width() as usize;
+        assert_eq!(tile.pixels()[stride + 1], 200);
+        assert_eq!(tile.pixels()[stride], 0);
+        let top = image.tile_pixels(&image.top_tile()).unwrap();
+        assert_eq!(top.pixels().len(), (325 + 2) * (150 + 2));
+        // Every level was written so dropping the image is all that keeps the tiles around
+        let spill = image.spill.clone();
+        assert!(image.tile_path(&key).exists());
+        drop(image);
+        assert!(!spill.exists());
+    }
+
+    #[test]
+    fn test_visible_tiles() {
+        let image = tiled(1300, 600);
+        let visible = image.visible_tiles(ImageRegion::new(600.0, 0.0, 1100.0, 100.0), 1.0);
+        assert_eq!(visible.len(), 2);
+        assert_eq!(visible[0].column, 1);
+        assert_eq!(visible[1].column, 2);
+        assert_eq!(image.level_for_scale(0.3), 1);
+        assert_eq!(image.level_for_scale(0.01), 2);
+    }
+
+    #[test]
+    fn test_missing_tiles_fall_back_to_coarser_levels() {
+        let image = tiled(1300, 600);
+        let visible = image.visible_tiles(ImageRegion::new(0.0, 0.0, 1300.0, 600.0), 1.0);
+        let top = image.top_tile();
+        let draws = image.plan_draws(&visible, |key| {
+            key.level == 0 && key.column == 0 || *key == top
+        });
+        // The resident tiles at level 0 are drawn as is and the rest of the image comes from the top tile
+        let covered: f32 = draws
+            .iter()
+            .map(|draw| {
+                (draw.region.right - draw.region.left) * (draw.region.bottom - draw.region.top)
+            })
+            .sum();
+        assert!((covered - 1300.0 * 600.0).abs() < 1.0);
+        assert!(draws
+            .iter()
+            .all(|draw| draw.key.level == 0 || draw.key == top));
+        assert!(draws
+            .iter()
+            .all(|draw| draw.uv.left >= 0.0 && draw.uv.right <= 1.0));
+    }
+}
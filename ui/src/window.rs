@@ -4,7 +4,7 @@ use super::{
 };
 use std::os::raw::c_void;
 use erupt::vk1_0::VertexInputRate;
-use renderer::AttributeFormat;
+use renderer::{Attachment, Vertex};
 use winit;
 
 
@@ -13,6 +13,14 @@ pub struct Window {
     event_loop: winit::event_loop::EventLoop<()>,
 }
 
+// The layout of each vertex in the vertex buffer
+#[derive(Vertex)]
+#[repr(C)]
+struct UiVertex {
+    position: [f32; 2],
+    colour: [f32; 3],
+}
+
 
 
 impl Window {
@@ -85,7 +93,10 @@ impl Window {
             .extensions_to_load(|mng| {
                 // Load the swapchain extension
                 mng.add_extension(DeviceExtensions::Swapchain);
-            }).create_device();
+            })
+            // Reusing compiled pipelines between runs makes startup much faster
+            .pipeline_cache_directory(std::env::temp_dir().join("ArtRef"))
+            .create_device();
         
         let presenter = configure_presenter.select_present_mode(|mng| {
                 mng.pick(PresentMode::Mailbox);
@@ -117,7 +128,9 @@ impl Window {
                     // Otherwise we can safely set it to min + 1
                     image_count.set_image_count(image_count.min_images() + 1);
                 }
-            }).create_presenter();
+            })
+            .create_presenter(&device, &device.queues().graphics_queues()[0])
+            .expect("Failed to create the swapchain");
         // Image view settings
         // - viewtype
         // - swizzle
@@ -137,9 +150,7 @@ impl Window {
             Ok(())
         }).expect("Failed to configure shaders")
         .configure_vertex_input(&mut|configure_input|{
-            configure_input.add_binding(0,erupt::vk1_0::VertexInputRate::VERTEX, 1)
-                .add_attribute(0, 0, AttributeFormat::Float)
-                .add_attribute(1, 0, AttributeFormat::Float);
+            configure_input.add_vertex::<UiVertex>(0, VertexInputRate::VERTEX);
             // configure_input.add_binding(1, VertexInputRate::INSTANCE, 8)
             //     .add_attribute(2, erupt::vk1_0::Format::R8G8B8_SRGB);
         })
@@ -148,16 +159,21 @@ impl Window {
             settings.set_restart(false);
         })
         .configure_viewport(&mut|viewports| {
-            // TODO: Support multiple viewports as well as some way to check if its available
-            viewports.create_viewport(0.0, 0.0, presenter.get_width() as f32, presenter.get_height() as f32, 0.0, 1.0, 0, 0, presenter.get_width(), presenter.get_height())
+            // Set from the swapchain extent each frame so resizing doesn't recreate the pipeline
+            viewports.dynamic(1);
         })
         .configure_rasterizer(&mut|settings| {
             
         });
         
         // TODO: This will be multiple renderpasses and should be contained in a frame graph
-        let single_pass = device.create_renderpass().create_test_renderpass(presenter.get_format());
-        //single_pass.create_test_renderpass();
+        let single_pass = device.create_renderpass()
+            .add_attachment(Attachment::presented(presenter.get_format()))
+            .add_subpass(|subpass| {
+                subpass.color(0);
+            })
+            .create()
+            .expect("Failed to create renderpass");
         
         // Read shaders from files or w/e
         // create shader modules for each shader